@@ -1,7 +1,8 @@
 use rand::{Rng, Closed01};
 use std::str::FromStr;
 use clap::{Arg, App};
-use na::{Pnt2, Pnt3, Vec2, Vec3};
+use na::{Point2, Point3, Vector2, Vector3};
+use nalgebra04::Pnt3;
 
 fn random_closed01<R: Rng>(rng: &mut R) -> f32 {
     rng.gen::<Closed01<f32>>().0
@@ -13,37 +14,38 @@ fn random_coord<R: Rng>(rng: &mut R) -> f32 {
 }
 
 pub trait MyPoint {
+    /// The point for drawing with kiss3d, which uses nalgebra 0.4.
     fn into_pnt3(self) -> Pnt3<f32>;
     fn random<R: Rng>(rng: &mut R) -> Self;
     fn random_around<R: Rng>(rng: &mut R, pt: Self, dist: f32) -> Self;
 }
 
-impl MyPoint for Pnt3<f32> {
+impl MyPoint for Point3<f32> {
     fn into_pnt3(self) -> Pnt3<f32> {
-        self
+        Pnt3::new(self.x, self.y, self.z)
     }
 
-    fn random<R: Rng>(rng: &mut R) -> Pnt3<f32> {
-        Pnt3::new(random_coord(rng), random_coord(rng), random_coord(rng))
+    fn random<R: Rng>(rng: &mut R) -> Point3<f32> {
+        Point3::new(random_coord(rng), random_coord(rng), random_coord(rng))
     }
 
     fn random_around<R: Rng>(rng: &mut R, pt: Self, dist: f32) -> Self {
-        let d = Vec3::new(random_coord(rng), random_coord(rng), random_coord(rng)) * dist;
+        let d = Vector3::new(random_coord(rng), random_coord(rng), random_coord(rng)) * dist;
         pt + d
     }
 }
 
-impl MyPoint for Pnt2<f32> {
+impl MyPoint for Point2<f32> {
     fn into_pnt3(self) -> Pnt3<f32> {
         Pnt3::new(self.x, self.y, 3.0)
     }
 
-    fn random<R: Rng>(rng: &mut R) -> Pnt2<f32> {
-        Pnt2::new(random_coord(rng), random_coord(rng))
+    fn random<R: Rng>(rng: &mut R) -> Point2<f32> {
+        Point2::new(random_coord(rng), random_coord(rng))
     }
 
     fn random_around<R: Rng>(rng: &mut R, pt: Self, dist: f32) -> Self {
-        let d = Vec2::new(random_coord(rng), random_coord(rng)) * dist;
+        let d = Vector2::new(random_coord(rng), random_coord(rng)) * dist;
         pt + d
     }
 }
@@ -1,12 +1,13 @@
 extern crate space_colonization;
 extern crate nalgebra as na;
+extern crate nalgebra04;
 extern crate rand;
-extern crate num;
 extern crate clap;
 extern crate eps_writer;
 
-use na::{Pnt2, Vec2, FloatPnt, FloatVec};
-use num::Zero;
+use na::{Point2, Vector2};
+use nalgebra04::Vec2;
+use space_colonization::position;
 use space_colonization::{SpaceColonization, SqDist};
 use eps_writer::{EpsDocument, Position, Points, Lines, SetRGB};
 use std::fs::File;
@@ -18,8 +19,8 @@ pub mod common;
 const SCALE: f32 = 400.0;
 
 fn run<T, F>(config: &Config)
-    where T: MyPoint + FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug
+    where T: MyPoint + position::Position<f32, F> + Debug,
+          F: position::Vector<f32> + Copy + Debug
 {
     let mut rng = rand::thread_rng();
 
@@ -90,5 +91,5 @@ fn main() {
 
     println!("{:?}", config);
     assert!(config.use_3d == false);
-    run::<Pnt2<f32>, Vec2<f32>>(&config);
+    run::<Point2<f32>, Vector2<f32>>(&config);
 }
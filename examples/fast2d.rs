@@ -0,0 +1,77 @@
+//! Times ```SpaceColonization2D``` against the generic ```SpaceColonization```
+//! with its spatial index on the same 2D venation setup. Both grow the same
+//! nodes.
+//!
+//! cargo run --release --example fast2d [num_attractors]
+
+extern crate space_colonization;
+extern crate nalgebra as na;
+extern crate rand;
+
+use na::{Point2, Vector2};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use space_colonization::{SpaceColonization, SpaceColonization2D, SqDist};
+use std::env;
+use std::time::Instant;
+
+const ATTRACT_DIST: f32 = 0.05;
+const CONNECT_DIST: f32 = 0.01;
+const MAX_LENGTH: u32 = 1000;
+const MAX_BRANCHES: u32 = 3;
+const MOVE_DIST: f32 = 0.005;
+const ITERATIONS: usize = 300;
+
+fn attractors(n: usize) -> Vec<[f32; 2]> {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    (0..n).map(|_| [rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0)]).collect()
+}
+
+/// Runs ```ITERATIONS``` iterations, returns the seconds and the nodes.
+fn run_generic(attractors: &[[f32; 2]]) -> (f64, usize) {
+    let mut sc: SpaceColonization<Point2<f32>, Vector2<f32>, ()> =
+        SpaceColonization::new(SqDist::from_dist(ATTRACT_DIST),
+                               SqDist::from_dist(CONNECT_DIST),
+                               MAX_LENGTH,
+                               MAX_BRANCHES,
+                               MOVE_DIST);
+    sc.set_spatial_index(Some(ATTRACT_DIST));
+    sc.add_root_node(Point2::new(0.0, 0.0));
+    for a in attractors.iter() {
+        sc.add_default_attractor(Point2::new(a[0], a[1]));
+    }
+    let start = Instant::now();
+    sc.take_steps(ITERATIONS).count();
+    (seconds(start), sc.num_nodes())
+}
+
+fn run_fast2d(attractors: &[[f32; 2]]) -> (f64, usize) {
+    let mut sc: SpaceColonization2D<()> =
+        SpaceColonization2D::new(SqDist::from_dist(ATTRACT_DIST),
+                                 SqDist::from_dist(CONNECT_DIST),
+                                 MAX_LENGTH,
+                                 MAX_BRANCHES,
+                                 MOVE_DIST);
+    sc.add_root_node([0.0, 0.0]);
+    for &a in attractors.iter() {
+        sc.add_default_attractor(a);
+    }
+    let start = Instant::now();
+    sc.by_ref().take(ITERATIONS).count();
+    (seconds(start), sc.num_nodes())
+}
+
+fn seconds(start: Instant) -> f64 {
+    let elapsed = start.elapsed();
+    elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+}
+
+fn main() {
+    let n = env::args().nth(1).and_then(|n| n.parse().ok()).unwrap_or(20000);
+    let attractors = attractors(n);
+
+    let (fast, fast_nodes) = run_fast2d(&attractors);
+    let (generic, nodes) = run_generic(&attractors);
+    println!("SpaceColonization2D: {:8.3}s {:6} nodes", fast, fast_nodes);
+    println!("SpaceColonization:   {:8.3}s {:6} nodes", generic, nodes);
+    println!("speed-up: {:.1}x", generic / fast);
+}
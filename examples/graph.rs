@@ -2,15 +2,16 @@
 
 extern crate space_colonization;
 extern crate nalgebra as na;
+extern crate nalgebra04;
 extern crate kiss3d;
 extern crate rand;
-extern crate num;
 extern crate clap;
 extern crate dot;
 
 use kiss3d::window::Window;
-use na::{Pnt2, Pnt3, Vec2, Vec3, FloatPnt, FloatVec};
-use num::Zero;
+use na::{Point2, Point3, Vector2, Vector3};
+use nalgebra04::Pnt3;
+use space_colonization::position::{Position, Vector};
 use space_colonization::{SpaceColonization, SqDist, Attractor, ConnectAction};
 use common::{MyPoint, Config};
 use std::fmt::Debug;
@@ -31,8 +32,8 @@ impl Default for Information {
 }
 
 fn run<T, F>(config: &Config)
-    where T: MyPoint + FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug
+    where T: MyPoint + Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug
 {
     let mut rng = rand::thread_rng();
 
@@ -70,8 +71,13 @@ fn run<T, F>(config: &Config)
                 //connect_action: ConnectAction::KillAttractor,
                 connect_action: ConnectAction::DisableForConnectingRoot, //{iterations:100_000},
                 active_from_iteration: 0,
+                active_until_iteration: None,
+                strength_ramp: None,
                 not_for_root: Some(root_idx),
                 not_for_connecting_root: None,
+                min_node_length: None,
+                max_node_length: None,
+                group: 0,
             });
         }
         // target nodes do not exist. but their attractor points. later we want to generate only
@@ -172,8 +178,8 @@ fn main() {
     //println!("{:?}", config);
 
     if config.use_3d {
-        run::<Pnt3<f32>, Vec3<f32>>(&config);
+        run::<Point3<f32>, Vector3<f32>>(&config);
     } else {
-        run::<Pnt2<f32>, Vec2<f32>>(&config);
+        run::<Point2<f32>, Vector2<f32>>(&config);
     }
 }
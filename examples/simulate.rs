@@ -1,13 +1,14 @@
 extern crate space_colonization;
 extern crate nalgebra as na;
+extern crate nalgebra04;
 extern crate kiss3d;
 extern crate rand;
-extern crate num;
 extern crate clap;
 
 use kiss3d::window::Window;
-use na::{Pnt2, Pnt3, Vec2, Vec3, FloatPnt, FloatVec};
-use num::Zero;
+use na::{Point2, Point3, Vector2, Vector3};
+use nalgebra04::Pnt3;
+use space_colonization::position::{Position, Vector};
 use space_colonization::{SpaceColonization, SqDist};
 use common::{MyPoint, Config};
 use std::fmt::Debug;
@@ -15,8 +16,8 @@ use std::fmt::Debug;
 pub mod common;
 
 fn run<T, F>(config: &Config)
-    where T: MyPoint + FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug
+    where T: MyPoint + Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug
 {
     let mut rng = rand::thread_rng();
 
@@ -77,8 +78,8 @@ fn main() {
     println!("{:?}", config);
 
     if config.use_3d {
-        run::<Pnt3<f32>, Vec3<f32>>(&config);
+        run::<Point3<f32>, Vector3<f32>>(&config);
     } else {
-        run::<Pnt2<f32>, Vec2<f32>>(&config);
+        run::<Point2<f32>, Vector2<f32>>(&config);
     }
 }
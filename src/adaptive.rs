@@ -0,0 +1,74 @@
+//! Shrinking the step size towards the end of a run, so that nodes don't
+//! overshoot and oscillate around the last few attractors.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, scalar};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AdaptiveStep {
+    /// Once fewer than ```below``` attractors are active, the step size is
+    /// scaled by ```active / below```, but not below ```min_factor```.
+    RemainingAttractors {
+        below: usize,
+        min_factor: f32,
+    },
+
+    /// The step size is limited to ```fraction``` times the mean distance
+    /// between the active attractors and their nearest active node, but
+    /// not below ```min_factor``` times the regular step size. Costs a pass
+    /// over all attractor/node pairs per iteration.
+    NearestDistance {
+        fraction: f32,
+        min_factor: f32,
+    },
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Adapts the step size (see ```set_move_dist```) to the remaining
+    /// attractors. ```None``` disables it.
+    pub fn set_adaptive_step(&mut self, adaptive_step: Option<AdaptiveStep>) {
+        self.adaptive_step = adaptive_step;
+    }
+
+    /// The step size for ```current_iteration```, scaled down if adaptive
+    /// steps are enabled.
+    pub(crate) fn adapted_move_dist(&self, current_iteration: u32, start_index: usize) -> S {
+        let move_dist = self.move_dist.value_at(current_iteration);
+        let active = self.attractors.iter().filter(|a| a.is_active_in(current_iteration));
+        match self.adaptive_step {
+            None => move_dist,
+            Some(AdaptiveStep::RemainingAttractors { below, min_factor }) => {
+                let remaining = active.count();
+                if remaining >= below {
+                    move_dist
+                } else {
+                    let factor = remaining as f32 / below as f32;
+                    move_dist * scalar(factor.max(min_factor))
+                }
+            }
+            Some(AdaptiveStep::NearestDistance { fraction, min_factor }) => {
+                let mut sum = S::zero();
+                let mut count = 0;
+                for a in active {
+                    let sqdist = self.sqdist_to_frontier(&a.position, start_index);
+                    if sqdist.is_finite() {
+                        sum = sum + sqdist.sqrt();
+                        count += 1;
+                    }
+                }
+                if count == 0 {
+                    return move_dist;
+                }
+                let limit = scalar::<S>(fraction) * sum / scalar(count as f32);
+                move_dist.min(limit).max(move_dist * scalar(min_factor))
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Closed venation: newly grown nodes link with nearby nodes of other
+//! branches, so that the structure contains loops, e.g. for leaf veins or
+//! street networks. Every node keeps its single parent, a link is stored as
+//! a bridge (see ```bridges```) from the new node to the node it reached.
+//! Bridges are visited by ```visit_bridge_segments``` and written by all
+//! exporters.
+
+use position::{Position, Vector, Scalar};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, IndexType, SqDist};
+use record::Event;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Anastomosis<S = f32> {
+    /// A new node closer than this to a node of another branch links with
+    /// it (with the metric or manifold, if set).
+    pub distance: S,
+
+    /// Nodes of the new node's own tree are linked with if they are more
+    /// than this many segments away along the tree. At least 1, so that a
+    /// node never links with its parent.
+    pub min_separation: u32,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Links new nodes with nearby nodes of other branches. ```None```
+    /// disables it. Costs a pass over all nodes per new node.
+    pub fn set_anastomosis(&mut self, anastomosis: Option<Anastomosis<S>>) {
+        if let Some(ref anastomosis) = anastomosis {
+            assert!(anastomosis.min_separation >= 1);
+        }
+        self.anastomosis = anastomosis;
+    }
+
+    pub fn anastomosis(&self) -> Option<Anastomosis<S>> {
+        self.anastomosis
+    }
+
+    /// Links every node from ```start_index``` on with the nearest
+    /// qualifying node before it. Each pair of new nodes is thus only
+    /// considered once.
+    pub(crate) fn link_anastomoses(&mut self, start_index: usize) {
+        let anastomosis = match self.anastomosis {
+            Some(anastomosis) => anastomosis,
+            None => return,
+        };
+        let max_dist = SqDist::from_dist(anastomosis.distance);
+        // a node which keeps being attracted to the same spot grows a child
+        // there in every iteration, which should not link again.
+        let mut linked: HashSet<(IndexType, IndexType)> =
+            self.bridges
+                .iter()
+                .map(|&(tip, target)| (self.nodes[tip.0 as usize].parent.0, target.0))
+                .collect();
+
+        for i in start_index..self.nodes.len() {
+            let tip = &self.nodes[i];
+            let mut nearest: Option<(usize, SqDist<S>)> = None;
+            for (j, node) in self.nodes[..i].iter().enumerate() {
+                let dist = self.sqdist_to_node(&tip.position, &node.position);
+                let nearer = match nearest {
+                    Some((_, d)) => dist < d,
+                    None => dist < max_dist,
+                };
+                // the walk along the tree is only done for candidates.
+                if !nearer ||
+                   (node.root == tip.root &&
+                    self.tree_distance(NodeIdx::of(i), NodeIdx::of(j)) <=
+                    anastomosis.min_separation) {
+                    continue;
+                }
+                nearest = Some((j, dist));
+            }
+
+            if let Some((j, _)) = nearest {
+                if !linked.insert((tip.parent.0, j as IndexType)) {
+                    continue;
+                }
+                let bridge = (NodeIdx::of(i), NodeIdx::of(j));
+                self.bridges.push(bridge);
+                self.record(Event::BridgeAdded {
+                    tip: bridge.0,
+                    target: bridge.1,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Generators for attractor distributions. They return positions, which
+//! are turned into attractors with the default distances of the simulation
+//! by ```extend_default_attractors```, or customized via
+//! ```default_attractor``` and added with ```extend_attractors```:
+//!
+//! ```ignore
+//! let points = attractors::scatter_in_sphere(center, 1.0, 1000, &mut rng);
+//! sc.extend_default_attractors(points);
+//! ```
+//!
+//! Attractor sets can be saved to files of their own with ```save```, to be
+//! authored once and reused across simulations:
+//!
+//! ```ignore
+//! sc.extend_attractors(attractors::load("canopy.attractors")?);
+//! ```
+//!
+//! The format is line based text like the snapshots, with one line per
+//! attractor holding all its fields, including the connect action and the
+//! schedule. With the ```serde``` feature, ```Attractor``` can also be
+//! serialized with any serde format.
+
+use position::{Position, Vector, Coords, Scalar};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use super::{SpaceColonization, Attractor, ConnectAction};
+use snapshot::{self, SnapshotError, Token, Tokens};
+
+const HEADER: &str = "space-colonization-attractors 1";
+
+/// ```n``` points uniformly distributed in the box from ```min``` to
+/// ```max```.
+pub fn scatter_in_aabb<P, R>(min: P, max: P, n: usize, rng: &mut R) -> Vec<P>
+    where P: Coords<f32>,
+          R: Rng
+{
+    let dim = P::dim();
+    (0..n)
+        .map(|_| {
+            let mut p = min;
+            for i in 0..dim {
+                p[i] = min[i] + rng.gen::<f32>() * (max[i] - min[i]);
+            }
+            p
+        })
+        .collect()
+}
+
+/// ```n``` points uniformly distributed in the ball around ```center```.
+pub fn scatter_in_sphere<P, R>(center: P, radius: f32, n: usize, rng: &mut R) -> Vec<P>
+    where P: Coords<f32>,
+          R: Rng
+{
+    let dim = P::dim();
+    let mut points = Vec::with_capacity(n);
+    while points.len() < n {
+        let offsets: Vec<f32> = (0..dim).map(|_| 2.0 * rng.gen::<f32>() - 1.0).collect();
+        if offsets.iter().map(|o| o * o).sum::<f32>() > 1.0 {
+            continue;
+        }
+        let mut p = center;
+        for i in 0..dim {
+            p[i] = center[i] + offsets[i] * radius;
+        }
+        points.push(p);
+    }
+    points
+}
+
+/// ```n``` points uniformly distributed over the area of the triangle
+/// mesh with the given ```vertices``` and ```triangles```.
+pub fn scatter_on_surface<P, F, R>(vertices: &[P],
+                                   triangles: &[[usize; 3]],
+                                   n: usize,
+                                   rng: &mut R)
+                                   -> Vec<P>
+    where P: Position<f32, F>,
+          F: Vector<f32>,
+          R: Rng
+{
+    // cumulative areas, to pick the triangles by area.
+    let mut total = 0.0;
+    let cumulative: Vec<f32> = triangles.iter()
+                                        .map(|t| {
+                                            let a = vertices[t[1]] - vertices[t[0]];
+                                            let b = vertices[t[2]] - vertices[t[0]];
+                                            let d = a.sqnorm() * b.sqnorm() - a.dot(&b) * a.dot(&b);
+                                            total += 0.5 * d.max(0.0).sqrt();
+                                            total
+                                        })
+                                        .collect();
+    if triangles.is_empty() || total <= 0.0 {
+        return Vec::new();
+    }
+
+    (0..n)
+        .map(|_| {
+            let x = rng.gen::<f32>() * total;
+            let k = match cumulative.iter().position(|&c| x < c) {
+                Some(k) => k,
+                None => triangles.len() - 1,
+            };
+            let t = triangles[k];
+            let (r1, r2) = (rng.gen::<f32>().sqrt(), rng.gen::<f32>());
+            let p0 = vertices[t[0]];
+            p0 + (vertices[t[1]] - p0) * (r1 * (1.0 - r2)) + (vertices[t[2]] - p0) * (r1 * r2)
+        })
+        .collect()
+}
+
+/// Points at least ```spacing``` apart which fill the region where ```sdf```
+/// is not positive, within the box from ```min``` to ```max``` (Bridson's
+/// algorithm). Parts of the region which are not connected are seeded by
+/// random samples, so small islands may stay empty.
+pub fn poisson_disk_in_region<P, S, R>(sdf: S,
+                                       min: P,
+                                       max: P,
+                                       spacing: f32,
+                                       rng: &mut R)
+                                       -> Vec<P>
+    where P: Coords<f32>,
+          S: Fn(&P) -> f32,
+          R: Rng
+{
+    const CANDIDATES: usize = 30;
+    assert!(spacing > 0.0);
+    let dim = P::dim();
+    let cell_size = spacing / (dim as f32).sqrt();
+    let reach = (dim as f32).sqrt().ceil() as i64;
+    let cell = |p: &P| -> Vec<i64> {
+        (0..dim).map(|i| (p[i] / cell_size).floor() as i64).collect()
+    };
+    let inside = |p: &P| (0..dim).all(|i| p[i] >= min[i] && p[i] <= max[i]) && sdf(p) <= 0.0;
+
+    let mut points: Vec<P> = Vec::new();
+    let mut grid: HashMap<Vec<i64>, usize> = HashMap::new();
+    // whether ```p``` keeps the spacing to the points so far.
+    let fits = |p: &P, points: &[P], grid: &HashMap<Vec<i64>, usize>| {
+        let c = cell(p);
+        let mut neighbor = vec![-reach; dim];
+        'cells: loop {
+            let key: Vec<i64> = c.iter().zip(neighbor.iter()).map(|(a, b)| a + b).collect();
+            if let Some(&j) = grid.get(&key) {
+                let q = points[j];
+                let d2 = (0..dim).map(|i| (p[i] - q[i]) * (p[i] - q[i])).sum::<f32>();
+                if d2 < spacing * spacing {
+                    return false;
+                }
+            }
+            for n in neighbor.iter_mut() {
+                if *n < reach {
+                    *n += 1;
+                    continue 'cells;
+                }
+                *n = -reach;
+            }
+            return true;
+        }
+    };
+
+    let mut active: Vec<usize> = Vec::new();
+    let mut seeds = 0;
+    loop {
+        let candidate = if active.is_empty() {
+            if seeds >= CANDIDATES {
+                break;
+            }
+            seeds += 1;
+            let p = scatter_in_aabb(min, max, 1, rng)[0];
+            if inside(&p) && fits(&p, &points, &grid) {
+                Some(p)
+            } else {
+                continue;
+            }
+        } else {
+            let k = (rng.gen::<f32>() * active.len() as f32) as usize % active.len();
+            let center = points[active[k]];
+            let mut found = None;
+            for _ in 0..CANDIDATES {
+                // a random offset between ```spacing``` and twice that.
+                let offsets: Vec<f32> = (0..dim).map(|_| 4.0 * rng.gen::<f32>() - 2.0).collect();
+                let len2 = offsets.iter().map(|o| o * o).sum::<f32>();
+                if !(1.0..=4.0).contains(&len2) {
+                    continue;
+                }
+                let mut p = center;
+                for i in 0..dim {
+                    p[i] = center[i] + offsets[i] * spacing;
+                }
+                if inside(&p) && fits(&p, &points, &grid) {
+                    found = Some(p);
+                    break;
+                }
+            }
+            if found.is_none() {
+                active.swap_remove(k);
+            }
+            found
+        };
+
+        if let Some(p) = candidate {
+            grid.insert(cell(&p), points.len());
+            active.push(points.len());
+            points.push(p);
+        }
+    }
+    points
+}
+
+/// Writes ```attractors``` to the file at ```path```.
+pub fn save<P, I, S, A>(attractors: &[Attractor<P, I, S>], path: A) -> Result<(), SnapshotError>
+    where P: Coords<S>,
+          I: Copy + Token,
+          S: Scalar + FromStr,
+          A: AsRef<Path>
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_attractors(&mut writer, attractors)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the attractors saved with ```save```.
+pub fn load<P, I, S, A>(path: A) -> Result<Vec<Attractor<P, I, S>>, SnapshotError>
+    where P: Coords<S>,
+          I: Copy + Token,
+          S: Scalar + FromStr,
+          A: AsRef<Path>
+{
+    read_attractors(BufReader::new(File::open(path)?))
+}
+
+pub fn write_attractors<W, P, I, S>(w: &mut W,
+                                    attractors: &[Attractor<P, I, S>])
+                                    -> Result<(), SnapshotError>
+    where W: Write,
+          P: Coords<S>,
+          I: Copy + Token,
+          S: Scalar
+{
+    let dim = P::dim();
+    writeln!(w, "{}", HEADER)?;
+    writeln!(w, "dim {}", dim)?;
+    for a in attractors.iter() {
+        write!(w, "attractor")?;
+        snapshot::write_attractor(w, a, dim)?;
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+pub fn read_attractors<R, P, I, S>(r: R) -> Result<Vec<Attractor<P, I, S>>, SnapshotError>
+    where R: BufRead,
+          P: Coords<S>,
+          I: Copy + Token,
+          S: Scalar + FromStr
+{
+    let dim = P::dim();
+    let mut attractors = Vec::new();
+    for (n, line) in r.lines().enumerate() {
+        let line = line?;
+        let mut t = Tokens {
+            line: n + 1,
+            iter: line.split_whitespace(),
+        };
+        if n == 0 {
+            if line.trim() != HEADER {
+                return t.error("not an attractor set");
+            }
+            continue;
+        }
+        match t.iter.next() {
+            Some("dim") => {
+                let set_dim: usize = t.parse()?;
+                if set_dim != dim {
+                    return t.error("dimension mismatch");
+                }
+            }
+            // the roots refer to the simulation the set is used with.
+            Some("attractor") => attractors.push(t.attractor(dim, usize::MAX)?),
+            Some(_) => return t.error("unknown keyword"),
+            None => {}
+        }
+    }
+    Ok(attractors)
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// An attractor at ```position``` with the default distances, as added
+    /// by ```add_default_attractor```.
+    pub fn default_attractor(&self, position: P) -> Attractor<P, I, S> {
+        Attractor {
+            attract_dist: self.default_attract_dist,
+            connect_dist: self.default_connect_dist,
+            strength: 1.0,
+            position,
+            information: I::default(),
+            connect_action: ConnectAction::KillAttractor,
+            active_from_iteration: 0,
+            active_until_iteration: None,
+            strength_ramp: None,
+            not_for_root: None,
+            not_for_connecting_root: None,
+            min_node_length: None,
+            max_node_length: None,
+            group: 0,
+        }
+    }
+
+    pub fn extend_attractors<T>(&mut self, attractors: T)
+        where T: IntoIterator<Item = Attractor<P, I, S>>
+    {
+        let attractors = attractors.into_iter();
+        let (lower, _) = attractors.size_hint();
+        self.reserve_attractors(lower);
+        for attractor in attractors {
+            self.add_attractor(attractor);
+        }
+    }
+
+    /// Adds a default attractor at each of ```positions```.
+    pub fn extend_default_attractors<T>(&mut self, positions: T)
+        where T: IntoIterator<Item = P>
+    {
+        let positions = positions.into_iter();
+        let (lower, _) = positions.size_hint();
+        self.reserve_attractors(lower);
+        for position in positions {
+            self.add_default_attractor(position);
+        }
+    }
+
+    /// Adds a default attractor at each of ```positions```, e.g. a point
+    /// cloud loaded in one piece.
+    pub fn add_default_attractors_from(&mut self, positions: &[P]) {
+        self.extend_default_attractors(positions.iter().cloned());
+    }
+
+    /// Makes room for ```additional``` more attractors, so that adding
+    /// them one by one does not reallocate.
+    pub fn reserve_attractors(&mut self, additional: usize) {
+        self.attractors.reserve(additional);
+        self.attractor_meta.reserve(additional);
+    }
+
+    pub fn attractors(&self) -> &[Attractor<P, I, S>] {
+        &self.attractors
+    }
+
+    /// The attractors, e.g. to change their distances or schedules between
+    /// phases. Changes made through the slice are neither recorded (see
+    /// ```start_recording```) nor rewound (see ```rewind```).
+    pub fn attractors_mut(&mut self) -> &mut [Attractor<P, I, S>] {
+        &mut self.attractors
+    }
+
+    /// Removes every attractor for which ```predicate``` returns true. They
+    /// count as expired in the statistics, and the last attractors move
+    /// into their places. Returns the number of removed attractors.
+    pub fn remove_attractors_where<C>(&mut self, mut predicate: C) -> usize
+        where C: FnMut(&Attractor<P, I, S>) -> bool
+    {
+        let mut removed = 0;
+        // backwards, so that the attractors moving into the gaps have been
+        // checked already.
+        for idx in (0..self.attractors.len()).rev() {
+            if predicate(&self.attractors[idx]) {
+                self.remove_attractor(idx, false);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// The number of attractors which take part in the next iteration or
+    /// a later one, i.e. which are not expired.
+    pub fn remaining_attractors(&self) -> usize {
+        let next_iteration = self.next_iteration;
+        self.attractors.iter().filter(|ap| !ap.is_expired_in(next_iteration)).count()
+    }
+
+    /// Makes every attractor available to all trees from the next
+    /// iteration on: clears ```not_for_connecting_root``` and moves any
+    /// later ```active_from_iteration``` (e.g. from
+    /// ```ConnectAction::DisableFor```) to the next iteration. Expired
+    /// attractors stay expired.
+    pub fn reactivate_all(&mut self) {
+        let next_iteration = self.next_iteration;
+        for idx in 0..self.attractors.len() {
+            let ap = self.attractors[idx];
+            if ap.not_for_connecting_root.is_none() && ap.active_from_iteration <= next_iteration {
+                continue;
+            }
+            self.note_attractor_state(idx);
+            let ap = &mut self.attractors[idx];
+            ap.not_for_connecting_root = None;
+            if ap.active_from_iteration > next_iteration {
+                ap.active_from_iteration = next_iteration;
+            }
+        }
+        self.record_full_state();
+    }
+}
@@ -0,0 +1,113 @@
+//! Custom per-segment attributes, e.g. widths, materials or stress. A
+//! segment is identified by its child node, so every attribute holds one
+//! value per node (the values of the roots are unused). The values grow with
+//! the structure and follow the nodes when they are pruned or subdivided,
+//! so there is no need to keep parallel ```Vec```s in sync.
+//!
+//! Attributes are not part of snapshots or recordings.
+
+use position::{Position, Vector, Scalar};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use super::SpaceColonization;
+
+trait Column {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn push_default(&mut self);
+
+    /// Replaces the values by ```values[sources[i]]``` for every ```i```.
+    fn remap(&mut self, sources: &[usize]);
+}
+
+struct TypedColumn<T> {
+    default: T,
+    values: Vec<T>,
+}
+
+impl<T: Clone + 'static> Column for TypedColumn<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn push_default(&mut self) {
+        self.values.push(self.default.clone());
+    }
+
+    fn remap(&mut self, sources: &[usize]) {
+        self.values = sources.iter().map(|&i| self.values[i].clone()).collect();
+    }
+}
+
+/// The attribute columns of a ```SpaceColonization```, by name.
+#[derive(Default)]
+pub(crate) struct SegmentAttributes {
+    columns: BTreeMap<String, Box<dyn Column>>,
+}
+
+impl SegmentAttributes {
+    /// Called for every node added.
+    pub(crate) fn push_default(&mut self) {
+        for column in self.columns.values_mut() {
+            column.push_default();
+        }
+    }
+
+    /// Called after the nodes were rebuilt: the value of the new node ```i```
+    /// is taken from the old node ```sources[i]```. Values pushed during the
+    /// rebuild are dropped.
+    pub(crate) fn remap(&mut self, sources: &[usize]) {
+        for column in self.columns.values_mut() {
+            column.remap(sources);
+        }
+    }
+
+    fn typed<T: 'static>(&self, name: &str) -> Option<&TypedColumn<T>> {
+        self.columns.get(name).and_then(|column| column.as_any().downcast_ref())
+    }
+
+    fn typed_mut<T: 'static>(&mut self, name: &str) -> Option<&mut TypedColumn<T>> {
+        self.columns.get_mut(name).and_then(|column| column.as_any_mut().downcast_mut())
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Adds the attribute ```name```, set to ```default``` for all existing
+    /// and future nodes. Replaces an existing attribute of the same name.
+    pub fn add_segment_attribute<T: Clone + 'static>(&mut self, name: &str, default: T) {
+        let column = TypedColumn {
+            values: vec![default.clone(); self.nodes.len()],
+            default,
+        };
+        self.segment_attributes.columns.insert(name.to_string(), Box::new(column));
+    }
+
+    /// Returns false if there is no attribute ```name```.
+    pub fn remove_segment_attribute(&mut self, name: &str) -> bool {
+        self.segment_attributes.columns.remove(name).is_some()
+    }
+
+    pub fn segment_attribute_names(&self) -> Vec<&str> {
+        self.segment_attributes.columns.keys().map(|name| &name[..]).collect()
+    }
+
+    /// The values of the attribute ```name```, indexed by ```NodeIdx```.
+    /// ```None``` if there is no such attribute or it has another type.
+    pub fn segment_attribute<T: 'static>(&self, name: &str) -> Option<&[T]> {
+        self.segment_attributes.typed(name).map(|column| &column.values[..])
+    }
+
+    pub fn segment_attribute_mut<T: 'static>(&mut self, name: &str) -> Option<&mut [T]> {
+        self.segment_attributes.typed_mut(name).map(|column| &mut column.values[..])
+    }
+}
@@ -0,0 +1,164 @@
+//! Running many independent simulations in parallel.
+
+use position::{Position, Vector};
+use std::cmp;
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::SpaceColonization;
+use steps::DoneReason;
+
+/// Limits applied to every single run, on top of the ```DoneConditions```
+/// the run was set up with.
+#[derive(Debug, Copy, Clone)]
+pub struct Budget {
+    pub max_iterations: usize,
+    pub max_nodes: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct Output<O> {
+    /// Index of the run, i.e. into ```configs``` and ```seeds```.
+    pub run: usize,
+    pub seed: u64,
+    pub iterations: usize,
+    pub stop_reason: DoneReason,
+    pub result: O,
+}
+
+#[derive(Debug)]
+pub enum BatchError {
+    /// The run panicked, either in the user callbacks or the simulation.
+    Panicked(String),
+}
+
+fn run_one<C, S, X, O, P, F, I>(config: &C,
+                                seed: u64,
+                                budget: &Budget,
+                                setup: &S,
+                                extract: &X)
+                                -> (usize, DoneReason, O)
+    where S: Fn(&C, u64) -> SpaceColonization<P, F, I>,
+          X: Fn(&C, SpaceColonization<P, F, I>) -> O,
+          P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    let start = Instant::now();
+    let mut sc = setup(config, seed);
+    let first_iteration = sc.next_iteration;
+
+    // the budget tightens the run's own conditions, which are restored
+    // before the simulation is handed to ```extract```.
+    let own_conditions = sc.done_conditions();
+    let mut conditions = own_conditions;
+    let max_iterations = first_iteration
+        .saturating_add(cmp::min(budget.max_iterations, u32::MAX as usize) as u32);
+    conditions.max_iterations = Some(match conditions.max_iterations {
+        Some(own) => cmp::min(own, max_iterations),
+        None => max_iterations,
+    });
+    if let Some(max_nodes) = budget.max_nodes {
+        conditions.node_budget = Some(match conditions.node_budget {
+            Some(own) => cmp::min(own, max_nodes),
+            None => max_nodes,
+        });
+    }
+    sc.set_done_conditions(conditions);
+
+    let stop_reason = loop {
+        if let Some(reason) = sc.done_reason() {
+            break reason;
+        }
+        if let Some(max_duration) = budget.max_duration {
+            if start.elapsed() >= max_duration {
+                break DoneReason::MaxDuration;
+            }
+        }
+        if sc.try_step().is_err() {
+            break DoneReason::NodeLimit;
+        }
+    };
+
+    let iterations = (sc.next_iteration - first_iteration) as usize;
+    sc.set_done_conditions(own_conditions);
+    (iterations, stop_reason, extract(config, sc))
+}
+
+/// Runs one simulation per pair of ```configs[i]``` and ```seeds[i]``` on
+/// a pool of ```num_threads``` threads (0 means one per available CPU).
+///
+/// ```setup``` creates the simulation from a config and seed, and
+/// ```extract``` turns the finished simulation into the result that is
+/// sent back. Results are returned in the order of ```configs```.
+pub fn run_all<C, S, X, O, P, F, I>(configs: &[C],
+                                    seeds: &[u64],
+                                    budget: Budget,
+                                    num_threads: usize,
+                                    setup: S,
+                                    extract: X)
+                                    -> Vec<Result<Output<O>, BatchError>>
+    where C: Sync,
+          S: Fn(&C, u64) -> SpaceColonization<P, F, I> + Sync,
+          X: Fn(&C, SpaceColonization<P, F, I>) -> O + Sync,
+          O: Send,
+          P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    assert!(configs.len() == seeds.len());
+
+    let num_threads = if num_threads == 0 {
+        thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    } else {
+        num_threads
+    };
+
+    let next_run = AtomicUsize::new(0);
+    let results = Mutex::new((0..configs.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..num_threads {
+            scope.spawn(|| {
+                loop {
+                    let run = next_run.fetch_add(1, Ordering::SeqCst);
+                    if run >= configs.len() {
+                        break;
+                    }
+                    let seed = seeds[run];
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                        run_one(&configs[run], seed, &budget, &setup, &extract)
+                    }));
+                    let result = match outcome {
+                        Ok((iterations, stop_reason, result)) => {
+                            Ok(Output {
+                                run,
+                                seed,
+                                iterations,
+                                stop_reason,
+                                result,
+                            })
+                        }
+                        Err(payload) => {
+                            let msg = if let Some(s) = payload.downcast_ref::<&str>() {
+                                s.to_string()
+                            } else if let Some(s) = payload.downcast_ref::<String>() {
+                                s.clone()
+                            } else {
+                                "unknown panic".to_string()
+                            };
+                            Err(BatchError::Panicked(msg))
+                        }
+                    };
+                    results.lock().unwrap()[run] = Some(result);
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap().into_iter().map(|r| r.unwrap()).collect()
+}
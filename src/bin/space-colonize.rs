@@ -0,0 +1,234 @@
+//! Generates a structure from a scene description and writes it to a file.
+//!
+//! ```text
+//! space-colonize <scene.toml|scene.json> <output.svg|output.obj|output.json|output.ply>
+//! ```
+//!
+//! A scene in TOML looks like this (JSON uses the same structure):
+//!
+//! ```toml
+//! dimensions = 3           # 2 or 3
+//! profile = "small-tree"   # optional, see Profile::from_name
+//! attract_dist = 0.25      # optional, overrides the profile
+//! connect_dist = 0.1       # optional
+//! move_dist = 0.05         # optional
+//! max_length = 100         # optional
+//! max_branches = 10        # optional
+//! max_iterations = 500
+//! seed = 1
+//!
+//! [[roots]]
+//! position = [0.0, -1.0, 0.0]
+//! material = 1             # optional, tags the tree in the output
+//! color = [120, 80, 40]    # optional, RGB
+//!
+//! [[attractors]]
+//! sampler = "box"
+//! min = [-1.0, -0.5, -1.0]
+//! max = [1.0, 1.0, 1.0]
+//! count = 1000
+//!
+//! [[attractors]]
+//! sampler = "sphere"
+//! center = [0.0, 1.5, 0.0]
+//! radius = 0.5
+//! count = 300
+//!
+//! [[attractors]]
+//! sampler = "points"
+//! points = [[0.0, 2.0, 0.0], [0.5, 2.0, 0.0]]
+//! ```
+
+extern crate space_colonization;
+extern crate nalgebra as na;
+extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+
+use na::{Point2, Point3, Vector2, Vector3};
+use rand::{Rng, SeedableRng, StdRng};
+use space_colonization::attractors;
+use space_colonization::position::{Coords, Position, Vector};
+use space_colonization::export::{Format, Material};
+use space_colonization::{SpaceColonization, SpaceColonizationBuilder, Profile, SqDist};
+use std::env;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Read};
+use std::path::Path;
+use std::process;
+
+#[derive(Debug, Deserialize)]
+struct Root {
+    position: Vec<f32>,
+    material: Option<u32>,
+    color: Option<[u8; 3]>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "sampler", rename_all = "lowercase")]
+enum Sampler {
+    Box {
+        min: Vec<f32>,
+        max: Vec<f32>,
+        count: usize,
+    },
+    Sphere {
+        center: Vec<f32>,
+        radius: f32,
+        count: usize,
+    },
+    Points {
+        points: Vec<Vec<f32>>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct Scene {
+    #[serde(default = "default_dimensions")]
+    dimensions: usize,
+    profile: Option<String>,
+    attract_dist: Option<f32>,
+    connect_dist: Option<f32>,
+    move_dist: Option<f32>,
+    max_length: Option<u32>,
+    max_branches: Option<u32>,
+    #[serde(default = "default_max_iterations")]
+    max_iterations: u32,
+    #[serde(default = "default_seed")]
+    seed: usize,
+    roots: Vec<Root>,
+    attractors: Vec<Sampler>,
+}
+
+fn default_dimensions() -> usize {
+    3
+}
+
+fn default_max_iterations() -> u32 {
+    500
+}
+
+fn default_seed() -> usize {
+    1
+}
+
+fn point<P>(coords: &[f32]) -> Result<P, String>
+    where P: Coords<f32>
+{
+    let dim = P::dim();
+    if coords.len() != dim {
+        return Err(format!("expected {} coordinates, got {:?}", dim, coords));
+    }
+    let mut p = P::origin();
+    for i in 0..dim {
+        p[i] = coords[i];
+    }
+    Ok(p)
+}
+
+fn sample<P, R>(sampler: &Sampler, rng: &mut R) -> Result<Vec<P>, String>
+    where P: Coords<f32>,
+          R: Rng
+{
+    match *sampler {
+        Sampler::Box { ref min, ref max, count } => {
+            Ok(attractors::scatter_in_aabb(point(min)?, point(max)?, count, rng))
+        }
+        Sampler::Sphere { ref center, radius, count } => {
+            Ok(attractors::scatter_in_sphere(point(center)?, radius, count, rng))
+        }
+        Sampler::Points { ref points } => points.iter().map(|c| point(c)).collect(),
+    }
+}
+
+fn run<P, F>(scene: &Scene, output: &Path, format: Format) -> Result<(), String>
+    where P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug
+{
+    let mut builder = match scene.profile {
+        Some(ref name) => {
+            Profile::from_name(name).ok_or_else(|| format!("unknown profile {}", name))?.builder()
+        }
+        None => SpaceColonizationBuilder::new(),
+    };
+    if let Some(d) = scene.attract_dist {
+        builder = builder.attract_dist(SqDist::from_dist(d));
+    }
+    if let Some(d) = scene.connect_dist {
+        builder = builder.connect_dist(SqDist::from_dist(d));
+    }
+    if let Some(d) = scene.move_dist {
+        builder = builder.move_dist(d);
+    }
+    if let Some(n) = scene.max_length {
+        builder = builder.max_length(n);
+    }
+    if let Some(n) = scene.max_branches {
+        builder = builder.max_branches(n);
+    }
+
+    let mut sc: SpaceColonization<P, F, ()> = builder.build();
+    let mut rng: StdRng = SeedableRng::from_seed(&[scene.seed][..]);
+    for (i, root) in scene.roots.iter().enumerate() {
+        let position = point(&root.position)?;
+        if root.material.is_some() || root.color.is_some() {
+            let material = Material::new(root.material.unwrap_or(i as u32),
+                                         root.color.unwrap_or([0, 0, 0]));
+            sc.add_root_node_with_material(position, material);
+        } else {
+            sc.add_root_node(position);
+        }
+    }
+    for sampler in scene.attractors.iter() {
+        sc.add_default_attractors_from(&sample::<P, _>(sampler, &mut rng)?);
+    }
+
+    sc.run_until_done(scene.max_iterations);
+
+    let file = File::create(output).map_err(|e| format!("{}: {}", output.display(), e))?;
+    sc.export(&mut BufWriter::new(file), format)
+      .map_err(|e| format!("{}: {}", output.display(), e))
+}
+
+fn load_scene(path: &Path) -> Result<Scene, String> {
+    let mut content = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let scene = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        _ => toml::from_str(&content).map_err(|e| e.to_string()),
+    };
+    scene.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: space-colonize <scene.toml|scene.json> \
+                   <output.svg|output.obj|output.json|output.ply>");
+        process::exit(1);
+    }
+    let (scene_path, output) = (Path::new(&args[1]), Path::new(&args[2]));
+
+    let result = load_scene(scene_path).and_then(|scene| {
+        let format = output.extension()
+                           .and_then(|e| e.to_str())
+                           .and_then(Format::from_extension)
+                           .ok_or_else(|| format!("unknown output format {}", output.display()))?;
+        match scene.dimensions {
+            2 => run::<Point2<f32>, Vector2<f32>>(&scene, output, format),
+            3 => run::<Point3<f32>, Vector3<f32>>(&scene, output, format),
+            n => Err(format!("unsupported number of dimensions {}", n)),
+        }
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
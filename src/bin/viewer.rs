@@ -0,0 +1,175 @@
+//! Runs a simulation configured by a file and shows it live. Drag with the
+//! mouse to orbit the camera, scroll to zoom.
+//!
+//! The config file contains ```key = value``` lines, ```#``` starts a
+//! comment. Keys (with defaults):
+//!
+//! ```text
+//! profile = small-tree   # optional, see Profile::from_name
+//! influence_radius = 0.25
+//! kill_distance = 0.1
+//! move_distance = 0.05
+//! max_length = 100
+//! max_branches = 10
+//! num_attractors = 1000
+//! num_roots = 1
+//! use_3d = true
+//! seed = 1
+//! ```
+//!
+//! Explicit distances override those of the profile.
+
+extern crate space_colonization;
+extern crate nalgebra as na;
+extern crate nalgebra04;
+extern crate kiss3d;
+extern crate rand;
+
+use kiss3d::window::Window;
+use na::{Point3, Vector3};
+use rand::{Rng, SeedableRng, StdRng};
+use space_colonization::{SpaceColonization, SpaceColonizationBuilder, Profile, SqDist};
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+use std::str::FromStr;
+
+struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    fn load(path: &str) -> Result<Config, String> {
+        let mut content = String::new();
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut content))
+            .map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut values = HashMap::new();
+        for (n, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => {
+                    values.insert(key.trim().to_string(), value.trim().to_string());
+                }
+                _ => return Err(format!("{}:{}: expected key = value", path, n + 1)),
+            }
+        }
+        Ok(Config { values: values })
+    }
+
+    fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.values.get(key) {
+            None => Ok(None),
+            Some(value) => {
+                value.parse()
+                     .map(Some)
+                     .map_err(|_| format!("invalid value for {}: {}", key, value))
+            }
+        }
+    }
+
+    fn get_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        self.get(key).map(|v| v.unwrap_or(default))
+    }
+}
+
+fn random_point<R: Rng>(rng: &mut R, use_3d: bool) -> Point3<f32> {
+    let mut coord = || 2.0 * rng.gen::<f32>() - 1.0;
+    let x = coord();
+    let y = coord();
+    let z = if use_3d { coord() } else { 0.0 };
+    Point3::new(x, y, z)
+}
+
+/// kiss3d still uses the math types of nalgebra 0.4.
+fn kiss3d_point(p: &Point3<f32>) -> nalgebra04::Pnt3<f32> {
+    nalgebra04::Pnt3::new(p.x, p.y, p.z)
+}
+
+fn setup(config: &Config) -> Result<SpaceColonization<Point3<f32>, Vector3<f32>, ()>, String> {
+    let profile = match config.get::<String>("profile")? {
+        Some(name) => {
+            Some(Profile::from_name(&name).ok_or_else(|| format!("unknown profile {}", name))?)
+        }
+        None => None,
+    };
+    let mut builder = match profile {
+        Some(profile) => profile.builder(),
+        None => SpaceColonizationBuilder::new(),
+    };
+    if let Some(d) = config.get("influence_radius")? {
+        builder = builder.attract_dist(SqDist::from_dist(d));
+    }
+    if let Some(d) = config.get("kill_distance")? {
+        builder = builder.connect_dist(SqDist::from_dist(d));
+    }
+    if let Some(d) = config.get::<f32>("move_distance")? {
+        builder = builder.move_dist(d);
+    }
+    if let Some(n) = config.get("max_length")? {
+        builder = builder.max_length(n);
+    }
+    if let Some(n) = config.get("max_branches")? {
+        builder = builder.max_branches(n);
+    }
+
+    let default_attractors = profile.map(|p| p.num_attractors()).unwrap_or(1000);
+    let num_attractors = config.get_or("num_attractors", default_attractors)?;
+    let num_roots = config.get_or("num_roots", 1usize)?;
+    let use_3d = config.get_or("use_3d", true)?;
+    let seed = config.get_or("seed", 1usize)?;
+
+    let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+    let mut sc = builder.build();
+    for _ in 0..num_roots {
+        sc.add_root_node(random_point(&mut rng, use_3d));
+    }
+    for _ in 0..num_attractors {
+        sc.add_default_attractor(random_point(&mut rng, use_3d));
+    }
+    Ok(sc)
+}
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            println!("usage: viewer <config file>");
+            process::exit(1);
+        }
+    };
+    let mut sc = match Config::load(&path).and_then(|config| setup(&config)) {
+        Ok(sc) => sc,
+        Err(err) => {
+            println!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut window = Window::new("Space Colonization");
+    let white = nalgebra04::Pnt3::new(1.0, 1.0, 1.0);
+    let red = nalgebra04::Pnt3::new(1.0, 0.0, 0.0);
+    let mut iteration = 0;
+    let mut growing = true;
+
+    while window.render() {
+        sc.visit_attractor_points(&mut |p| window.draw_point(&kiss3d_point(p), &white));
+        sc.visit_node_segments(&mut |a, b| {
+            window.draw_line(&kiss3d_point(a), &kiss3d_point(b), &red)
+        });
+
+        if growing {
+            let new_nodes = sc.next().unwrap_or(0);
+            iteration += 1;
+            println!("Iteration: {}. New nodes: {}", iteration, new_nodes);
+            growing = new_nodes > 0;
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Step by step construction of a ```SpaceColonization```.
+
+use position::{Position, Vector};
+use std::fmt::Debug;
+use super::{SpaceColonization, SqDist, InformationMode, ConnectPolicy, Biomass};
+use cone::BranchAngle;
+use falloff::Falloff;
+use profiles::Profile;
+use jitter::Jitter;
+use oscillation::OscillationDetection;
+use prefilter::ConnectPrefilter;
+use schedule::Schedule;
+use steps::DoneConditions;
+use tropism::Tropism;
+
+/// Collects the scalar parameters of a ```SpaceColonization```. Options
+/// which take closures (fields, metrics, ...) are set on the built value.
+#[derive(Debug, Clone)]
+pub struct SpaceColonizationBuilder {
+    attract_dist: SqDist,
+    connect_dist: SqDist,
+    max_length: u32,
+    max_branches: u32,
+    move_dist: Schedule<f32>,
+    node_limit: Option<usize>,
+    information_mode: InformationMode,
+    connect_policy: ConnectPolicy,
+    connect_prefilter: Option<ConnectPrefilter>,
+    falloff: Falloff,
+    branch_angle: Option<BranchAngle>,
+    biomass: Option<Biomass>,
+    spatial_index: Option<f32>,
+    world_scale: f32,
+    // direction coordinates and weight.
+    tropism: Option<(Vec<f32>, Schedule<f32>)>,
+    jitter: Option<Jitter>,
+    oscillation: Option<OscillationDetection>,
+    merge_distance: Option<f32>,
+    done_conditions: DoneConditions,
+}
+
+impl Default for SpaceColonizationBuilder {
+    /// The defaults of the examples.
+    fn default() -> SpaceColonizationBuilder {
+        SpaceColonizationBuilder {
+            attract_dist: SqDist::from_dist(0.25),
+            connect_dist: SqDist::from_dist(0.1),
+            max_length: 100,
+            max_branches: 10,
+            move_dist: Schedule::new(0.05),
+            node_limit: None,
+            information_mode: InformationMode::KeepLast,
+            connect_policy: ConnectPolicy::Closest,
+            connect_prefilter: None,
+            falloff: Falloff::Constant,
+            branch_angle: None,
+            biomass: None,
+            spatial_index: None,
+            world_scale: 1.0,
+            tropism: None,
+            jitter: None,
+            oscillation: None,
+            merge_distance: None,
+            done_conditions: DoneConditions::default(),
+        }
+    }
+}
+
+impl SpaceColonizationBuilder {
+    pub fn new() -> SpaceColonizationBuilder {
+        SpaceColonizationBuilder::default()
+    }
+
+    /// Starts from the parameters of a ```Profile```.
+    pub fn from_profile(profile: Profile) -> SpaceColonizationBuilder {
+        profile.builder()
+    }
+
+    /// The influence radius of default attractors.
+    pub fn attract_dist(mut self, attract_dist: SqDist) -> Self {
+        self.attract_dist = attract_dist;
+        self
+    }
+
+    /// The kill distance of default attractors.
+    pub fn connect_dist(mut self, connect_dist: SqDist) -> Self {
+        self.connect_dist = connect_dist;
+        self
+    }
+
+    pub fn max_length(mut self, max_length: u32) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn max_branches(mut self, max_branches: u32) -> Self {
+        self.max_branches = max_branches;
+        self
+    }
+
+    /// A constant or a ```Schedule```.
+    pub fn move_dist<S>(mut self, move_dist: S) -> Self
+        where S: Into<Schedule<f32>>
+    {
+        self.move_dist = move_dist.into();
+        self
+    }
+
+    /// See ```SpaceColonization::set_node_limit```.
+    pub fn node_limit(mut self, limit: usize) -> Self {
+        self.node_limit = Some(limit);
+        self
+    }
+
+    pub fn information_mode(mut self, mode: InformationMode) -> Self {
+        self.information_mode = mode;
+        self
+    }
+
+    pub fn connect_policy(mut self, policy: ConnectPolicy) -> Self {
+        self.connect_policy = policy;
+        self
+    }
+
+    /// See ```SpaceColonization::set_connect_prefilter```.
+    pub fn connect_prefilter(mut self, prefilter: ConnectPrefilter) -> Self {
+        self.connect_prefilter = Some(prefilter);
+        self
+    }
+
+    /// See ```SpaceColonization::set_falloff```.
+    pub fn falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// See ```SpaceColonization::set_branch_angle```.
+    pub fn branch_angle(mut self, branch_angle: BranchAngle) -> Self {
+        self.branch_angle = Some(branch_angle);
+        self
+    }
+
+    /// See ```SpaceColonization::set_biomass```.
+    pub fn biomass(mut self, biomass: Biomass) -> Self {
+        self.biomass = Some(biomass);
+        self
+    }
+
+    /// See ```SpaceColonization::set_spatial_index```.
+    pub fn spatial_index(mut self, cell_size: f32) -> Self {
+        self.spatial_index = Some(cell_size);
+        self
+    }
+
+    /// The scene has ```meters_per_unit``` meters per unit, while the
+    /// distances given to the builder are in meters. They are converted to
+    /// scene units when building, see ```SpaceColonization::set_world_scale```.
+    pub fn world_scale(mut self, meters_per_unit: f32) -> Self {
+        assert!(meters_per_unit > 0.0);
+        self.world_scale = meters_per_unit;
+        self
+    }
+
+    /// A tropism along the vector with the coordinates ```direction```, see
+    /// ```SpaceColonization::set_tropism```.
+    pub fn tropism<W>(mut self, direction: &[f32], weight: W) -> Self
+        where W: Into<Schedule<f32>>
+    {
+        self.tropism = Some((direction.to_vec(), weight.into()));
+        self
+    }
+
+    /// See ```SpaceColonization::set_jitter```.
+    pub fn jitter<M>(mut self, magnitude: M, seed: u64) -> Self
+        where M: Into<Schedule<f32>>
+    {
+        self.jitter = Some(Jitter {
+            magnitude: magnitude.into(),
+            seed,
+        });
+        self
+    }
+
+    /// See ```SpaceColonization::set_oscillation_detection```.
+    pub fn oscillation_detection(mut self, detection: OscillationDetection) -> Self {
+        self.oscillation = Some(detection);
+        self
+    }
+
+    /// See ```SpaceColonization::set_merge_distance```.
+    pub fn merge_distance(mut self, distance: f32) -> Self {
+        self.merge_distance = Some(distance);
+        self
+    }
+
+    /// When the growth is done, see
+    /// ```SpaceColonization::set_done_conditions```.
+    pub fn done_conditions(mut self, conditions: DoneConditions) -> Self {
+        self.done_conditions = conditions;
+        self
+    }
+
+    /// See ```DoneConditions::node_budget```.
+    pub fn node_budget(mut self, budget: usize) -> Self {
+        self.done_conditions.node_budget = Some(budget);
+        self
+    }
+
+    /// See ```DoneConditions::max_iterations```.
+    pub fn max_iterations(mut self, max_iterations: u32) -> Self {
+        self.done_conditions.max_iterations = Some(max_iterations);
+        self
+    }
+
+    /// Ends the simulation as an iterator once the growth is done, see
+    /// ```DoneConditions::stop```.
+    pub fn stop_when_done(mut self) -> Self {
+        self.done_conditions.stop = true;
+        self
+    }
+
+    pub fn build<P, F, I>(&self) -> SpaceColonization<P, F, I>
+        where P: Position<f32, F> + Debug,
+              F: Vector<f32> + Copy + Debug,
+              I: Copy + Default + Debug
+    {
+        let factor = 1.0 / self.world_scale;
+        let sqfactor = factor * factor;
+        let mut move_dist = self.move_dist.clone();
+        for keyframe in move_dist.keyframes.iter_mut() {
+            keyframe.1 *= factor;
+        }
+        let mut sc = SpaceColonization::new(SqDist(self.attract_dist.0 * sqfactor),
+                                            SqDist(self.connect_dist.0 * sqfactor),
+                                            self.max_length,
+                                            self.max_branches,
+                                            move_dist.value_at(0));
+        sc.set_move_dist(move_dist);
+        sc.world_scale = self.world_scale;
+        if let Some(limit) = self.node_limit {
+            sc.set_node_limit(limit);
+        }
+        sc.set_information_mode(self.information_mode);
+        sc.set_connect_policy(self.connect_policy);
+        sc.set_connect_prefilter(self.connect_prefilter);
+        sc.set_falloff(self.falloff);
+        sc.set_branch_angle(self.branch_angle);
+        if let Some(biomass) = self.biomass {
+            sc.set_biomass(Biomass {
+                initial: biomass.initial * factor,
+                per_connection: biomass.per_connection * factor,
+            });
+        }
+        sc.set_spatial_index(self.spatial_index.map(|cell_size| cell_size * factor));
+        if let Some((ref direction, ref weight)) = self.tropism {
+            assert!(direction.len() == P::dim());
+            sc.set_tropism(Some(Tropism::from_coords(direction, weight.clone())));
+        }
+        sc.set_jitter(self.jitter.clone());
+        sc.set_oscillation_detection(self.oscillation.map(|detection| {
+            OscillationDetection {
+                iterations: detection.iterations,
+                radius: detection.radius * factor,
+                disable_attractors: detection.disable_attractors,
+            }
+        }));
+        sc.set_merge_distance(self.merge_distance.map(|distance| distance * factor));
+        sc.set_done_conditions(self.done_conditions);
+        sc
+    }
+}
@@ -0,0 +1,105 @@
+//! How close the attractors came to being reached: after every iteration,
+//! the distance of each watched attractor to the nearest node which could
+//! connect with it, e.g. for "almost reached" indicators or to find out why
+//! some attractors are never consumed.
+//!
+//! Every node is scanned for every watched attractor, so watching only a
+//! subset is much cheaper for large simulations. The distances are not
+//! part of snapshots.
+
+use position::{Position, Vector, Scalar};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+
+/// Which attractors to report, by their stable id (see
+/// ```SpaceColonization::attractor_id```).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureWatch {
+    All,
+    Ids(BTreeSet<u64>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CaptureDistance<S = f32> {
+    /// The stable id of the attractor.
+    pub id: u64,
+
+    /// The nearest node which could connect with the attractor, ```None```
+    /// if there is none (e.g. all nodes are excluded by
+    /// ```not_for_root```).
+    pub node: Option<NodeIdx>,
+
+    /// The distance to ```node``` (with the metric or manifold, if set).
+    pub distance: S,
+
+    /// The square root of the attractor's ```connect_dist```, to compare
+    /// ```distance``` with.
+    pub connect_distance: S,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Reports the distances of the ```watch```ed attractors after every
+    /// iteration, starting with the current state. ```None``` stops it.
+    pub fn set_capture_watch(&mut self, watch: Option<CaptureWatch>) {
+        self.capture_watch = watch;
+        self.update_capture_distances();
+    }
+
+    /// The distances of the watched attractors which are active in the
+    /// next iteration, in attractor order.
+    pub fn capture_distances(&self) -> &[CaptureDistance<S>] {
+        &self.capture_distances
+    }
+
+    pub(crate) fn update_capture_distances(&mut self) {
+        self.capture_distances.clear();
+        let watch = match self.capture_watch {
+            Some(ref watch) => watch,
+            None => return,
+        };
+
+        let next_iteration = self.next_iteration;
+        for (ap, meta) in self.attractors.iter().zip(self.attractor_meta.iter()) {
+            if !ap.is_active_in(next_iteration) {
+                continue;
+            }
+            if let CaptureWatch::Ids(ref ids) = *watch {
+                if !ids.contains(&meta.id) {
+                    continue;
+                }
+            }
+
+            let mut nearest = None;
+            for (i, node) in self.nodes.iter().enumerate() {
+                let excluded_root = self.exclude_roots && node.is_root();
+                if self.is_dormant(NodeIdx::of(i)) {
+                    continue;
+                }
+                if !node.is_active(&self.roots, self.max_length, self.max_branches) ||
+                   excluded_root || !ap.accepts_node_length(node.length) ||
+                   ap.not_for_root == Some(node.root) ||
+                   ap.not_for_connecting_root == Some(node.root) {
+                    continue;
+                }
+                let dist = self.sqdist_to_node(&node.position, &ap.position);
+                match nearest {
+                    Some((_, nearest_dist)) if nearest_dist <= dist => {}
+                    _ => nearest = Some((i, dist)),
+                }
+            }
+
+            self.capture_distances.push(CaptureDistance {
+                id: meta.id,
+                node: nearest.map(|(i, _)| NodeIdx::of(i)),
+                distance: nearest.map_or(S::infinity(), |(_, dist)| dist.0.sqrt()),
+                connect_distance: ap.connect_dist.0.sqrt(),
+            });
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Selecting the main channel of a structure, e.g. the primary path of a
+//! lightning bolt or river, so that it can be drawn differently from the
+//! side branches.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// The leaf furthest away from its root (in number of segments), e.g.
+    /// the tip of a lightning bolt that reached the ground first.
+    pub fn deepest_leaf(&self) -> Option<NodeIdx> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|&(_, node)| node.is_leaf())
+            .max_by_key(|&(_, node)| node.length)
+            .map(|(i, _)| NodeIdx::of(i))
+    }
+
+    /// The path from the root of ```target_leaf``` to ```target_leaf```,
+    /// root first.
+    pub fn select_main_channel(&self, target_leaf: NodeIdx) -> Vec<NodeIdx> {
+        let mut channel = vec![target_leaf];
+        let mut node = &self.nodes[target_leaf.0 as usize];
+        while !node.is_root() {
+            channel.push(node.parent);
+            node = &self.nodes[node.parent.0 as usize];
+        }
+        channel.reverse();
+        channel
+    }
+
+    /// Whether each node is on the main channel to ```target_leaf```,
+    /// indexed by ```NodeIdx```.
+    pub fn main_channel_flags(&self, target_leaf: NodeIdx) -> Vec<bool> {
+        let mut flags = vec![false; self.nodes.len()];
+        for idx in self.select_main_channel(target_leaf) {
+            flags[idx.0 as usize] = true;
+        }
+        flags
+    }
+
+    /// Copies ```radii``` (indexed by ```NodeIdx```), multiplying the radii
+    /// of the nodes on the main channel to ```target_leaf``` by ```boost```.
+    pub fn boost_main_channel(&self, radii: &[f32], target_leaf: NodeIdx, boost: f32) -> Vec<f32> {
+        assert!(radii.len() == self.nodes.len());
+        self.main_channel_flags(target_leaf)
+            .iter()
+            .zip(radii.iter())
+            .map(|(&on_channel, &r)| if on_channel { r * boost } else { r })
+            .collect()
+    }
+}
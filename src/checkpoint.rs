@@ -0,0 +1,509 @@
+//! Rewinding the growth, e.g. to scrub back and forth in an interactive
+//! tool. Growth only appends nodes, so rewinding keeps the number of nodes
+//! instead of the nodes and drops the nodes added since.
+//!
+//! ```set_history``` records what each of the last iterations changed
+//! besides appending nodes: the attractors added, removed, moved or
+//! disabled (as ```record::Event```s, with their state before), the
+//! attractors claimed, and the information, dormancy and stalls of the
+//! nodes that changed. ```rewind``` undoes these changes, so the history
+//! costs as much as the iterations changed rather than the whole
+//! structure:
+//!
+//! ```ignore
+//! sc.set_history(Some(100));
+//! sc.take_steps(10).count();
+//! sc.rewind(3);
+//! ```
+//!
+//! ```checkpoint``` copies the attractors and the information of every
+//! node instead, to be restored with ```restore``` at any later point.
+//!
+//! Edits which rebuild or move nodes (e.g. ```prune```, ```smooth``` or
+//! ```transform```) invalidate older checkpoints and clear the history.
+//! Heatmaps, the provenance of the remaining nodes, the state of the
+//! observers, ```propagate_information``` and changes through
+//! ```attractors_mut``` are not rewound.
+
+use position::{Position, Vector, Scalar};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use super::{SpaceColonization, Attractor, AttractorMeta, NodeIdx};
+use cluster::Cluster;
+use competition::RootCounts;
+use stats::AttractorCounts;
+use record::Event;
+
+/// The state of a simulation at the start of an iteration, see
+/// ```SpaceColonization::checkpoint```.
+#[derive(Debug, Clone)]
+pub struct Checkpoint<P, F, I, S = f32>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug
+{
+    iteration: u32,
+    num_nodes: usize,
+    num_bridges: usize,
+    num_tree_connections: usize,
+    // the assigned information of every node.
+    information: Vec<Option<I>>,
+    // the biomass and counters of every root.
+    roots: Vec<(NodeIdx, S, RootCounts)>,
+    attractors: Vec<Attractor<P, I, S>>,
+    attractor_meta: Vec<AttractorMeta<F>>,
+    attractor_counts: BTreeMap<u32, AttractorCounts>,
+    clusters: Vec<Cluster<P>>,
+    next_attractor_id: u64,
+    quiet_iterations: u32,
+    dormancy: Option<Vec<u32>>,
+    stalls: Option<Vec<u32>>,
+}
+
+impl<P, F, I, S> Checkpoint<P, F, I, S>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug
+{
+    /// The iteration the simulation continues with after ```restore```.
+    pub fn iteration(&self) -> u32 {
+        self.iteration
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+}
+
+/// A change an iteration made besides appending nodes, bridges and tree
+/// connections. Undone in reverse order.
+#[derive(Debug, Clone)]
+enum Change<P, F, I, S>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug
+{
+    /// An attractor event, with the state of the attractor before it
+    /// (except for ```AttractorAdded```).
+    Attractor(Event<P>, Option<(Attractor<P, I, S>, AttractorMeta<F>)>),
+
+    /// The state of the attractor at the index before a claim or
+    /// connection, which are not events.
+    AttractorState(usize, Attractor<P, I, S>, AttractorMeta<F>),
+
+    /// The attractors were reordered, the new attractor ```i``` being the
+    /// old attractor ```permutation[i]```.
+    Reordered(Vec<usize>),
+
+    ClusterAdded,
+
+    /// The cluster removed from the index.
+    ClusterRemoved(usize, Cluster<P>),
+
+    /// The assigned information of a node before it received new one.
+    Information(usize, Option<I>),
+
+    /// The iteration a node counted as fresh from before it grew or woke.
+    Dormancy(usize, u32),
+
+    /// The stalls of a node before they were counted or reset.
+    Stalls(usize, u32),
+}
+
+/// The state at the start of an iteration which appending nodes does not
+/// keep, and the changes made since.
+#[derive(Debug, Clone)]
+struct Delta<P, F, I, S>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug
+{
+    iteration: u32,
+    num_nodes: usize,
+    num_bridges: usize,
+    num_tree_connections: usize,
+    // the biomass and counters of every root.
+    roots: Vec<(S, RootCounts)>,
+    attractor_counts: BTreeMap<u32, AttractorCounts>,
+    next_attractor_id: u64,
+    quiet_iterations: u32,
+    changes: Vec<Change<P, F, I, S>>,
+}
+
+pub(crate) struct History<P, F, I, S>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug
+{
+    capacity: usize,
+    deltas: VecDeque<Delta<P, F, I, S>>,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// The current state, to be restored with ```restore```. Copies the
+    /// attractors and the information of every node, but not the nodes.
+    pub fn checkpoint(&self) -> Checkpoint<P, F, I, S> {
+        Checkpoint {
+            iteration: self.next_iteration,
+            num_nodes: self.nodes.len(),
+            num_bridges: self.bridges.len(),
+            num_tree_connections: self.tree_connections.len(),
+            information: self.nodes.iter().map(|node| node.assigned_information).collect(),
+            roots: self.roots
+                       .iter()
+                       .map(|root| (root.node, root.biomass, root.competition))
+                       .collect(),
+            attractors: self.attractors.clone(),
+            attractor_meta: self.attractor_meta.clone(),
+            attractor_counts: self.attractor_counts.clone(),
+            clusters: self.clusters.clone(),
+            next_attractor_id: self.next_attractor_id,
+            quiet_iterations: self.quiet_iterations,
+            dormancy: self.dormancy.as_ref().map(|dormancy| dormancy.since.clone()),
+            stalls: self.oscillation.as_ref().map(|oscillation| oscillation.stalls.clone()),
+        }
+    }
+
+    /// Goes back to ```checkpoint```, which must have been taken from this
+    /// simulation without rebuilding its nodes since. Options changed since
+    /// are kept. Panics if the nodes of the checkpoint are gone.
+    pub fn restore(&mut self, checkpoint: &Checkpoint<P, F, I, S>) {
+        assert!(checkpoint.num_nodes <= self.nodes.len(),
+                "the checkpoint is newer than the nodes");
+        self.truncate_nodes(checkpoint.num_nodes);
+
+        let iteration = checkpoint.iteration;
+        for (node, &information) in self.nodes.iter_mut().zip(checkpoint.information.iter()) {
+            node.assigned_information = information;
+            node.received_information.retain(|&(_, received)| received < iteration);
+        }
+        for &(root, biomass, competition) in checkpoint.roots.iter() {
+            let state = self.root_state_mut(root);
+            state.biomass = biomass;
+            state.competition = competition;
+        }
+        if let (Some(dormancy), Some(since)) = (self.dormancy.as_mut(),
+                                                checkpoint.dormancy.as_ref()) {
+            dormancy.since = since.clone();
+        }
+        if let (Some(oscillation), Some(stalls)) = (self.oscillation.as_mut(),
+                                                    checkpoint.stalls.as_ref()) {
+            oscillation.stalls = stalls.clone();
+        }
+        self.bridges.truncate(checkpoint.num_bridges);
+        self.tree_connections.truncate(checkpoint.num_tree_connections);
+
+        self.attractors = checkpoint.attractors.clone();
+        self.attractor_meta = checkpoint.attractor_meta.clone();
+        self.attractor_counts = checkpoint.attractor_counts.clone();
+        self.clusters = checkpoint.clusters.clone();
+        self.next_attractor_id = checkpoint.next_attractor_id;
+        self.next_iteration = iteration;
+        self.quiet_iterations = checkpoint.quiet_iterations;
+
+        self.update_capture_distances();
+        self.record_full_state();
+    }
+
+    /// Records the changes of each of the last ```capacity``` iterations,
+    /// see ```rewind```. ```None``` drops them.
+    pub fn set_history(&mut self, capacity: Option<usize>) {
+        self.history = capacity.map(|capacity| {
+            History {
+                capacity,
+                deltas: VecDeque::with_capacity(capacity),
+            }
+        });
+    }
+
+    /// The number of iterations ```rewind``` can go back.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |history| history.deltas.len())
+    }
+
+    /// Undoes the last ```iterations``` iterations, or as many as the
+    /// history holds, together with the attractors added or changed between
+    /// them. Returns the number of undone iterations.
+    pub fn rewind(&mut self, iterations: usize) -> usize {
+        let mut undone = Vec::new();
+        if let Some(ref mut history) = self.history {
+            while undone.len() < iterations {
+                match history.deltas.pop_back() {
+                    Some(delta) => undone.push(delta),
+                    None => break,
+                }
+            }
+        }
+        let oldest = match undone.last() {
+            Some(oldest) => oldest,
+            None => return 0,
+        };
+        // the changes refer to nodes of later iterations, so they are undone
+        // before the nodes are dropped.
+        for delta in undone.iter() {
+            for change in delta.changes.iter().rev() {
+                self.undo(change, delta.iteration);
+            }
+        }
+
+        self.truncate_nodes(oldest.num_nodes);
+
+        for (state, &(biomass, competition)) in self.roots.iter_mut().zip(oldest.roots.iter()) {
+            state.biomass = biomass;
+            state.competition = competition;
+        }
+        self.bridges.truncate(oldest.num_bridges);
+        self.tree_connections.truncate(oldest.num_tree_connections);
+        self.attractor_counts = oldest.attractor_counts.clone();
+        self.next_attractor_id = oldest.next_attractor_id;
+        self.next_iteration = oldest.iteration;
+        self.quiet_iterations = oldest.quiet_iterations;
+
+        self.update_capture_distances();
+        self.record_full_state();
+        undone.len()
+    }
+
+    /// Drops the nodes from ```num_nodes``` on, which were appended since.
+    fn truncate_nodes(&mut self, num_nodes: usize) {
+        self.nodes.truncate(num_nodes);
+        self.positions.truncate(num_nodes);
+        self.parents.truncate(num_nodes);
+        self.children.truncate(num_nodes);
+        for (i, children) in self.children.iter_mut().enumerate() {
+            children.retain(|&child| (child as usize) < num_nodes);
+            self.nodes[i].branches = children.len() as u32;
+        }
+        let sources: Vec<usize> = (0..num_nodes).collect();
+        self.remap_node_data(&sources);
+        let cell_size = self.spatial_index_cell_size();
+        self.set_spatial_index(cell_size);
+    }
+
+    fn undo(&mut self, change: &Change<P, F, I, S>, iteration: u32) {
+        match *change {
+            Change::Attractor(Event::AttractorAdded { .. }, _) => {
+                self.attractors.pop();
+                self.attractor_meta.pop();
+            }
+            Change::Attractor(Event::AttractorRemoved { index, .. }, Some((attractor, meta))) => {
+                self.attractors.push(attractor);
+                self.attractor_meta.push(meta);
+                let last = self.attractors.len() - 1;
+                self.attractors.swap(index, last);
+                self.attractor_meta.swap(index, last);
+            }
+            Change::Attractor(Event::AttractorMoved { index, .. }, Some((attractor, meta))) |
+            Change::Attractor(Event::AttractorDisabled { index, .. }, Some((attractor, meta))) |
+            Change::AttractorState(index, attractor, meta) => {
+                self.attractors[index] = attractor;
+                self.attractor_meta[index] = meta;
+            }
+            Change::Attractor(..) => {}
+            Change::Reordered(ref permutation) => {
+                let mut attractors = self.attractors.clone();
+                let mut attractor_meta = self.attractor_meta.clone();
+                for (i, &old) in permutation.iter().enumerate() {
+                    attractors[old] = self.attractors[i];
+                    attractor_meta[old] = self.attractor_meta[i];
+                }
+                self.attractors = attractors;
+                self.attractor_meta = attractor_meta;
+            }
+            Change::ClusterAdded => {
+                self.clusters.pop();
+            }
+            Change::ClusterRemoved(index, ref cluster) => {
+                self.clusters.push(cluster.clone());
+                let last = self.clusters.len() - 1;
+                self.clusters.swap(index, last);
+            }
+            Change::Information(node, information) => {
+                let node = &mut self.nodes[node];
+                node.assigned_information = information;
+                node.received_information.retain(|&(_, received)| received < iteration);
+            }
+            Change::Dormancy(node, since) => {
+                if let Some(ref mut dormancy) = self.dormancy {
+                    dormancy.since[node] = since;
+                }
+            }
+            Change::Stalls(node, stalls) => {
+                if let Some(ref mut oscillation) = self.oscillation {
+                    oscillation.stalls[node] = stalls;
+                }
+            }
+        }
+    }
+
+    /// Starts recording the changes of an iteration, if the history is
+    /// enabled.
+    pub(crate) fn push_history(&mut self) {
+        match self.history {
+            Some(ref history) if history.capacity > 0 => {}
+            _ => return,
+        }
+        let delta = Delta {
+            iteration: self.next_iteration,
+            num_nodes: self.nodes.len(),
+            num_bridges: self.bridges.len(),
+            num_tree_connections: self.tree_connections.len(),
+            roots: self.roots.iter().map(|root| (root.biomass, root.competition)).collect(),
+            attractor_counts: self.attractor_counts.clone(),
+            next_attractor_id: self.next_attractor_id,
+            quiet_iterations: self.quiet_iterations,
+            changes: Vec::new(),
+        };
+        let history = self.history.as_mut().unwrap();
+        if history.deltas.len() >= history.capacity {
+            history.deltas.pop_front();
+        }
+        history.deltas.push_back(delta);
+    }
+
+    /// Drops the history, whose changes no longer match the nodes.
+    pub(crate) fn clear_history(&mut self) {
+        if let Some(ref mut history) = self.history {
+            history.deltas.clear();
+        }
+    }
+
+    fn is_recording_history(&self) -> bool {
+        self.history.as_ref().is_some_and(|history| !history.deltas.is_empty())
+    }
+
+    fn note(&mut self, change: Change<P, F, I, S>) {
+        if let Some(delta) = self.history.as_mut().and_then(|history| history.deltas.back_mut()) {
+            delta.changes.push(change);
+        }
+    }
+
+    /// Notes an attractor event for ```rewind```, before the change.
+    pub(crate) fn note_attractor(&mut self, event: &Event<P>) {
+        if !self.is_recording_history() {
+            return;
+        }
+        let state = match *event {
+            Event::AttractorRemoved { index, .. } |
+            Event::AttractorMoved { index, .. } |
+            Event::AttractorDisabled { index, .. } => {
+                Some((self.attractors[index], self.attractor_meta[index]))
+            }
+            _ => None,
+        };
+        self.note(Change::Attractor(*event, state));
+    }
+
+    /// Notes the state of the attractor at ```idx``` before a change which
+    /// is not an event.
+    pub(crate) fn note_attractor_state(&mut self, idx: usize) {
+        if self.is_recording_history() {
+            let (attractor, meta) = (self.attractors[idx], self.attractor_meta[idx]);
+            self.note(Change::AttractorState(idx, attractor, meta));
+        }
+    }
+
+    /// Notes that the attractors were reordered by ```permutation```.
+    pub(crate) fn note_reordered(&mut self, permutation: &[usize]) {
+        if self.is_recording_history() {
+            self.note(Change::Reordered(permutation.to_vec()));
+        }
+    }
+
+    pub(crate) fn note_cluster_added(&mut self) {
+        self.note(Change::ClusterAdded);
+    }
+
+    /// Notes the cluster at ```idx```, before it is swap-removed.
+    pub(crate) fn note_cluster_removed(&mut self, idx: usize) {
+        if self.is_recording_history() {
+            let cluster = self.clusters[idx].clone();
+            self.note(Change::ClusterRemoved(idx, cluster));
+        }
+    }
+
+    /// Notes the assigned information of ```node```, before it changes.
+    pub(crate) fn note_information(&mut self, node: NodeIdx) {
+        let node = node.0 as usize;
+        let information = self.nodes[node].assigned_information;
+        self.note(Change::Information(node, information));
+    }
+
+    pub(crate) fn note_dormancy(&mut self, node: usize, since: u32) {
+        self.note(Change::Dormancy(node, since));
+    }
+
+    pub(crate) fn note_stalls(&mut self, node: usize, stalls: u32) {
+        self.note(Change::Stalls(node, stalls));
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod tests {
+    use na::{Point2, Vector2};
+    use fixtures::{self, snapshot};
+    use oscillation::OscillationDetection;
+    use priority::AttractorOrder;
+    use {SpaceColonization, ConnectAction};
+
+    fn simulation() -> SpaceColonization<Point2<f32>, Vector2<f32>, u32> {
+        let mut sc = fixtures::simulation(&[Point2::new(0.0, 0.0)], 300, |i, attractor| {
+            attractor.information = i as u32;
+            attractor.strength = 1.0 + (i % 3) as f32;
+            if i % 7 == 0 {
+                attractor.connect_action = ConnectAction::DisableFor { iterations: 5 };
+            }
+        });
+        sc.set_dormancy(Some(3));
+        sc.set_oscillation_detection(Some(OscillationDetection {
+            iterations: 2,
+            radius: 0.01,
+            disable_attractors: true,
+        }));
+        sc.set_attractor_order(AttractorOrder::Strength, 2);
+        sc
+    }
+
+    #[test]
+    fn rewind_restores_the_earlier_state() {
+        let mut sc = simulation();
+        sc.set_history(Some(20));
+        sc.take_steps(8).count();
+        let earlier = snapshot(&sc);
+        sc.take_steps(12).count();
+        assert_eq!(sc.history_len(), 20);
+
+        assert_eq!(sc.rewind(12), 12);
+        assert_eq!(sc.history_len(), 8);
+        assert_eq!(snapshot(&sc), earlier);
+    }
+
+    #[test]
+    fn replay_after_rewind_is_identical() {
+        let mut sc = simulation();
+        sc.set_history(Some(50));
+        sc.take_steps(20).count();
+        sc.rewind(12);
+        sc.take_steps(32).count();
+
+        let mut straight = simulation();
+        straight.take_steps(40).count();
+        assert_eq!(snapshot(&sc), snapshot(&straight));
+    }
+
+    #[test]
+    fn restore_returns_to_the_checkpoint() {
+        let mut sc = simulation();
+        sc.take_steps(10).count();
+        let checkpoint = sc.checkpoint();
+        let earlier = snapshot(&sc);
+        sc.take_steps(10).count();
+        sc.restore(&checkpoint);
+        assert_eq!(snapshot(&sc), earlier);
+    }
+}
@@ -0,0 +1,153 @@
+//! Clustering of dense attractor clouds (e.g. derived from point clouds)
+//! into weighted super-attractors, which are split up into their members
+//! once branches come close. This cuts the cost of the early iterations,
+//! where most attractors are far away from any node.
+
+use position::{Position, Vector, Scalar};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use super::{SpaceColonization, Attractor, ConnectAction, SqDist, scalar};
+use record::Event;
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClusterOptions {
+    /// Edge length of the grid cells the attractors are binned into.
+    pub cell_size: f32,
+
+    /// A cluster is split up into its members once an active node comes
+    /// closer than this distance to its centroid.
+    pub uncluster_dist: f32,
+
+    /// If set, all clusters are split up at this iteration.
+    pub until_iteration: Option<u32>,
+}
+
+/// A super-attractor standing in for its ```members```.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cluster<P> {
+    pub centroid: P,
+    pub members: Vec<P>,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Adds default attractors at ```positions```, binned into a grid.
+    /// Cells containing more than one position are represented by a single
+    /// super-attractor at their centroid, weighted by the number of
+    /// members, until a node comes close. Super-attractors never connect
+    /// and are not counted in the attractor statistics.
+    pub fn add_clustered_attractors(&mut self, positions: &[P], options: ClusterOptions) {
+        assert!(options.cell_size > 0.0);
+        self.cluster_options = Some(options);
+
+        let dim = P::dim();
+        let cell_size: S = scalar(options.cell_size);
+        let mut cells: BTreeMap<Vec<i64>, Vec<P>> = BTreeMap::new();
+        for p in positions.iter() {
+            let cell = (0..dim).map(|i| (p[i] / cell_size).floor().to_i64().unwrap()).collect();
+            cells.entry(cell).or_default().push(*p);
+        }
+
+        // a member can be half a cell diagonal away from the centroid.
+        let reach = self.default_attract_dist.0.sqrt() +
+                    scalar(0.5 * options.cell_size * (dim as f32).sqrt());
+        for (_, members) in cells.into_iter() {
+            if members.len() == 1 {
+                self.add_default_attractor(members[0]);
+                continue;
+            }
+            let mut centroid = members[0];
+            for p in members[1..].iter() {
+                centroid = centroid + (*p - members[0]);
+            }
+            let offset = (centroid - members[0]) / scalar(members.len() as f32);
+            let centroid = members[0] + offset;
+
+            let attractor = Attractor {
+                attract_dist: SqDist::from_dist(reach),
+                connect_dist: SqDist(S::zero()),
+                strength: members.len() as f32,
+                position: centroid,
+                information: I::default(),
+                connect_action: ConnectAction::DisableForConnectingRoot,
+                active_from_iteration: 0,
+                active_until_iteration: None,
+                strength_ramp: None,
+                not_for_root: None,
+                not_for_connecting_root: None,
+                min_node_length: None,
+                max_node_length: None,
+                group: 0,
+            };
+            let event = Event::AttractorAdded { position: centroid };
+            self.note_attractor(&event);
+            self.record(event);
+            let meta = self.new_attractor_meta();
+            self.attractor_meta.push(meta);
+            self.attractors.push(attractor);
+            self.note_cluster_added();
+            self.clusters.push(Cluster {
+                centroid,
+                members,
+            });
+        }
+    }
+
+    pub fn num_clusters(&self) -> usize {
+        self.clusters.len()
+    }
+
+    /// Whether the attractor at ```idx``` stands in for a cluster.
+    pub(crate) fn is_super_attractor(&self, idx: usize) -> bool {
+        let a = &self.attractors[idx];
+        a.connect_dist.0 == S::zero() && self.clusters.iter().any(|c| c.centroid == a.position)
+    }
+
+    /// Splits up the clusters which active nodes came close to (or all, if
+    /// clustering has ended).
+    pub(crate) fn uncluster_attractors(&mut self, current_iteration: u32, start_index: usize) {
+        let options = match self.cluster_options {
+            Some(options) if !self.clusters.is_empty() => options,
+            _ => return,
+        };
+        let expand_all = match options.until_iteration {
+            Some(until) => current_iteration >= until,
+            None => false,
+        };
+        let uncluster_sqdist: S = scalar(options.uncluster_dist * options.uncluster_dist);
+
+        let mut i = 0;
+        while i < self.clusters.len() {
+            let centroid = self.clusters[i].centroid;
+            if !expand_all && self.sqdist_to_frontier(&centroid, start_index) >= uncluster_sqdist {
+                i += 1;
+                continue;
+            }
+
+            self.note_cluster_removed(i);
+            let cluster = self.clusters.swap_remove(i);
+            let found = self.attractors
+                            .iter()
+                            .position(|a| a.connect_dist.0 == S::zero() && a.position == centroid);
+            if let Some(idx) = found {
+                let event = Event::AttractorRemoved {
+                    index: idx,
+                    killed: false,
+                };
+                self.note_attractor(&event);
+                self.attractors.swap_remove(idx);
+                self.attractor_meta.swap_remove(idx);
+                self.record(event);
+            }
+            for p in cluster.members.into_iter() {
+                self.add_default_attractor(p);
+            }
+        }
+    }
+}
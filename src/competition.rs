@@ -0,0 +1,139 @@
+//! How multiple trees competed for the attractors, e.g. for ecology
+//! experiments or to balance the seeds of a multi-tree scene.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use std::ops::IndexMut;
+use super::{SpaceColonization, NodeIdx};
+
+/// Counters kept on every root node.
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct RootCounts {
+    pub(crate) consumed: usize,
+    pub(crate) connections: usize,
+    pub(crate) contested_consumed: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RootReport<P, S = f32> {
+    pub root: NodeIdx,
+
+    /// Number of nodes of the tree, including the root.
+    pub num_nodes: usize,
+
+    /// Attractors killed by nodes of the tree.
+    pub consumed: usize,
+
+    /// Number of times a node of the tree connected with an attractor.
+    pub connections: usize,
+
+    /// Corners of the axis-aligned bounding box of the tree's nodes.
+    pub territory_min: P,
+    pub territory_max: P,
+
+    /// Volume (area in 2D) of the bounding box.
+    pub territory_volume: S,
+
+    /// Consumed attractors which were contested before.
+    pub contested_consumed: usize,
+
+    /// Remaining contested attractors that were last claimed by the tree.
+    pub contested_claimed: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompetitionReport<P, S = f32> {
+    /// One report per root, ordered by root index.
+    pub roots: Vec<RootReport<P, S>>,
+
+    /// Remaining attractors which are contested.
+    pub remaining_contested: usize,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Summarizes per root what its tree consumed and covered. An attractor
+    /// is contested once nodes of more than one tree were nearest to it
+    /// over time.
+    pub fn competition_report(&self) -> CompetitionReport<P, S>
+        where P: IndexMut<usize, Output = S>
+    {
+        let dim = P::dim();
+        let mut roots: Vec<RootReport<P, S>> = Vec::new();
+        // position of each root's report in ```roots```.
+        let mut report_of = vec![None; self.nodes.len()];
+
+        // roots are always stored before the nodes of their tree.
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.is_root() {
+                report_of[i] = Some(roots.len());
+                let competition = self.root_state(node.root).competition;
+                roots.push(RootReport {
+                    root: node.root,
+                    num_nodes: 0,
+                    consumed: competition.consumed,
+                    connections: competition.connections,
+                    territory_min: node.position,
+                    territory_max: node.position,
+                    territory_volume: S::zero(),
+                    contested_consumed: competition.contested_consumed,
+                    contested_claimed: 0,
+                });
+            }
+            let report = match report_of[node.root.0 as usize] {
+                Some(r) => &mut roots[r],
+                None => continue,
+            };
+            report.num_nodes += 1;
+            for k in 0..dim {
+                if node.position[k] < report.territory_min[k] {
+                    report.territory_min[k] = node.position[k];
+                }
+                if node.position[k] > report.territory_max[k] {
+                    report.territory_max[k] = node.position[k];
+                }
+            }
+        }
+
+        for report in roots.iter_mut() {
+            report.territory_volume = (0..dim)
+                                          .map(|k| report.territory_max[k] -
+                                                   report.territory_min[k])
+                                          .fold(S::one(), |volume, extent| volume * extent);
+        }
+
+        let mut remaining_contested = 0;
+        for meta in self.attractor_meta.iter().filter(|m| m.contested) {
+            remaining_contested += 1;
+            let claimant = meta.nearest_root.and_then(|root| report_of[root.0 as usize]);
+            if let Some(r) = claimant {
+                roots[r].contested_claimed += 1;
+            }
+        }
+
+        CompetitionReport {
+            roots,
+            remaining_contested,
+        }
+    }
+
+    /// Credits a connection with the attractor at ```ap_idx``` to ```root```.
+    /// Call before the attractor is removed.
+    pub(crate) fn note_connection(&mut self, ap_idx: usize, root: NodeIdx, killed: bool) {
+        self.attractor_meta[ap_idx].claim(root);
+        let contested = self.attractor_meta[ap_idx].contested;
+        let counts = &mut self.root_state_mut(root).competition;
+        counts.connections += 1;
+        if killed {
+            counts.consumed += 1;
+            if contested {
+                counts.contested_consumed += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Limiting the growth directions of a tree to a cone around an axis, e.g.
+//! so that seeds at ground level don't grow down into the terrain when
+//! attractors were sampled slightly below it. The cone is checked after the
+//! flow field steered the direction.
+//!
+//! A ```BranchAngle``` limits the growth direction of every node to a cone
+//! around its own heading instead, so that branches can't fold back on
+//! themselves. It is checked before the growth cone of the tree.
+
+use position::{Position, Vector, Scalar};
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, scalar};
+
+/// What happens to a growth direction outside the cone.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConeMode {
+    /// The node does not grow in this iteration.
+    Reject,
+
+    /// The direction is moved onto the nearest direction of the cone.
+    Clamp,
+
+    /// The direction is mirrored at the plane normal to the axis, and
+    /// clamped if it is still outside (only with cones narrower than a
+    /// hemisphere).
+    Reflect,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GrowthCone<F> {
+    pub axis: F,
+
+    /// The largest angle between a growth direction and the axis, in
+    /// radians.
+    pub max_angle: f32,
+
+    pub mode: ConeMode,
+}
+
+/// The largest angle between the segment from the parent to a node and
+/// the direction the node grows in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BranchAngle {
+    /// In radians.
+    pub max_angle: f32,
+
+    pub mode: ConeMode,
+}
+
+impl<F> GrowthCone<F> {
+    /// Allows all directions with a non-negative component along ```axis```.
+    pub fn hemisphere(axis: F, mode: ConeMode) -> GrowthCone<F> {
+        GrowthCone {
+            axis,
+            max_angle: PI / 2.0,
+            mode,
+        }
+    }
+
+    /// The normalized ```direction``` limited to the cone, ```None``` if the
+    /// node should not grow.
+    pub fn constrain<S>(&self, direction: F) -> Option<F>
+        where F: Vector<S> + Copy,
+              S: Scalar
+    {
+        if self.max_angle >= PI || self.axis.is_zero() {
+            return Some(direction);
+        }
+        let axis = self.axis.normalize();
+        let min_cos = scalar::<S>(self.max_angle).cos();
+        let cos = direction.dot(&axis);
+        if cos >= min_cos {
+            return Some(direction);
+        }
+        match self.mode {
+            ConeMode::Reject => None,
+            ConeMode::Clamp => self.clamp(axis, direction),
+            ConeMode::Reflect => {
+                let reflected = direction - axis * (cos + cos);
+                if reflected.dot(&axis) >= min_cos {
+                    Some(reflected)
+                } else {
+                    self.clamp(axis, reflected)
+                }
+            }
+        }
+    }
+
+    fn clamp<S>(&self, axis: F, direction: F) -> Option<F>
+        where F: Vector<S> + Copy,
+              S: Scalar
+    {
+        let perpendicular = direction - axis * direction.dot(&axis);
+        if perpendicular.sqnorm() < scalar(1e-12) {
+            // straight against the axis, there is no nearest direction.
+            return None;
+        }
+        let max_angle: S = scalar(self.max_angle);
+        Some(axis * max_angle.cos() + perpendicular.normalize() * max_angle.sin())
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Limits the growth of the tree of ```root``` to ```cone```. ```None```
+    /// allows all directions.
+    pub fn set_growth_cone(&mut self, root: NodeIdx, cone: Option<GrowthCone<F>>) {
+        self.root_state_mut(root).growth_cone = cone;
+    }
+
+    /// The growth cone of the tree ```node``` belongs to.
+    pub fn growth_cone_of(&self, node: NodeIdx) -> Option<GrowthCone<F>> {
+        self.root_state(node).growth_cone
+    }
+
+    /// Limits the growth directions of all nodes to ```branch_angle```
+    /// around their heading. ```None``` allows all directions. Can be
+    /// overridden per tree with ```RootConfig::branch_angle```.
+    pub fn set_branch_angle(&mut self, branch_angle: Option<BranchAngle>) {
+        self.branch_angle = branch_angle;
+    }
+
+    pub fn branch_angle(&self) -> Option<BranchAngle> {
+        self.branch_angle
+    }
+
+    /// The growth ```direction``` of the node at ```index``` limited to the
+    /// branch angle of its tree. Roots grow in all directions.
+    pub(crate) fn constrain_to_branch_angle(&self, index: usize, direction: F) -> Option<F> {
+        let branch_angle = match self.root_state(NodeIdx::of(index)).config.branch_angle {
+            Some(branch_angle) => branch_angle,
+            None => {
+                match self.branch_angle {
+                    Some(branch_angle) => branch_angle,
+                    None => return Some(direction),
+                }
+            }
+        };
+        match self.heading(index) {
+            Some(heading) => {
+                GrowthCone {
+                        axis: heading,
+                        max_angle: branch_angle.max_angle,
+                        mode: branch_angle.mode,
+                    }
+                    .constrain(direction)
+            }
+            None => Some(direction),
+        }
+    }
+
+    /// The growth ```direction``` of a node of the tree of ```root```,
+    /// limited to its cone.
+    pub(crate) fn constrain_to_cone(&self, root: NodeIdx, direction: F) -> Option<F> {
+        match self.root_state(root).growth_cone {
+            Some(ref cone) => cone.constrain(direction),
+            None => Some(direction),
+        }
+    }
+}
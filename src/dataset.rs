@@ -0,0 +1,153 @@
+//! Generating labeled datasets of synthetic branching structures, e.g. to
+//! train machine learning models. Every sample is grown from a config
+//! drawn from a distribution and a seed, on a thread pool (see
+//! ```batch::run_all```), and written to its own file. A ```labels.csv```
+//! lists the file, seed and parameters of every sample.
+
+use position::{Position, Vector};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use super::SpaceColonization;
+use batch::{self, BatchError, Budget};
+use export::Format;
+use steps::DoneReason;
+
+/// The parameters of a config, written to ```labels.csv```.
+pub trait Labels {
+    /// Names and values, in the same order for every config.
+    fn labels(&self) -> Vec<(&'static str, f32)>;
+}
+
+/// A written sample.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub index: usize,
+    pub seed: u64,
+    pub path: PathBuf,
+    pub iterations: usize,
+    pub stop_reason: DoneReason,
+}
+
+/// Writes samples to ```directory```, named ```sample_<index>.<extension>```.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    directory: PathBuf,
+    format: Format,
+    budget: Budget,
+}
+
+impl Dataset {
+    pub fn new<A: AsRef<Path>>(directory: A, format: Format, budget: Budget) -> Dataset {
+        Dataset {
+            directory: directory.as_ref().to_path_buf(),
+            format,
+            budget,
+        }
+    }
+
+    pub fn sample_path(&self, index: usize) -> PathBuf {
+        self.directory.join(format!("sample_{:05}.{}", index, self.format.extension()))
+    }
+
+    pub fn labels_path(&self) -> PathBuf {
+        self.directory.join("labels.csv")
+    }
+
+    /// Generates ```count``` samples. Sample ```i``` has the seed
+    /// ```base_seed + i``` and the config ```distribution(seed)```, from
+    /// which ```setup``` creates the simulation. ```num_threads``` is
+    /// passed on to ```batch::run_all```.
+    ///
+    /// Returns every sample, or why its run failed. Failed samples are
+    /// left out of ```labels.csv```.
+    pub fn generate<C, D, S, P, F, I>(&self,
+                                      count: usize,
+                                      base_seed: u64,
+                                      num_threads: usize,
+                                      distribution: D,
+                                      setup: S)
+                                      -> io::Result<Vec<Result<Sample, BatchError>>>
+        where C: Labels + Sync,
+              D: Fn(u64) -> C,
+              S: Fn(&C, u64) -> SpaceColonization<P, F, I> + Sync,
+              P: Position<f32, F> + Debug,
+              F: Vector<f32> + Copy + Debug,
+              I: Copy + Default + Debug
+    {
+        let seeds: Vec<u64> = (0..count).map(|i| base_seed.wrapping_add(i as u64)).collect();
+        let configs: Vec<C> = seeds.iter().map(|&seed| distribution(seed)).collect();
+        let indices: Vec<usize> = (0..count).collect();
+
+        // the index is part of the config, so that the runs know their file.
+        let runs: Vec<(usize, &C)> = indices.into_iter().zip(configs.iter()).collect();
+        let outputs = batch::run_all(&runs,
+                                     &seeds,
+                                     self.budget,
+                                     num_threads,
+                                     |&(_, config), seed| setup(config, seed),
+                                     |&(index, _), sc| self.write_sample(index, &sc));
+
+        let mut samples = Vec::with_capacity(count);
+        for output in outputs.into_iter() {
+            samples.push(match output {
+                Ok(output) => {
+                    Ok(Sample {
+                        index: output.run,
+                        seed: output.seed,
+                        path: output.result?,
+                        iterations: output.iterations,
+                        stop_reason: output.stop_reason,
+                    })
+                }
+                Err(err) => Err(err),
+            });
+        }
+        self.write_labels(&samples, &configs)?;
+        Ok(samples)
+    }
+
+    fn write_sample<P, F, I>(&self,
+                             index: usize,
+                             sc: &SpaceColonization<P, F, I>)
+                             -> io::Result<PathBuf>
+        where P: Position<f32, F> + Debug,
+              F: Vector<f32> + Copy + Debug,
+              I: Copy + Default + Debug
+    {
+        let path = self.sample_path(index);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        sc.export(&mut writer, self.format)?;
+        writer.flush()?;
+        Ok(path)
+    }
+
+    fn write_labels<C: Labels>(&self,
+                               samples: &[Result<Sample, BatchError>],
+                               configs: &[C])
+                               -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(self.labels_path())?);
+        write!(w, "file,seed,iterations,stop_reason")?;
+        if let Some(config) = configs.first() {
+            for (name, _) in config.labels() {
+                write!(w, ",{}", name)?;
+            }
+        }
+        writeln!(w)?;
+        for sample in samples.iter().filter_map(|s| s.as_ref().ok()) {
+            let file = sample.path.file_name().unwrap().to_string_lossy();
+            write!(w,
+                   "{},{},{},{:?}",
+                   file,
+                   sample.seed,
+                   sample.iterations,
+                   sample.stop_reason)?;
+            for (_, value) in configs[sample.index].labels() {
+                write!(w, ",{}", value)?;
+            }
+            writeln!(w)?;
+        }
+        w.flush()
+    }
+}
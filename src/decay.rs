@@ -0,0 +1,68 @@
+//! Length-weighted growth: the longer a branch (in number of segments from
+//! its root), the less likely its tip grows in a given iteration. This
+//! approximates the cost of transporting resources out to the tips and
+//! keeps crowns compact, instead of a few runaway branches consuming all
+//! attractors.
+//!
+//! The draw is a hash of the seed, the iteration and the node index, so that
+//! runs are reproducible and snapshots need no random number generator
+//! state.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::SpaceColonization;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LengthDecay {
+    /// The branch length at which the growth probability has halved.
+    pub half_length: f32,
+
+    /// The growth probability never drops below this, so that long branches
+    /// still reach their attractors eventually.
+    pub min_probability: f32,
+
+    pub seed: u64,
+}
+
+impl LengthDecay {
+    /// The probability that a node at ```length``` grows.
+    pub fn probability(&self, length: u32) -> f32 {
+        let p = 0.5f32.powf(length as f32 / self.half_length);
+        p.max(self.min_probability)
+    }
+}
+
+/// A uniform number in [0, 1), derived from the arguments (splitmix64).
+pub(crate) fn uniform(seed: u64, iteration: u32, node: usize) -> f32 {
+    let mut z = seed ^ ((iteration as u64) << 32) ^ (node as u64);
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z = z ^ (z >> 31);
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Makes the growth of a node less likely the longer its branch.
+    /// ```None``` disables it, so that every attracted node grows.
+    pub fn set_length_decay(&mut self, decay: Option<LengthDecay>) {
+        self.length_decay = decay;
+    }
+
+    /// Whether the attracted node ```node``` grows in ```current_iteration```.
+    pub(crate) fn passes_length_decay(&self, node: usize, current_iteration: u32) -> bool {
+        match self.length_decay {
+            Some(decay) => {
+                let p = decay.probability(self.nodes[node].length);
+                p >= 1.0 || uniform(decay.seed, current_iteration, node) < p
+            }
+            None => true,
+        }
+    }
+}
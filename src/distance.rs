@@ -0,0 +1,131 @@
+//! Sampling the distance to the grown structure on a regular grid, e.g. as
+//! a signed distance field for rendering, to carve obstacles for a
+//! subsequent run or for ambient-occlusion-like shading.
+
+use position::{Position, Vector};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::IndexMut;
+use super::SpaceColonization;
+
+/// Sample points ```origin + k * spacing``` with ```k[i] < counts[i]```
+/// along every axis. Samples are ordered with the first axis varying
+/// fastest.
+#[derive(Debug, Clone)]
+pub struct SampleGrid<P> {
+    pub origin: P,
+    pub spacing: f32,
+    pub counts: Vec<usize>,
+}
+
+impl<P> SampleGrid<P>
+    where P: Copy + IndexMut<usize, Output = f32>
+{
+    pub fn num_samples(&self) -> usize {
+        self.counts.iter().product()
+    }
+
+    /// The position of the sample with the given (flat) index.
+    pub fn sample(&self, mut index: usize) -> P {
+        let mut p = self.origin;
+        for (i, &count) in self.counts.iter().enumerate() {
+            p[i] += (index % count) as f32 * self.spacing;
+            index /= count;
+        }
+        p
+    }
+}
+
+/// Calls ```visitor``` with every cell in the box from ```lo``` to ```hi```
+/// (inclusive).
+fn visit_cells<V>(lo: &[i64], hi: &[i64], visitor: &mut V)
+    where V: FnMut(&[i64])
+{
+    if lo.iter().zip(hi.iter()).any(|(l, h)| l > h) {
+        return;
+    }
+    let mut cell = lo.to_vec();
+    loop {
+        visitor(&cell);
+        let mut axis = 0;
+        loop {
+            if axis == cell.len() {
+                return;
+            }
+            if cell[axis] < hi[axis] {
+                cell[axis] += 1;
+                break;
+            }
+            cell[axis] = lo[axis];
+            axis += 1;
+        }
+    }
+}
+
+impl<P, F, I> SpaceColonization<P, F, I>
+    where P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    /// The distance from every sample of ```grid``` to the nearest
+    /// segment, in sample order. With ```radii``` (indexed by
+    /// ```NodeIdx```), segments are tubes whose radius is interpolated
+    /// between their nodes, and distances inside are negative. Distances
+    /// are clamped to ```max_dist```, which bounds the work per sample.
+    pub fn distance_field(&self,
+                          grid: &SampleGrid<P>,
+                          radii: Option<&[f32]>,
+                          max_dist: f32)
+                          -> Vec<f32>
+        where P: IndexMut<usize, Output = f32>
+    {
+        assert!(max_dist > 0.0);
+        assert!(grid.counts.len() == P::dim());
+        if let Some(radii) = radii {
+            assert!(radii.len() == self.nodes.len());
+        }
+        let dim = P::dim();
+        let radius = |i: usize| radii.map(|r| r[i]).unwrap_or(0.0);
+        let cell_of = |x: f32| (x / max_dist).floor() as i64;
+
+        // every segment is binned into all cells within ```max_dist``` of
+        // its surface, so that each sample only looks at its own cell. Roots
+        // are included as zero length segments.
+        let mut cells: HashMap<Vec<i64>, Vec<usize>> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let parent = node.parent.0 as usize;
+            let (a, b) = (&self.nodes[parent].position, &node.position);
+            let reach = radius(i).max(radius(parent)) + max_dist;
+            let lo: Vec<i64> = (0..dim).map(|k| cell_of(a[k].min(b[k]) - reach)).collect();
+            let hi: Vec<i64> = (0..dim).map(|k| cell_of(a[k].max(b[k]) + reach)).collect();
+            visit_cells(&lo, &hi, &mut |cell| {
+                cells.entry(cell.to_vec()).or_default().push(i);
+            });
+        }
+
+        (0..grid.num_samples())
+            .map(|s| {
+                let p = grid.sample(s);
+                let cell: Vec<i64> = (0..dim).map(|k| cell_of(p[k])).collect();
+                let mut nearest = max_dist;
+                for &i in cells.get(&cell).map(|v| &v[..]).unwrap_or(&[]) {
+                    let parent = self.nodes[i].parent.0 as usize;
+                    let a = self.nodes[parent].position;
+                    let ab = self.nodes[i].position - a;
+                    let len2 = ab.sqnorm();
+                    let t = if len2 > 0.0 {
+                        ((p - a).dot(&ab) / len2).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+                    let r = radius(parent) + (radius(i) - radius(parent)) * t;
+                    let d = p.dist(&(a + ab * t)) - r;
+                    if d < nearest {
+                        nearest = d;
+                    }
+                }
+                nearest
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,156 @@
+//! Confining growth to a region, e.g. a crown envelope or a stencil shape.
+//! Sampling the attractors inside the region keeps most of the growth in
+//! it, but steps towards attractors near the boundary can still leave it.
+//! The domain is checked at the final position of every new node, after
+//! the quantizer and before the obstacles.
+//!
+//! Like obstacles, the domain can not be saved and is not rescaled by
+//! ```set_world_scale```.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, scalar};
+use obstacle::sdf_normal;
+
+pub enum GrowthDomain<P, S = f32> {
+    Sphere { center: P, radius: S },
+
+    /// An axis aligned box from ```min``` to ```max```.
+    Aabb { min: P, max: P },
+
+    /// The region where the function is negative. It should be a signed
+    /// distance, as its gradient gives the direction of projection.
+    Sdf(Box<dyn Fn(&P) -> S>),
+
+    /// The region where the function is true. Positions outside can't be
+    /// projected, so nodes are not grown there with either mode.
+    Predicate(Box<dyn Fn(&P) -> bool>),
+}
+
+/// What happens to a new node outside the domain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DomainMode {
+    /// The node is not grown in that iteration.
+    Reject,
+
+    /// The node is moved onto the nearest point of the boundary, unless
+    /// that is (almost) where its parent is.
+    Project,
+}
+
+impl<P, S: Scalar> GrowthDomain<P, S> {
+    pub fn sdf<D>(sdf: D) -> GrowthDomain<P, S>
+        where D: Fn(&P) -> S + 'static
+    {
+        GrowthDomain::Sdf(Box::new(sdf))
+    }
+
+    pub fn predicate<D>(predicate: D) -> GrowthDomain<P, S>
+        where D: Fn(&P) -> bool + 'static
+    {
+        GrowthDomain::Predicate(Box::new(predicate))
+    }
+
+    pub fn contains<F>(&self, p: &P) -> bool
+        where P: Position<S, F>,
+              F: Vector<S>
+    {
+        match *self {
+            GrowthDomain::Sphere { ref center, radius } => p.dist(center) <= radius,
+            GrowthDomain::Aabb { min, max } => {
+                (0..P::dim()).all(|i| p[i] >= min[i] && p[i] <= max[i])
+            }
+            GrowthDomain::Sdf(ref sdf) => sdf(p) <= S::zero(),
+            GrowthDomain::Predicate(ref predicate) => predicate(p),
+        }
+    }
+
+    /// The nearest point of the domain to ```p```, which is outside. ```h```
+    /// is the width of the central differences of an SDF.
+    fn project<F>(&self, p: &P, h: S) -> Option<P>
+        where P: Position<S, F>,
+              F: Vector<S> + Copy
+    {
+        match *self {
+            GrowthDomain::Sphere { center, radius } => {
+                let offset = *p - center;
+                let len = offset.norm();
+                if len > S::zero() {
+                    Some(center + offset * (radius / len))
+                } else {
+                    None
+                }
+            }
+            GrowthDomain::Aabb { min, max } => {
+                let mut projected = *p;
+                for i in 0..P::dim() {
+                    projected[i] = projected[i].max(min[i]).min(max[i]);
+                }
+                Some(projected)
+            }
+            GrowthDomain::Sdf(ref sdf) => {
+                let mut projected = *p;
+                // a few Newton steps towards the zero level set.
+                for _ in 0..4 {
+                    let d = sdf(&projected);
+                    if d <= S::zero() {
+                        return Some(projected);
+                    }
+                    let normal = sdf_normal(|p| sdf(p), &projected, h)?;
+                    projected = projected + normal * -d;
+                }
+                if sdf(&projected) <= h {
+                    Some(projected)
+                } else {
+                    None
+                }
+            }
+            GrowthDomain::Predicate(_) => None,
+        }
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Keeps new nodes inside ```domain```. Existing nodes are not moved.
+    pub fn set_growth_domain(&mut self, domain: GrowthDomain<P, S>, mode: DomainMode) {
+        self.growth_domain = Some((domain, mode));
+    }
+
+    pub fn clear_growth_domain(&mut self) {
+        self.growth_domain = None;
+    }
+
+    pub fn growth_domain(&self) -> Option<(&GrowthDomain<P, S>, DomainMode)> {
+        self.growth_domain.as_ref().map(|&(ref domain, mode)| (domain, mode))
+    }
+
+    /// Where the node at ```from``` grows when it would grow to ```to```,
+    /// or ```None``` if it doesn't grow.
+    pub(crate) fn constrain_to_domain(&self, from: &P, to: P) -> Option<P> {
+        let (domain, mode) = match self.growth_domain {
+            Some((ref domain, mode)) => (domain, mode),
+            None => return Some(to),
+        };
+        if domain.contains(&to) {
+            return Some(to);
+        }
+        if mode == DomainMode::Reject {
+            return None;
+        }
+
+        let length = to.dist(from);
+        let projected = domain.project(&to, scalar::<S>(0.01) * length)?;
+        if projected.dist(from) < scalar::<S>(0.01) * length {
+            // the parent is on the boundary and the step leads straight out.
+            None
+        } else {
+            Some(projected)
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Limiting the nodes the attractors search, to keep long simulations
+//! fast. Most nodes of a grown tree are buried inside it and never grow
+//! again, but every attractor still checks them in every iteration.
+//!
+//! With dormancy, a node which has not grown a child for a number of
+//! iterations is skipped by the attractors: it is neither attracted nor
+//! connects. Fields and node targeting still act on it, and it wakes up
+//! again when it grows. Nodes near new attractors have to be woken
+//! explicitly, e.g. with ```wake_nodes_near```.
+//!
+//! ```set_use_last_n_nodes``` is a cruder heuristic which only searches the
+//! most recently added nodes.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Dormancy {
+    /// The number of iterations without growth after which a node becomes
+    /// dormant.
+    pub(crate) after: u32,
+
+    /// The first iteration every node counts as fresh in, indexed by
+    /// ```NodeIdx```. Set when it is added, grows or is woken.
+    pub(crate) since: Vec<u32>,
+}
+
+impl Dormancy {
+    pub(crate) fn is_dormant(&self, node: usize, current_iteration: u32) -> bool {
+        current_iteration >= self.since[node].saturating_add(self.after)
+    }
+
+    /// See ```SegmentAttributes::remap```.
+    pub(crate) fn remap(&mut self, sources: &[usize]) {
+        self.since = sources.iter().map(|&i| self.since[i]).collect();
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Only the ```n``` most recently added nodes take part in an
+    /// iteration, older nodes never grow again. ```None``` uses all nodes.
+    pub fn set_use_last_n_nodes(&mut self, n: Option<usize>) {
+        self.use_last_n_nodes = n;
+    }
+
+    pub fn use_last_n_nodes(&self) -> Option<usize> {
+        self.use_last_n_nodes
+    }
+
+    /// Lets nodes which have not grown for ```after``` iterations fall
+    /// dormant. All existing nodes start fresh. ```None``` disables
+    /// dormancy and wakes all nodes.
+    pub fn set_dormancy(&mut self, after: Option<u32>) {
+        self.dormancy = after.map(|after| {
+            assert!(after > 0);
+            Dormancy {
+                after,
+                since: vec![self.next_iteration; self.nodes.len()],
+            }
+        });
+    }
+
+    pub fn dormancy(&self) -> Option<u32> {
+        self.dormancy.as_ref().map(|dormancy| dormancy.after)
+    }
+
+    /// Whether the attractors skip ```node``` in the next iteration,
+    /// because it is dormant or trapped (see
+    /// ```set_oscillation_detection```).
+    pub fn is_dormant(&self, node: NodeIdx) -> bool {
+        let dormant = match self.dormancy {
+            Some(ref dormancy) => dormancy.is_dormant(node.0 as usize, self.next_iteration),
+            None => false,
+        };
+        dormant || self.is_trapped(node)
+    }
+
+    /// The number of nodes the attractors skip in the next iteration.
+    pub fn num_dormant(&self) -> usize {
+        if self.dormancy.is_none() && self.oscillation.is_none() {
+            return 0;
+        }
+        (0..self.nodes.len()).filter(|&i| self.is_dormant(NodeIdx::of(i))).count()
+    }
+
+    /// Lets ```node``` be searched for another ```dormancy``` iterations,
+    /// and releases it if it was trapped.
+    pub fn wake_node(&mut self, node: NodeIdx) {
+        self.reset_dormancy(node);
+        self.set_stalls(node.0 as usize, 0);
+    }
+
+    /// Restarts the countdown of ```node```, which has grown. Unlike
+    /// ```wake_node```, this keeps its stalls (see
+    /// ```set_oscillation_detection```).
+    pub(crate) fn reset_dormancy(&mut self, node: NodeIdx) {
+        let (node, next_iteration) = (node.0 as usize, self.next_iteration);
+        let since = match self.dormancy {
+            Some(ref dormancy) if dormancy.since[node] != next_iteration => dormancy.since[node],
+            _ => return,
+        };
+        self.note_dormancy(node, since);
+        self.dormancy.as_mut().unwrap().since[node] = next_iteration;
+    }
+
+    /// Wakes all nodes within ```distance``` of ```position``` (with the
+    /// metric or manifold, if set), e.g. after adding attractors there.
+    pub fn wake_nodes_near(&mut self, position: &P, distance: S) {
+        let sqdist = distance * distance;
+        let near: Vec<usize> = (0..self.nodes.len())
+                                   .filter(|&i| {
+                                       self.sqdist_to_node(&self.nodes[i].position, position).0 <
+                                       sqdist
+                                   })
+                                   .collect();
+        for i in near {
+            self.wake_node(NodeIdx::of(i));
+        }
+    }
+
+    pub fn wake_all_nodes(&mut self) {
+        for i in 0..self.nodes.len() {
+            self.wake_node(NodeIdx::of(i));
+        }
+    }
+}
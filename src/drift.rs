@@ -0,0 +1,93 @@
+//! Moving attractors, so that structures can chase moving targets (e.g.
+//! grow towards a moving light source). Attractors are moved at the start
+//! of every iteration, first by their own velocity and then by the motion
+//! closure. Between iterations, ```update_attractors``` can move them (or
+//! change any other field) from outside. The general implementation has no
+//! attractor index that would need updating, so moving attractors costs no
+//! more than the move itself.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Attractor};
+use record::Event;
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Adds an attractor which moves by ```velocity``` every iteration.
+    pub fn add_moving_attractor(&mut self, attractor: Attractor<P, I, S>, velocity: F) {
+        self.push_attractor(attractor);
+        let last = self.attractor_meta.len() - 1;
+        self.attractor_meta[last].velocity = Some(velocity);
+        self.prefilter_new_attractor();
+    }
+
+    /// Moves every attractor to ```motion(position, iteration)``` at the
+    /// start of each iteration. Clustered super-attractors are not moved.
+    pub fn set_attractor_motion<M>(&mut self, motion: M)
+        where M: Fn(&P, u32) -> P + 'static
+    {
+        self.attractor_motion = Some(Box::new(motion));
+    }
+
+    pub fn clear_attractor_motion(&mut self) {
+        self.attractor_motion = None;
+    }
+
+    /// Calls ```update``` with every attractor, e.g. to move them to the
+    /// latest positions of agents between iterations. Unlike
+    /// ```attractors_mut```, moves are recorded (see ```start_recording```)
+    /// and rewound (see ```rewind```).
+    /// Clustered super-attractors are skipped.
+    pub fn update_attractors<C>(&mut self, mut update: C)
+        where C: FnMut(&mut Attractor<P, I, S>)
+    {
+        for idx in 0..self.attractors.len() {
+            if self.is_super_attractor(idx) {
+                continue;
+            }
+            let old = self.attractors[idx].position;
+            self.note_attractor_state(idx);
+            update(&mut self.attractors[idx]);
+            let position = self.attractors[idx].position;
+            if position != old {
+                self.record(Event::AttractorMoved {
+                    index: idx,
+                    position,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn move_attractors(&mut self, current_iteration: u32) {
+        let moving = self.attractor_motion.is_some() ||
+                     self.attractor_meta.iter().any(|m| m.velocity.is_some());
+        if !moving {
+            return;
+        }
+        for idx in 0..self.attractors.len() {
+            let old = self.attractors[idx].position;
+            let mut position = match self.attractor_meta[idx].velocity {
+                Some(velocity) => old + velocity,
+                None => old,
+            };
+            if let Some(ref motion) = self.attractor_motion {
+                if !self.is_super_attractor(idx) {
+                    position = motion(&position, current_iteration);
+                }
+            }
+            if position != old {
+                let event = Event::AttractorMoved {
+                    index: idx,
+                    position,
+                };
+                self.note_attractor(&event);
+                self.attractors[idx].position = position;
+                self.record(event);
+            }
+        }
+    }
+}
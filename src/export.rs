@@ -0,0 +1,518 @@
+//! Writing the grown structure to common file formats.
+//!
+//! Node ids in all formats are the ```NodeIdx``` of the nodes. Nodes are
+//! only ever appended while growing, so the ids are stable across
+//! iterations, which allows to assemble growth animations from a sequence
+//! of frames (see ```FrameExporter```).
+//!
+//! Trees whose root was given a ```Material``` are tagged with it in every
+//! format, so that multi-tree scenes stay distinguishable.
+
+use position::{Position, Vector, Scalar};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use super::{SpaceColonization, NodeIdx, scalar};
+use roots;
+use view::SpaceColonizationView;
+
+/// The file formats supported by ```SpaceColonization::export```.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// Line segments projected onto the x/y plane.
+    Svg,
+
+    /// Vertices and polylines (```l``` elements).
+    Obj,
+
+    /// ```{"iteration": n, "nodes": [{"id", "parent", "position"}, ...],
+    /// "bridges": [[tip, target], ...]}```.
+    /// Nodes of trees with a material also have a ```"material"``` id.
+    Json,
+
+    /// ASCII PLY with a colored vertex per node and an edge per segment and
+    /// bridge.
+    Ply,
+}
+
+/// A material id and display color, assigned to a tree via its root.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Material {
+    pub id: u32,
+
+    /// RGB color.
+    pub color: [u8; 3],
+}
+
+impl Material {
+    pub fn new(id: u32, color: [u8; 3]) -> Material {
+        Material {
+            id,
+            color,
+        }
+    }
+
+    /// The name used in OBJ and MTL files.
+    pub fn name(&self) -> String {
+        format!("material-{}", self.id)
+    }
+
+    /// The color as ```#rrggbb```.
+    pub fn hex_color(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.color[0], self.color[1], self.color[2])
+    }
+}
+
+impl Format {
+    /// The format of a file extension (```svg```, ```obj```, ```json``` or
+    /// ```ply```).
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension {
+            "svg" => Some(Format::Svg),
+            "obj" => Some(Format::Obj),
+            "json" => Some(Format::Json),
+            "ply" => Some(Format::Ply),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Format::Svg => "svg",
+            Format::Obj => "obj",
+            Format::Json => "json",
+            Format::Ply => "ply",
+        }
+    }
+}
+
+fn coords<P, F, S>(p: &P) -> Vec<S>
+    where P: Position<S, F>,
+          F: Vector<S>,
+          S: Scalar
+{
+    (0..P::dim()).map(|i| p[i]).collect()
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    pub fn export<W: Write>(&self, w: &mut W, format: Format) -> io::Result<()> {
+        self.view().export(w, format)
+    }
+
+    /// Assigns ```material``` to the tree of ```root```.
+    pub fn set_root_material(&mut self, root: NodeIdx, material: Material) {
+        self.root_state_mut(root).material = Some(material);
+    }
+
+    /// The material of the tree ```node``` belongs to.
+    pub fn material_of(&self, node: NodeIdx) -> Option<Material> {
+        self.view().material_of(node)
+    }
+
+    /// Writes all segments as SVG lines of ```stroke_width```, followed by
+    /// the bridges (see ```bridges```) with the class ```bridge```. The view
+    /// box encloses all nodes.
+    pub fn write_svg<W: Write>(&self, w: &mut W, stroke_width: S) -> io::Result<()> {
+        self.view().write_svg(w, stroke_width)
+    }
+
+    /// Writes one vertex per node (2D positions get ```z = 0```) and one
+    /// line element per segment. Segments of trees with a material are
+    /// preceded by ```usemtl```, see ```write_mtl```. Bridges (see
+    /// ```bridges```) follow in the group ```bridges```.
+    pub fn write_obj<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.view().write_obj(w)
+    }
+
+    /// Writes an MTL library with the materials of all trees.
+    pub fn write_mtl<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.view().write_mtl(w)
+    }
+
+    /// Writes one vertex per node, colored by its tree's material (white
+    /// without one), and one edge per segment and bridge.
+    pub fn write_ply<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.view().write_ply(w)
+    }
+
+    /// Like ```write_ply```, with additional vertex properties.
+    pub fn write_ply_with<W: Write>(&self,
+                                    w: &mut W,
+                                    attributes: PlyAttributes<S>)
+                                    -> io::Result<()> {
+        self.view().write_ply_with(w, attributes)
+    }
+
+    pub fn write_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.view().write_json(w)
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    pub fn export<W: Write>(&self, w: &mut W, format: Format) -> io::Result<()> {
+        match format {
+            Format::Svg => self.write_svg(w, scalar(0.005)),
+            Format::Obj => self.write_obj(w),
+            Format::Json => self.write_json(w),
+            Format::Ply => self.write_ply(w),
+        }
+    }
+
+    /// See ```SpaceColonization::material_of```.
+    pub fn material_of(&self, node: NodeIdx) -> Option<Material> {
+        roots::find(self.roots, self.nodes[node.0 as usize].root).material
+    }
+
+    /// See ```SpaceColonization::write_svg```.
+    pub fn write_svg<W: Write>(&self, w: &mut W, stroke_width: S) -> io::Result<()> {
+        let (mut min_x, mut min_y) = (S::infinity(), S::infinity());
+        let (mut max_x, mut max_y) = (S::neg_infinity(), S::neg_infinity());
+        for p in self.positions.iter() {
+            min_x = min_x.min(p[0]);
+            min_y = min_y.min(p[1]);
+            max_x = max_x.max(p[0]);
+            max_y = max_y.max(p[1]);
+        }
+        if self.positions.is_empty() {
+            min_x = S::zero();
+            min_y = S::zero();
+            max_x = S::zero();
+            max_y = S::zero();
+        }
+        let margin = stroke_width;
+
+        writeln!(w,
+                 "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+                 min_x - margin,
+                 min_y - margin,
+                 max_x - min_x + margin + margin,
+                 max_y - min_y + margin + margin)?;
+        writeln!(w,
+                 "<g stroke=\"black\" stroke-width=\"{}\" stroke-linecap=\"round\">",
+                 stroke_width)?;
+        for (i, &parent) in self.parents.iter().enumerate() {
+            let parent = parent as usize;
+            if parent != i {
+                let (a, b) = (&self.positions[i], &self.positions[parent]);
+                write!(w,
+                       "<line id=\"n{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"",
+                       i,
+                       b[0],
+                       b[1],
+                       a[0],
+                       a[1])?;
+                match self.material_of(NodeIdx::of(i)) {
+                    Some(m) => {
+                        writeln!(w, " class=\"{}\" stroke=\"{}\"/>", m.name(), m.hex_color())?
+                    }
+                    None => writeln!(w, "/>")?,
+                }
+            }
+        }
+        for &(tip, target) in self.bridges.iter() {
+            let (a, b) = (&self.positions[tip.0 as usize], &self.positions[target.0 as usize]);
+            writeln!(w,
+                     "<line class=\"bridge\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                     a[0],
+                     a[1],
+                     b[0],
+                     b[1])?;
+        }
+        writeln!(w, "</g>")?;
+        writeln!(w, "</svg>")
+    }
+
+    /// See ```SpaceColonization::write_obj```.
+    pub fn write_obj<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for p in self.positions.iter() {
+            let c = coords(p);
+            writeln!(w,
+                     "v {} {} {}",
+                     c[0],
+                     c.get(1).cloned().unwrap_or(S::zero()),
+                     c.get(2).cloned().unwrap_or(S::zero()))?;
+        }
+        // untagged segments first, as usemtl applies to everything below.
+        let mut by_material: BTreeMap<Option<u32>, Vec<usize>> = BTreeMap::new();
+        for (i, &parent) in self.parents.iter().enumerate() {
+            if parent as usize != i {
+                let id = self.material_of(NodeIdx::of(i)).map(|m| m.id);
+                by_material.entry(id).or_default().push(i);
+            }
+        }
+        for nodes in by_material.values() {
+            if let Some(m) = self.material_of(NodeIdx::of(nodes[0])) {
+                writeln!(w, "usemtl {}", m.name())?;
+            }
+            for &i in nodes.iter() {
+                // OBJ indices start at 1.
+                writeln!(w, "l {} {}", self.parents[i] as usize + 1, i + 1)?;
+            }
+        }
+        if !self.bridges.is_empty() {
+            writeln!(w, "g bridges")?;
+            for &(tip, target) in self.bridges.iter() {
+                writeln!(w, "l {} {}", tip.0 + 1, target.0 + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// See ```SpaceColonization::write_mtl```.
+    pub fn write_mtl<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut materials: Vec<Material> = Vec::new();
+        for root in self.roots.iter() {
+            match root.material {
+                Some(m) if !materials.iter().any(|other| other.id == m.id) => materials.push(m),
+                _ => {}
+            }
+        }
+        for m in materials.iter() {
+            writeln!(w, "newmtl {}", m.name())?;
+            writeln!(w,
+                     "Kd {} {} {}",
+                     m.color[0] as f32 / 255.0,
+                     m.color[1] as f32 / 255.0,
+                     m.color[2] as f32 / 255.0)?;
+        }
+        Ok(())
+    }
+
+    /// See ```SpaceColonization::write_ply```.
+    pub fn write_ply<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_ply_with(w, PlyAttributes::default())
+    }
+
+    /// See ```SpaceColonization::write_ply_with```.
+    pub fn write_ply_with<W: Write>(&self,
+                                    w: &mut W,
+                                    attributes: PlyAttributes<S>)
+                                    -> io::Result<()> {
+        if let Some(radii) = attributes.radii {
+            assert!(radii.len() == self.nodes.len());
+        }
+        let num_edges = self.parents.iter().enumerate().filter(|&(i, &p)| p as usize != i).count() +
+                        self.bridges.len();
+        writeln!(w, "ply")?;
+        writeln!(w, "format ascii 1.0")?;
+        writeln!(w, "element vertex {}", self.positions.len())?;
+        writeln!(w, "property float x")?;
+        writeln!(w, "property float y")?;
+        writeln!(w, "property float z")?;
+        writeln!(w, "property uchar red")?;
+        writeln!(w, "property uchar green")?;
+        writeln!(w, "property uchar blue")?;
+        writeln!(w, "property uint material")?;
+        if attributes.length {
+            writeln!(w, "property uint length")?;
+        }
+        if attributes.branches {
+            writeln!(w, "property uint branches")?;
+        }
+        if attributes.root {
+            writeln!(w, "property uint root")?;
+        }
+        if attributes.radii.is_some() {
+            writeln!(w, "property float radius")?;
+        }
+        writeln!(w, "element edge {}", num_edges)?;
+        writeln!(w, "property int vertex1")?;
+        writeln!(w, "property int vertex2")?;
+        writeln!(w, "end_header")?;
+        for (i, p) in self.positions.iter().enumerate() {
+            let c = coords(p);
+            let (color, id) = match self.material_of(NodeIdx::of(i)) {
+                Some(m) => (m.color, m.id),
+                None => ([255, 255, 255], 0),
+            };
+            write!(w,
+                   "{} {} {} {} {} {} {}",
+                   c[0],
+                   c.get(1).cloned().unwrap_or(S::zero()),
+                   c.get(2).cloned().unwrap_or(S::zero()),
+                   color[0],
+                   color[1],
+                   color[2],
+                   id)?;
+            let node = &self.nodes[i];
+            if attributes.length {
+                write!(w, " {}", node.length)?;
+            }
+            if attributes.branches {
+                write!(w, " {}", node.branches)?;
+            }
+            if attributes.root {
+                write!(w, " {}", node.root.0)?;
+            }
+            if let Some(radii) = attributes.radii {
+                write!(w, " {}", radii[i])?;
+            }
+            writeln!(w)?;
+        }
+        for (i, &parent) in self.parents.iter().enumerate() {
+            if parent as usize != i {
+                writeln!(w, "{} {}", parent, i)?;
+            }
+        }
+        for &(tip, target) in self.bridges.iter() {
+            writeln!(w, "{} {}", tip.0, target.0)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "{{\"iteration\": {}, \"nodes\": [", self.next_iteration)?;
+        for (i, p) in self.positions.iter().enumerate() {
+            let coords: Vec<String> = coords(p).iter().map(|c| format!("{}", c)).collect();
+            let material = match self.material_of(NodeIdx::of(i)) {
+                Some(m) => format!(", \"material\": {}", m.id),
+                None => String::new(),
+            };
+            writeln!(w,
+                     "  {{\"id\": {}, \"parent\": {}, \"position\": [{}]{}}}{}",
+                     i,
+                     self.parents[i],
+                     coords.join(", "),
+                     material,
+                     if i + 1 < self.positions.len() { "," } else { "" })?;
+        }
+        let bridges: Vec<String> = self.bridges
+                                       .iter()
+                                       .map(|&(tip, target)| format!("[{}, {}]", tip.0, target.0))
+                                       .collect();
+        writeln!(w, "], \"bridges\": [{}]}}", bridges.join(", "))
+    }
+}
+
+/// Optional per node properties of ```write_ply_with```.
+#[derive(Debug, Copy, Clone)]
+pub struct PlyAttributes<'a, S = f32> {
+    /// ```uint length```, the number of segments to the root.
+    pub length: bool,
+
+    /// ```uint branches```, the number of children.
+    pub branches: bool,
+
+    /// ```uint root```, the id of the root node.
+    pub root: bool,
+
+    /// ```float radius```, indexed by ```NodeIdx``` (e.g. from
+    /// ```compute_radii```).
+    pub radii: Option<&'a [S]>,
+}
+
+// derived, it would require ```S: Default```.
+impl<'a, S> Default for PlyAttributes<'a, S> {
+    fn default() -> PlyAttributes<'a, S> {
+        PlyAttributes {
+            length: false,
+            branches: false,
+            root: false,
+            radii: None,
+        }
+    }
+}
+
+/// Writes a frame of the growth animation every ```every``` iterations to
+/// ```directory```, named ```<prefix>_<iteration>.<extension>```.
+pub struct FrameExporter {
+    directory: PathBuf,
+    prefix: String,
+    format: Format,
+    every: u32,
+}
+
+impl FrameExporter {
+    pub fn new<A: AsRef<Path>>(directory: A,
+                               prefix: &str,
+                               format: Format,
+                               every: u32)
+                               -> FrameExporter {
+        assert!(every > 0);
+        FrameExporter {
+            directory: directory.as_ref().to_path_buf(),
+            prefix: prefix.to_string(),
+            format,
+            every,
+        }
+    }
+
+    /// The file name of the frame after ```iteration``` iterations.
+    pub fn frame_path(&self, iteration: u32) -> PathBuf {
+        self.directory.join(format!("{}_{:05}.{}", self.prefix, iteration, self.format.extension()))
+    }
+
+    /// Writes a frame if the number of iterations performed so far is a
+    /// multiple of ```every```. Returns the path of the written file.
+    pub fn export<P, F, I, S>(&self,
+                              sc: &SpaceColonization<P, F, I, S>)
+                              -> io::Result<Option<PathBuf>>
+        where P: Position<S, F> + Debug,
+              F: Vector<S> + Copy + Debug,
+              I: Copy + Default + Debug,
+              S: Scalar + Debug
+    {
+        if sc.next_iteration % self.every != 0 {
+            return Ok(None);
+        }
+        self.export_now(sc).map(Some)
+    }
+
+    /// Writes a frame of the current state regardless of ```every```, e.g.
+    /// the final state.
+    pub fn export_now<P, F, I, S>(&self, sc: &SpaceColonization<P, F, I, S>) -> io::Result<PathBuf>
+        where P: Position<S, F> + Debug,
+              F: Vector<S> + Copy + Debug,
+              I: Copy + Default + Debug,
+              S: Scalar + Debug
+    {
+        let path = self.frame_path(sc.next_iteration);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        sc.export(&mut writer, self.format)?;
+        writer.flush()?;
+        Ok(path)
+    }
+
+    /// Steps ```sc``` until no new nodes are grown (at most
+    /// ```max_iterations``` times), writing the initial state, every
+    /// ```every```-th iteration and the final state. Returns the written
+    /// files.
+    pub fn run<P, F, I, S>(&self,
+                           sc: &mut SpaceColonization<P, F, I, S>,
+                           max_iterations: u32)
+                           -> io::Result<Vec<PathBuf>>
+        where P: Position<S, F> + Debug,
+              F: Vector<S> + Copy + Debug,
+              I: Copy + Default + Debug,
+              S: Scalar + Debug
+    {
+        let mut paths = Vec::new();
+        paths.push(self.export_now(sc)?);
+        for _ in 0..max_iterations {
+            let new_nodes = sc.next().unwrap_or(0);
+            if new_nodes == 0 {
+                break;
+            }
+            if let Some(path) = self.export(sc)? {
+                paths.push(path);
+            }
+        }
+        if paths.last() != Some(&self.frame_path(sc.next_iteration)) {
+            paths.push(self.export_now(sc)?);
+        }
+        Ok(paths)
+    }
+}
@@ -0,0 +1,85 @@
+//! Weakening the pull of an attractor with the distance to the node it
+//! influences, so that the growth front follows the nearby attractors
+//! rather than being pulled as hard by those at the edge of the attraction
+//! radius. The falloff multiplies the ```strength``` of every attractor
+//! (after the ```strength_ramp```).
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Attractor, Easing, NodeIdx};
+
+/// The factor of the strength of an attractor, by the distance to the node
+/// relative to the attraction radius (```t``` in [0, 1]).
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Falloff {
+    /// Full strength within the whole radius (the default).
+    #[default]
+    Constant,
+
+    /// ```1 - t```.
+    Linear,
+
+    /// Full strength within the connect radius, falling off with the
+    /// inverse square of the distance beyond.
+    InverseSquare,
+
+    /// Smoothly from full strength at the node to zero at the radius.
+    Smoothstep,
+
+    /// Called with ```t```. Like the other closures, it is not saved by
+    /// snapshots or parts, where it becomes ```Constant```.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(fn(f32) -> f32),
+}
+
+impl Falloff {
+    /// The factor at ```t```, the distance relative to the attraction
+    /// radius, and ```s```, the distance relative to the connect radius.
+    fn apply(&self, t: f32, s: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::InverseSquare => 1.0 / (s * s).max(1.0),
+            Falloff::Smoothstep => Easing::Smoothstep.apply(1.0 - t),
+            Falloff::Custom(f) => f(t),
+        }
+    }
+
+    /// ```Custom``` replaced by ```Constant```, for saving.
+    pub(crate) fn persistent(&self) -> Falloff {
+        match *self {
+            Falloff::Custom(_) => Falloff::Constant,
+            other => other,
+        }
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Applies to all attractors. Distances are measured with the metric or
+    /// manifold, if set.
+    pub fn set_falloff(&mut self, falloff: Falloff) {
+        self.falloff = falloff;
+    }
+
+    pub fn falloff(&self) -> Falloff {
+        self.falloff
+    }
+
+    /// The factor of the strength of ```ap``` on ```node```.
+    pub(crate) fn falloff_factor(&self, node: NodeIdx, ap: &Attractor<P, I, S>) -> f32 {
+        if let Falloff::Constant = self.falloff {
+            return 1.0;
+        }
+        let sqdist = self.sqdist_to_node(&self.nodes[node.0 as usize].position, &ap.position);
+        let t = (sqdist.0 / ap.attract_dist.0).sqrt();
+        let s = (sqdist.0 / ap.connect_dist.0).sqrt();
+        self.falloff.apply(t.to_f32().unwrap(), s.to_f32().unwrap())
+    }
+}
@@ -0,0 +1,247 @@
+//! A specialized implementation for the common 2D case (e.g. leaf venation).
+//!
+//! Positions are stored as packed ```[f32; 2]``` in flat vectors and nodes
+//! are bucketed in a uniform grid, so that attractors only look at the
+//! nodes in neighbouring cells instead of scanning all nodes. It supports
+//! the classic algorithm only: every attractor uses the same distances and
+//! is killed when a node connects to it.
+//!
+//! ```examples/fast2d.rs``` measures it against ```SpaceColonization``` with
+//! its spatial index (about 2.5x faster with 20000 attractors).
+
+use std::collections::HashMap;
+use super::{IndexType, NodeIdx, SqDist};
+
+pub struct SpaceColonization2D<I>
+    where I: Copy + Default
+{
+    // nodes (struct of arrays)
+    positions: Vec<[f32; 2]>,
+    parents: Vec<u32>,
+    roots: Vec<u32>,
+    lengths: Vec<u32>,
+    branches: Vec<u32>,
+    growth: Vec<[f32; 2]>,
+    growth_count: Vec<u32>,
+    information: Vec<Option<I>>,
+
+    // attractors (struct of arrays)
+    attractor_positions: Vec<[f32; 2]>,
+    attractor_information: Vec<I>,
+
+    grid: HashMap<(i32, i32), Vec<u32>>,
+    cell_size: f32,
+
+    attract_dist: SqDist,
+    connect_dist: SqDist,
+    move_dist: f32,
+    max_length: u32,
+    max_branches: u32,
+}
+
+#[inline]
+fn sqdist(a: &[f32; 2], b: &[f32; 2]) -> f32 {
+    let dx = a[0] - b[0];
+    let dy = a[1] - b[1];
+    dx * dx + dy * dy
+}
+
+impl<I> SpaceColonization2D<I>
+    where I: Copy + Default
+{
+    pub fn new(attract_dist: SqDist,
+               connect_dist: SqDist,
+               max_length: u32,
+               max_branches: u32,
+               move_dist: f32)
+               -> SpaceColonization2D<I> {
+        SpaceColonization2D {
+            positions: Vec::new(),
+            parents: Vec::new(),
+            roots: Vec::new(),
+            lengths: Vec::new(),
+            branches: Vec::new(),
+            growth: Vec::new(),
+            growth_count: Vec::new(),
+            information: Vec::new(),
+            attractor_positions: Vec::new(),
+            attractor_information: Vec::new(),
+            grid: HashMap::new(),
+            // a node further away than one cell can't be influenced.
+            cell_size: attract_dist.0.sqrt().max(connect_dist.0.sqrt()),
+            attract_dist,
+            connect_dist,
+            move_dist,
+            max_length,
+            max_branches,
+        }
+    }
+
+    fn cell_of(&self, position: &[f32; 2]) -> (i32, i32) {
+        ((position[0] / self.cell_size).floor() as i32,
+         (position[1] / self.cell_size).floor() as i32)
+    }
+
+    fn push_node(&mut self, position: [f32; 2], parent: u32, root: u32, length: u32) -> NodeIdx {
+        // nodes are indexed with u32 internally.
+        assert!(self.positions.len() < u32::MAX as usize,
+                "node index overflow");
+        let idx = self.positions.len() as u32;
+        let cell = self.cell_of(&position);
+        self.grid.entry(cell).or_default().push(idx);
+        self.positions.push(position);
+        self.parents.push(parent);
+        self.roots.push(root);
+        self.lengths.push(length);
+        self.branches.push(0);
+        self.growth.push([0.0, 0.0]);
+        self.growth_count.push(0);
+        self.information.push(None);
+        NodeIdx(idx as IndexType)
+    }
+
+    pub fn add_root_node(&mut self, position: [f32; 2]) -> NodeIdx {
+        let idx = self.positions.len() as u32;
+        self.push_node(position, idx, idx, 0)
+    }
+
+    pub fn add_attractor(&mut self, position: [f32; 2], information: I) {
+        self.attractor_positions.push(position);
+        self.attractor_information.push(information);
+    }
+
+    pub fn add_default_attractor(&mut self, position: [f32; 2]) {
+        self.add_attractor(position, I::default());
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn num_attractors(&self) -> usize {
+        self.attractor_positions.len()
+    }
+
+    /// Node positions, indexed by ```NodeIdx```.
+    pub fn positions(&self) -> &[[f32; 2]] {
+        &self.positions
+    }
+
+    /// Parent indices, parallel to ```positions```. Roots are their own
+    /// parent.
+    pub fn parents(&self) -> &[u32] {
+        &self.parents
+    }
+
+    /// Information assigned to each node, parallel to ```positions```.
+    pub fn information(&self) -> &[Option<I>] {
+        &self.information
+    }
+
+    pub fn attractor_positions(&self) -> &[[f32; 2]] {
+        &self.attractor_positions
+    }
+
+    pub fn visit_node_segments<V>(&self, visitor: &mut V)
+        where V: FnMut(&[f32; 2], &[f32; 2])
+    {
+        for (i, &parent) in self.parents.iter().enumerate() {
+            if parent as usize != i {
+                visitor(&self.positions[i], &self.positions[parent as usize]);
+            }
+        }
+    }
+
+    fn is_active(&self, idx: usize) -> bool {
+        self.lengths[idx] < self.max_length && self.branches[idx] < self.max_branches
+    }
+
+    /// The nearest active node within the influence radius of ```position```.
+    fn nearest_active_node(&self, position: &[f32; 2]) -> Option<(u32, f32)> {
+        let (cx, cy) = self.cell_of(position);
+        let mut nearest = None;
+        let mut nearest_dist = self.attract_dist.0.max(self.connect_dist.0);
+        for dx in -1..2 {
+            for dy in -1..2 {
+                if let Some(cell) = self.grid.get(&(cx + dx, cy + dy)) {
+                    for &idx in cell.iter() {
+                        if !self.is_active(idx as usize) {
+                            continue;
+                        }
+                        let d = sqdist(&self.positions[idx as usize], position);
+                        if d < nearest_dist {
+                            nearest_dist = d;
+                            nearest = Some((idx, d));
+                        }
+                    }
+                }
+            }
+        }
+        nearest
+    }
+
+    /// Performs one iteration. Returns the number of new nodes.
+    pub fn step(&mut self) -> usize {
+        let num_nodes = self.positions.len();
+
+        let mut ap_idx = 0;
+        while ap_idx < self.attractor_positions.len() {
+            let ap = self.attractor_positions[ap_idx];
+            match self.nearest_active_node(&ap) {
+                Some((idx, d)) if d < self.connect_dist.0 => {
+                    self.information[idx as usize] = Some(self.attractor_information[ap_idx]);
+                    self.attractor_positions.swap_remove(ap_idx);
+                    self.attractor_information.swap_remove(ap_idx);
+                    continue;
+                }
+                Some((idx, d)) if d < self.attract_dist.0 => {
+                    let len = d.sqrt();
+                    if len > 0.0 {
+                        let node = &self.positions[idx as usize];
+                        let g = &mut self.growth[idx as usize];
+                        g[0] += (ap[0] - node[0]) / len;
+                        g[1] += (ap[1] - node[1]) / len;
+                        self.growth_count[idx as usize] += 1;
+                    }
+                }
+                _ => {}
+            }
+            ap_idx += 1;
+        }
+
+        for i in 0..num_nodes {
+            if self.growth_count[i] == 0 {
+                continue;
+            }
+            let g = self.growth[i];
+            let len = (g[0] * g[0] + g[1] * g[1]).sqrt();
+            if len > 0.0 {
+                let p = self.positions[i];
+                let new_position = [p[0] + g[0] / len * self.move_dist,
+                                    p[1] + g[1] / len * self.move_dist];
+                let (root, length) = (self.roots[i], self.lengths[i] + 1);
+                self.branches[i] += 1;
+                self.push_node(new_position, i as u32, root, length);
+            }
+            self.growth[i] = [0.0, 0.0];
+            self.growth_count[i] = 0;
+        }
+
+        self.positions.len() - num_nodes
+    }
+}
+
+impl<I> Iterator for SpaceColonization2D<I>
+    where I: Copy + Default
+{
+    type Item = usize;
+
+    /// Performs one iteration, see ```step```. Ends once no attractors
+    /// remain.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.attractor_positions.is_empty() {
+            return None;
+        }
+        Some(self.step())
+    }
+}
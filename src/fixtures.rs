@@ -0,0 +1,67 @@
+//! Fixtures shared by the tests.
+
+use position::{Coords, Position, Vector, Scalar};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use std::fmt::Debug;
+use std::str::FromStr;
+use snapshot::Token;
+use {SpaceColonization, Attractor, SqDist, scalar};
+
+/// ```n``` points scattered uniformly in ```[-1, 1]``` along each axis, the
+/// same ones on every call.
+pub fn scattered_points<P, S>(n: usize) -> Vec<P>
+    where P: Coords<S>,
+          S: Scalar
+{
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let dim = P::dim();
+    (0..n)
+        .map(|_| {
+            let mut p = P::origin();
+            for i in 0..dim {
+                p[i] = scalar(2.0 * rng.gen::<f32>() - 1.0);
+            }
+            p
+        })
+        .collect()
+}
+
+/// A simulation growing from ```roots``` into ```num_attractors``` default
+/// attractors at the ```scattered_points```. ```setup``` adjusts every
+/// attractor, given its index.
+pub fn simulation<P, F, I, S, A>(roots: &[P],
+                                 num_attractors: usize,
+                                 mut setup: A)
+                                 -> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug,
+          A: FnMut(usize, &mut Attractor<P, I, S>)
+{
+    let mut sc = SpaceColonization::new(SqDist::from_dist(scalar(0.3)),
+                                        SqDist::from_dist(scalar(0.05)),
+                                        100,
+                                        10,
+                                        scalar(0.03));
+    for &root in roots.iter() {
+        sc.add_root_node(root);
+    }
+    for (i, position) in scattered_points(num_attractors).into_iter().enumerate() {
+        sc.add_default_attractor(position);
+        setup(i, sc.attractors.last_mut().unwrap());
+    }
+    sc
+}
+
+/// The snapshot of ```sc```, for comparing whole simulations.
+pub fn snapshot<P, F, I, S>(sc: &SpaceColonization<P, F, I, S>) -> String
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug + Token,
+          S: Scalar + Debug + FromStr
+{
+    let mut snapshot = Vec::new();
+    sc.write_snapshot(&mut snapshot).unwrap();
+    String::from_utf8(snapshot).unwrap()
+}
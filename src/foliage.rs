@@ -0,0 +1,166 @@
+//! Placement of leaves and buds on the grown skeleton.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, scalar};
+use view::SpaceColonizationView;
+use frames::{perpendicular, binormal};
+
+/// An attachment point for foliage with an orientation frame.
+#[derive(Debug, Copy, Clone)]
+pub struct Anchor<P, F> {
+    /// The node the anchor is attached to.
+    pub node: NodeIdx,
+
+    pub position: P,
+
+    /// Normalized direction of the segment leading to the node.
+    pub direction: F,
+
+    /// A normalized vector perpendicular to ```direction```.
+    pub normal: F,
+}
+
+/// Bud placement along branches following a spiral.
+#[derive(Debug, Copy, Clone)]
+pub struct Phyllotaxis {
+    /// Angle (in radians) around the branch between successive buds.
+    pub divergence_angle: f32,
+
+    /// Distance along the branch between successive buds.
+    pub internode_spacing: f32,
+}
+
+impl Phyllotaxis {
+    /// The golden angle of about 137.5°, found in many plants.
+    pub fn golden(internode_spacing: f32) -> Phyllotaxis {
+        Phyllotaxis {
+            divergence_angle: 137.5f32.to_radians(),
+            internode_spacing,
+        }
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// One anchor at every leaf node (root nodes excluded).
+    pub fn leaf_anchors<'a>(&'a self) -> impl Iterator<Item = Anchor<P, F>> + 'a {
+        self.view().leaf_anchors()
+    }
+
+    /// Anchors at the last ```n``` nodes of every terminal branch, i.e.
+    /// walking from each leaf towards the root until ```n``` nodes are
+    /// visited or a branching point is reached.
+    pub fn leaf_anchors_terminal<'a>(&'a self,
+                                     n: usize)
+                                     -> impl Iterator<Item = Anchor<P, F>> + 'a {
+        self.view().leaf_anchors_terminal(n)
+    }
+
+    /// Walks all branches from the roots outwards and emits a bud every
+    /// ```internode_spacing``` of branch length, each rotated by the
+    /// divergence angle around the branch relative to the previous one.
+    /// The ```direction``` of each anchor is the branch direction, its
+    /// ```normal``` points towards the bud. The reference frame is carried
+    /// along branches so the spiral is continuous. In 2D buds alternate
+    /// between the two sides of a branch.
+    pub fn bud_anchors(&self, phyllotaxis: &Phyllotaxis) -> Vec<Anchor<P, F>> {
+        self.view().bud_anchors(phyllotaxis)
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    fn anchor_at(&self, idx: usize) -> Anchor<P, F> {
+        let node = &self.nodes[idx];
+        let parent = &self.nodes[node.parent.0 as usize];
+        let direction = (node.position - parent.position).normalize();
+        Anchor {
+            node: NodeIdx::of(idx),
+            position: node.position,
+            direction,
+            normal: perpendicular(&direction),
+        }
+    }
+
+    /// See ```SpaceColonization::leaf_anchors```.
+    pub fn leaf_anchors(&self) -> impl Iterator<Item = Anchor<P, F>> + 'a {
+        self.leaf_anchors_terminal(1)
+    }
+
+    /// See ```SpaceColonization::leaf_anchors_terminal```.
+    pub fn leaf_anchors_terminal(&self, n: usize) -> impl Iterator<Item = Anchor<P, F>> + 'a {
+        let mut anchors = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_leaf() || node.is_root() {
+                continue;
+            }
+            let mut idx = i;
+            for _ in 0..n {
+                let current = &self.nodes[idx];
+                if current.is_root() || (idx != i && current.branches > 1) {
+                    break;
+                }
+                anchors.push(self.anchor_at(idx));
+                idx = current.parent.0 as usize;
+            }
+        }
+        anchors.into_iter()
+    }
+
+    /// See ```SpaceColonization::bud_anchors```.
+    pub fn bud_anchors(&self, phyllotaxis: &Phyllotaxis) -> Vec<Anchor<P, F>> {
+        assert!(phyllotaxis.internode_spacing > 0.0);
+
+        let transport = self.transport_normals();
+        let spacing: S = scalar(phyllotaxis.internode_spacing);
+        let mut buds = Vec::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.is_root() {
+                continue;
+            }
+            let parent = &self.nodes[node.parent.0 as usize];
+            let direction = transport.directions[i];
+            let normal = transport.normals[i];
+            if direction.is_zero() {
+                continue;
+            }
+            let binormal = binormal(&direction, &normal);
+
+            let start = transport.arc[node.parent.0 as usize];
+            let end = transport.arc[i];
+
+            let mut k = (start / spacing).floor().to_u32().unwrap() + 1;
+            loop {
+                let s = scalar::<S>(k as f32) * spacing;
+                if s > end {
+                    break;
+                }
+                let angle: S = scalar(k as f32 * phyllotaxis.divergence_angle);
+                let outward = normal * angle.cos() + binormal * angle.sin();
+                buds.push(Anchor {
+                    node: NodeIdx::of(i),
+                    position: parent.position + direction * (s - start),
+                    direction,
+                    normal: if outward.is_zero() {
+                        normal
+                    } else {
+                        outward.normalize()
+                    },
+                });
+                k += 1;
+            }
+        }
+
+        buds
+    }
+}
@@ -0,0 +1,205 @@
+//! Reference frames carried along the branches.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, scalar};
+use view::SpaceColonizationView;
+
+/// Calls ```f``` with the vectors of an orthonormal basis of the space
+/// perpendicular to ```direction```, until it returns ```false```.
+fn orthonormal_subspace_basis<F, S, C>(direction: &F, mut f: C)
+    where F: Vector<S> + Copy,
+          S: Scalar,
+          C: FnMut(F) -> bool
+{
+    let mut basis = vec![direction.normalize()];
+    for axis in 0..F::dim() {
+        // Gram-Schmidt on the canonical basis
+        let mut v = F::basis_element(axis).unwrap();
+        for b in basis.iter() {
+            v = v - *b * v.dot(b);
+        }
+        if v.sqnorm() < scalar(1.0e-6) {
+            continue;
+        }
+        let v = v.normalize();
+        basis.push(v);
+        if !f(v) || basis.len() == F::dim() {
+            return;
+        }
+    }
+}
+
+/// Any normalized vector perpendicular to ```direction```.
+pub fn perpendicular<F, S>(direction: &F) -> F
+    where F: Vector<S> + Copy,
+          S: Scalar
+{
+    let mut normal = F::zero();
+    orthonormal_subspace_basis(direction, |v| {
+        normal = v;
+        false
+    });
+    normal
+}
+
+/// A normalized vector perpendicular to both ```direction``` and
+/// ```normal```, or zero if there is none (in 2D).
+pub fn binormal<F, S>(direction: &F, normal: &F) -> F
+    where F: Vector<S> + Copy,
+          S: Scalar
+{
+    let mut best: F = F::zero();
+    let mut best_sqnorm = S::zero();
+    orthonormal_subspace_basis(direction, |v: F| {
+        let rest = v - *normal * v.dot(normal);
+        let sqnorm = rest.sqnorm();
+        if sqnorm > best_sqnorm {
+            best_sqnorm = sqnorm;
+            best = rest;
+        }
+        true
+    });
+    if best_sqnorm > scalar(1.0e-6) {
+        best.normalize()
+    } else {
+        F::zero()
+    }
+}
+
+/// An orthonormal frame along a branch.
+#[derive(Debug, Copy, Clone)]
+pub struct Frame<F> {
+    /// Unit direction of the branch.
+    pub tangent: F,
+
+    pub normal: F,
+
+    /// Perpendicular to ```tangent``` and ```normal```. Zero in 2D.
+    pub binormal: F,
+}
+
+/// Per node quantities of the segment leading to the node, indexed by
+/// ```NodeIdx```. Root nodes have zero vectors.
+pub(crate) struct Transport<F, S = f32> {
+    /// Arc length from the root.
+    pub arc: Vec<S>,
+
+    /// Normalized direction of the incoming segment.
+    pub directions: Vec<F>,
+
+    /// A normal of the incoming segment, carried along from the parent
+    /// segment by parallel transport, so it does not twist arbitrarily.
+    pub normals: Vec<F>,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Computes a frame for every node, indexed by ```NodeIdx```. The
+    /// tangent is the direction of the segment leading to the node, the
+    /// normal is parallel transported along the branches (and into child
+    /// branches), which minimizes twist. Root nodes take the frame of their
+    /// first child; roots without children get zero vectors.
+    pub fn compute_frames(&self) -> Vec<Frame<F>> {
+        self.view().compute_frames()
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Carries a normal along all branches with the rotation minimizing
+    /// double reflection method (Wang et al. 2008). Child branches start
+    /// from the frame of their parent segment, so the twist is also
+    /// minimized across junctions.
+    pub(crate) fn transport_normals(&self) -> Transport<F, S> {
+        let n = self.nodes.len();
+        let mut arc = vec![S::zero(); n];
+        let mut directions: Vec<F> = vec![F::zero(); n];
+        let mut normals: Vec<F> = vec![F::zero(); n];
+
+        // parents are always stored before their children.
+        for i in 0..n {
+            let node = &self.nodes[i];
+            if node.is_root() {
+                continue;
+            }
+            let parent_idx = node.parent.0 as usize;
+            let segment = node.position - self.nodes[parent_idx].position;
+            let length = segment.norm();
+            arc[i] = arc[parent_idx] + length;
+            if length == S::zero() {
+                directions[i] = directions[parent_idx];
+                normals[i] = normals[parent_idx];
+                continue;
+            }
+            let direction = segment / length;
+
+            let previous = normals[parent_idx];
+            normals[i] = if previous.is_zero() {
+                perpendicular(&direction)
+            } else {
+                // reflect the parent frame across the bisecting plane of the
+                // segment, then across the plane between the tangents.
+                let parent_tangent = directions[parent_idx];
+                let two: S = scalar(2.0);
+                let c1 = length * length;
+                let reflected_normal = previous - segment * (two / c1 * segment.dot(&previous));
+                let reflected_tangent = parent_tangent -
+                                        segment * (two / c1 * segment.dot(&parent_tangent));
+                let v2 = direction - reflected_tangent;
+                let c2 = v2.sqnorm();
+                let normal = if c2 > scalar(1.0e-12) {
+                    reflected_normal - v2 * (two / c2 * v2.dot(&reflected_normal))
+                } else {
+                    reflected_normal
+                };
+                // remove numerical drift.
+                let normal = normal - direction * normal.dot(&direction);
+                if normal.sqnorm() < scalar(1.0e-12) {
+                    perpendicular(&direction)
+                } else {
+                    normal.normalize()
+                }
+            };
+            directions[i] = direction;
+        }
+
+        Transport {
+            arc,
+            directions,
+            normals,
+        }
+    }
+
+    /// See ```SpaceColonization::compute_frames```.
+    pub fn compute_frames(&self) -> Vec<Frame<F>> {
+        let transport = self.transport_normals();
+        let mut frames = Vec::with_capacity(self.nodes.len());
+        for (t, n) in transport.directions.iter().zip(transport.normals.iter()) {
+            frames.push(Frame {
+                tangent: *t,
+                normal: *n,
+                binormal: binormal(t, n),
+            });
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                let parent = node.parent.0 as usize;
+                if self.nodes[parent].is_root() && frames[parent].tangent.is_zero() {
+                    frames[parent] = frames[i];
+                }
+            }
+        }
+
+        frames
+    }
+}
@@ -0,0 +1,95 @@
+//! Exploratory growth into empty space: every iteration, short-lived
+//! attractors are injected slightly ahead of the growing tips, e.g. for
+//! roots searching the soil when the regular attractors are sparse.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Attractor, ConnectAction, NodeIdx, SqDist};
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FrontierInjection<S = f32> {
+    /// How far ahead of a tip, in its growth direction, the attractor is
+    /// placed.
+    pub distance: S,
+
+    pub attract_dist: SqDist<S>,
+    pub connect_dist: SqDist<S>,
+    pub strength: f32,
+
+    /// Number of iterations until an injected attractor expires.
+    pub lifetime: u32,
+
+    /// If set, only tips which no other attractor is attracting get one.
+    pub only_unattracted: bool,
+
+    /// Group of the injected attractors, to tell them apart in the
+    /// statistics.
+    pub group: u32,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Injects attractors ahead of the active tips every iteration.
+    /// ```None``` disables it.
+    pub fn set_frontier_injection(&mut self, injection: Option<FrontierInjection<S>>) {
+        self.frontier_injection = injection;
+    }
+
+    pub(crate) fn inject_frontier_attractors(&mut self,
+                                             current_iteration: u32,
+                                             start_index: usize) {
+        let injection = match self.frontier_injection {
+            Some(injection) => injection,
+            None => return,
+        };
+
+        let mut positions = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate().skip(start_index) {
+            // roots have no growth direction yet.
+            if node.is_root() || !node.is_leaf() ||
+               !node.is_active(&self.roots, self.max_length, self.max_branches) {
+                continue;
+            }
+            if injection.only_unattracted && self.is_attracted(NodeIdx::of(i), current_iteration) {
+                continue;
+            }
+            let parent = &self.nodes[node.parent.0 as usize];
+            let direction = match self.manifold {
+                Some(ref manifold) => manifold.log(&parent.position, &node.position),
+                None => node.position - parent.position,
+            };
+            if direction.sqnorm() == S::zero() {
+                continue;
+            }
+            let ahead = direction.normalize() * injection.distance;
+            positions.push(match self.manifold {
+                Some(ref manifold) => manifold.exp(&node.position, &ahead),
+                None => node.position + ahead,
+            });
+        }
+
+        for position in positions.into_iter() {
+            self.add_attractor(Attractor {
+                attract_dist: injection.attract_dist,
+                connect_dist: injection.connect_dist,
+                strength: injection.strength,
+                position,
+                information: I::default(),
+                connect_action: ConnectAction::KillAttractor,
+                active_from_iteration: current_iteration,
+                active_until_iteration: Some(current_iteration + injection.lifetime),
+                strength_ramp: None,
+                not_for_root: None,
+                not_for_connecting_root: None,
+                min_node_length: None,
+                max_node_length: None,
+                group: injection.group,
+            });
+        }
+    }
+}
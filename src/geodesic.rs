@@ -0,0 +1,138 @@
+//! Approximate geodesic distances on a surface, represented as a graph of
+//! surface sample points (e.g. the vertices and edges of a mesh).
+
+use position::{Position, Vector};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::f32;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use super::{Metric, SqDist};
+
+#[derive(Copy, Clone, PartialEq)]
+struct State {
+    dist: f32,
+    vertex: usize,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    // reversed, so that the BinaryHeap becomes a min-heap.
+    fn cmp(&self, other: &State) -> Ordering {
+        other.dist.partial_cmp(&self.dist).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &State) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A graph metric approximating geodesic distances on a surface.
+///
+/// The distance between two positions is the distance of each position to
+/// its nearest graph vertex plus the shortest path between those vertices.
+/// Shortest paths are computed on demand and cached per target vertex.
+/// Finding the nearest vertex is a linear scan, so this is meant for
+/// moderately sized surface graphs.
+pub struct GeodesicGraph<P, F> {
+    vertices: Vec<P>,
+    adjacency: Vec<Vec<(usize, f32)>>,
+    cache: RefCell<HashMap<usize, Rc<Vec<f32>>>>,
+    _marker: PhantomData<F>,
+}
+
+impl<P, F> GeodesicGraph<P, F>
+    where P: Position<f32, F>,
+          F: Vector<f32>
+{
+    /// Builds the graph from surface points and undirected edges between
+    /// them. Edge weights are the euclidean lengths of the edges.
+    pub fn new(vertices: Vec<P>, edges: &[(usize, usize)]) -> GeodesicGraph<P, F> {
+        let mut adjacency = vec![Vec::new(); vertices.len()];
+        for &(a, b) in edges {
+            let w = vertices[a].dist(&vertices[b]);
+            adjacency[a].push((b, w));
+            adjacency[b].push((a, w));
+        }
+        GeodesicGraph {
+            vertices,
+            adjacency,
+            cache: RefCell::new(HashMap::new()),
+            _marker: PhantomData,
+        }
+    }
+
+    fn nearest_vertex(&self, position: &P) -> Option<usize> {
+        let mut nearest = None;
+        let mut nearest_dist = f32::INFINITY;
+        for (i, v) in self.vertices.iter().enumerate() {
+            let d = v.sqdist(position);
+            if d < nearest_dist {
+                nearest_dist = d;
+                nearest = Some(i);
+            }
+        }
+        nearest
+    }
+
+    /// Shortest path lengths from ```source``` to every vertex (Dijkstra).
+    fn distances_from(&self, source: usize) -> Rc<Vec<f32>> {
+        if let Some(dists) = self.cache.borrow().get(&source) {
+            return dists.clone();
+        }
+
+        let mut dists = vec![f32::INFINITY; self.vertices.len()];
+        let mut heap = BinaryHeap::new();
+        dists[source] = 0.0;
+        heap.push(State {
+            dist: 0.0,
+            vertex: source,
+        });
+
+        while let Some(State { dist, vertex }) = heap.pop() {
+            if dist > dists[vertex] {
+                continue;
+            }
+            for &(next, w) in self.adjacency[vertex].iter() {
+                let d = dist + w;
+                if d < dists[next] {
+                    dists[next] = d;
+                    heap.push(State {
+                        dist: d,
+                        vertex: next,
+                    });
+                }
+            }
+        }
+
+        let dists = Rc::new(dists);
+        self.cache.borrow_mut().insert(source, dists.clone());
+        dists
+    }
+
+    /// The approximate geodesic distance between ```a``` and ```b```.
+    pub fn dist(&self, a: &P, b: &P) -> f32 {
+        match (self.nearest_vertex(a), self.nearest_vertex(b)) {
+            (Some(va), Some(vb)) => {
+                // cache by ```b```, which is the attractor position when
+                // used as a metric.
+                let path = self.distances_from(vb)[va];
+                a.dist(&self.vertices[va]) + path + self.vertices[vb].dist(b)
+            }
+            _ => a.dist(b),
+        }
+    }
+}
+
+impl<P, F> Metric<P> for GeodesicGraph<P, F>
+    where P: Position<f32, F>,
+          F: Vector<f32>
+{
+    fn sqdist(&self, a: &P, b: &P) -> SqDist {
+        SqDist(self.dist(a, b).powi(2))
+    }
+}
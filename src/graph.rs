@@ -0,0 +1,101 @@
+//! The grown structure as a petgraph ```Graph```, for graph algorithms
+//! like shortest paths or centrality (```petgraph``` feature).
+//!
+//! The graph index of every node is its ```NodeIdx```. Edges point from
+//! the parent to the child, and from the tip to the target for bridges
+//! (see ```SpaceColonization::bridges```). Use ```Graph::into_edge_type```
+//! to get an undirected graph.
+
+use petgraph::graph::Graph;
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+use view::SpaceColonizationView;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeData<P, I> {
+    pub idx: NodeIdx,
+    pub position: P,
+    pub root: NodeIdx,
+
+    /// The number of segments to the root.
+    pub length: u32,
+
+    pub information: Option<I>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// From a parent to its child.
+    Segment,
+
+    /// From the tip of a bridge to its target.
+    Bridge,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EdgeData<S = f32> {
+    pub kind: EdgeKind,
+
+    /// The distance between the two nodes.
+    pub length: S,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// All nodes, with an edge per segment and bridge weighted by its
+    /// length.
+    pub fn to_graph(&self) -> Graph<NodeData<P, I>, EdgeData<S>> {
+        self.view().to_graph()
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::to_graph```.
+    pub fn to_graph(&self) -> Graph<NodeData<P, I>, EdgeData<S>> {
+        let mut graph = Graph::with_capacity(self.nodes.len(),
+                                             self.nodes.len() + self.bridges.len());
+        let indices: Vec<_> = self.nodes
+                                  .iter()
+                                  .enumerate()
+                                  .map(|(i, node)| {
+                                      graph.add_node(NodeData {
+                                          idx: NodeIdx::of(i),
+                                          position: node.position,
+                                          root: node.root,
+                                          length: node.length,
+                                          information: node.assigned_information,
+                                      })
+                                  })
+                                  .collect();
+
+        let mut add_edge = |a: NodeIdx, b: NodeIdx, kind: EdgeKind| {
+            let (a, b) = (a.0 as usize, b.0 as usize);
+            let length = self.nodes[a].position.dist(&self.nodes[b].position);
+            graph.add_edge(indices[a],
+                           indices[b],
+                           EdgeData {
+                               kind,
+                               length,
+                           });
+        };
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                add_edge(node.parent, NodeIdx::of(i), EdgeKind::Segment);
+            }
+        }
+        for &(tip, target) in self.bridges.iter() {
+            add_edge(tip, target, EdgeKind::Bridge);
+        }
+        graph
+    }
+}
@@ -0,0 +1,92 @@
+//! How the attraction of a node turns into its next step. A
+//! ```GrowthModel``` accumulates the pull of the attractors on a node and
+//! derives the direction and length of the step from it, e.g. to limit the
+//! curvature of branches or to weight the attractors differently. The flow
+//! field, jitter, growth cone, step scaling, manifold, quantizer and
+//! obstacles apply to the step of any model.
+//!
+//! ```DefaultGrowth``` follows Runions et al.: the node moves by the move
+//! distance along the normalized sum of the unit directions towards its
+//! attractors, weighted by their strength.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, scalar};
+
+/// The state of a node which is about to grow.
+#[derive(Debug, Copy, Clone)]
+pub struct GrowthState<'a, P: 'a, F, S = f32> {
+    pub position: &'a P,
+
+    /// The direction from the parent to the node, ```None``` for roots.
+    pub heading: Option<F>,
+
+    /// The accumulated attraction (see ```GrowthModel::accumulate```),
+    /// including the attraction fields, node targeting and the tropism.
+    pub growth: F,
+
+    /// The number of attractors (and fields) pulling the node, at least 1.
+    pub growth_count: u32,
+
+    /// The move distance of the iteration.
+    pub move_dist: S,
+}
+
+pub trait GrowthModel<P, F, S = f32> {
+    /// Adds the pull of an attractor of ```strength``` in the direction
+    /// ```towards``` (whose length is the distance to the attractor) to
+    /// ```growth```, which starts at zero in every iteration.
+    fn accumulate(&self, growth: F, towards: F, strength: f32) -> F;
+
+    /// The unit direction and the length of the step of a node, ```None```
+    /// if it should not grow.
+    fn step(&self, state: &GrowthState<P, F, S>) -> Option<(F, S)>;
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DefaultGrowth;
+
+impl<P, F, S> GrowthModel<P, F, S> for DefaultGrowth
+    where F: Vector<S> + Copy,
+          S: Scalar
+{
+    fn accumulate(&self, growth: F, towards: F, strength: f32) -> F {
+        growth + towards.normalize() * scalar(strength)
+    }
+
+    fn step(&self, state: &GrowthState<P, F, S>) -> Option<(F, S)> {
+        Some((state.growth.normalize(), state.move_dist))
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    pub fn set_growth_model<M>(&mut self, model: M)
+        where M: GrowthModel<P, F, S> + 'static
+    {
+        self.growth_model = Box::new(model);
+    }
+
+    /// Returns to ```DefaultGrowth```.
+    pub fn reset_growth_model(&mut self) {
+        self.growth_model = Box::new(DefaultGrowth);
+    }
+
+    /// The direction from the parent of ```node``` to it, ```None``` for
+    /// roots.
+    pub(crate) fn heading(&self, node: usize) -> Option<F> {
+        let node = &self.nodes[node];
+        if node.is_root() {
+            return None;
+        }
+        let parent = &self.nodes[node.parent.0 as usize].position;
+        Some(match self.manifold {
+            Some(ref manifold) => manifold.log(&node.position, parent) * -S::one(),
+            None => node.position - *parent,
+        })
+    }
+}
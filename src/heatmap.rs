@@ -0,0 +1,147 @@
+//! Accumulating where attractors shaped the structure: for every cell of a
+//! grid, the number of iterations in which an attractor inside it
+//! influenced (or connected with) a node, next to the number of iterations
+//! in which it contained any active attractor. Cells with a high presence
+//! but no influence hold dead attractor weight.
+//!
+//! The heatmap is not part of snapshots.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::ops::Index;
+use super::{SpaceColonization, scalar};
+use distance::SampleGrid;
+
+/// Counts per cell. The cells are the boxes from ```grid.sample(k)``` to
+/// ```grid.sample(k) + spacing``` along every axis, in sample order.
+#[derive(Debug, Clone)]
+pub struct Heatmap<P> {
+    pub grid: SampleGrid<P>,
+
+    /// Iterations with an influencing attractor.
+    pub influence: Vec<u32>,
+
+    /// Iterations with an active attractor.
+    pub presence: Vec<u32>,
+
+    touched: Vec<bool>,
+}
+
+/// Counts the touched cells towards ```counts``` and resets them.
+fn flush(touched: &mut [bool], counts: &mut [u32]) {
+    for (t, c) in touched.iter_mut().zip(counts.iter_mut()) {
+        if *t {
+            *c += 1;
+            *t = false;
+        }
+    }
+}
+
+impl<P> Heatmap<P> {
+    fn new(grid: SampleGrid<P>) -> Heatmap<P> {
+        let n = grid.counts.iter().product();
+        Heatmap {
+            grid,
+            influence: vec![0; n],
+            presence: vec![0; n],
+            touched: vec![false; n],
+        }
+    }
+
+    /// The flat index of the cell containing ```p```, if any.
+    pub fn cell_of<S>(&self, p: &P) -> Option<usize>
+        where P: Index<usize, Output = S>,
+              S: Scalar
+    {
+        let mut index = 0;
+        let mut stride = 1;
+        for (i, &count) in self.grid.counts.iter().enumerate() {
+            let k = ((p[i] - self.grid.origin[i]) / scalar(self.grid.spacing)).floor();
+            if !(k >= S::zero() && k < scalar(count as f32)) {
+                return None;
+            }
+            index += k.to_usize().unwrap() * stride;
+            stride *= count;
+        }
+        Some(index)
+    }
+
+    fn touch<S>(&mut self, p: &P)
+        where P: Index<usize, Output = S>,
+              S: Scalar
+    {
+        if let Some(cell) = self.cell_of(p) {
+            self.touched[cell] = true;
+        }
+    }
+
+    /// Writes ```counts``` (```influence``` or ```presence```) as a binary
+    /// grayscale PGM image, scaled so that the largest count is white.
+    /// Grids of more than two dimensions are written as a vertical stack of
+    /// their x/y slices.
+    pub fn write_pgm<W: Write>(&self, w: &mut W, counts: &[u32]) -> io::Result<()> {
+        assert!(counts.len() == self.influence.len());
+        let width = self.grid.counts[0];
+        let height = counts.len() / width.max(1);
+        let max = counts.iter().cloned().max().unwrap_or(0).max(1);
+        write!(w, "P5\n{} {}\n255\n", width, height)?;
+        let pixels: Vec<u8> = counts.iter().map(|&c| (c as u64 * 255 / max as u64) as u8).collect();
+        w.write_all(&pixels)
+    }
+
+    /// Writes ```counts``` as raw little-endian ```u32```s in sample order,
+    /// e.g. to load as a volume.
+    pub fn write_raw<W: Write>(&self, w: &mut W, counts: &[u32]) -> io::Result<()> {
+        for &c in counts.iter() {
+            w.write_all(&[c as u8, (c >> 8) as u8, (c >> 16) as u8, (c >> 24) as u8])?;
+        }
+        Ok(())
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Starts accumulating a heatmap over ```grid```, discarding any
+    /// previous one.
+    pub fn enable_heatmap(&mut self, grid: SampleGrid<P>) {
+        self.heatmap = Some(Heatmap::new(grid));
+    }
+
+    /// Stops accumulating and returns the heatmap.
+    pub fn take_heatmap(&mut self) -> Option<Heatmap<P>> {
+        self.heatmap.take()
+    }
+
+    pub fn heatmap(&self) -> Option<&Heatmap<P>> {
+        self.heatmap.as_ref()
+    }
+
+    /// Counts the cells of the attractors active in ```current_iteration```.
+    pub(crate) fn heatmap_presence(&mut self, current_iteration: u32) {
+        if let Some(ref mut heatmap) = self.heatmap {
+            for a in self.attractors.iter().filter(|a| a.is_active_in(current_iteration)) {
+                heatmap.touch(&a.position);
+            }
+            flush(&mut heatmap.touched, &mut heatmap.presence);
+        }
+    }
+
+    /// Marks the cell of an attractor which influenced a node.
+    pub(crate) fn heatmap_influence(&mut self, position: &P) {
+        if let Some(ref mut heatmap) = self.heatmap {
+            heatmap.touch(position);
+        }
+    }
+
+    /// Counts the cells marked by ```heatmap_influence``` this iteration.
+    pub(crate) fn heatmap_flush_influence(&mut self) {
+        if let Some(ref mut heatmap) = self.heatmap {
+            flush(&mut heatmap.touched, &mut heatmap.influence);
+        }
+    }
+}
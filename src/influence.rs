@@ -0,0 +1,57 @@
+//! Step lengths depending on the number of influences of a node, i.e. the
+//! attractors (and attraction fields) pulling it in an iteration. By
+//! default every node grows by the move distance. A step scaling multiplies
+//! the move distance by a function of the number of influences, so that
+//! heavily attracted nodes lunge further, or, inversely, slow down and
+//! branch more densely.
+//!
+//! Presets, to be passed to ```set_step_scaling```:
+//!
+//! * ```logarithmic```: ```1 + ln(n)```, longer steps towards dense
+//!   attractor clouds which grow slowly with ```n```.
+//! * ```square_root```: ```sqrt(n)```, a stronger lunge.
+//! * ```inverse```: ```1 / n```, nodes pulled by many attractors creep,
+//!   so the attractors around them are consumed by more, shorter branches.
+//! * ```at_least(k)```: only nodes with ```k``` or more influences grow.
+//!
+//! All presets return 1 for a single influence.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::SpaceColonization;
+
+pub fn logarithmic(influences: u32) -> f32 {
+    1.0 + (influences as f32).ln()
+}
+
+pub fn square_root(influences: u32) -> f32 {
+    (influences as f32).sqrt()
+}
+
+pub fn inverse(influences: u32) -> f32 {
+    1.0 / influences as f32
+}
+
+pub fn at_least(k: u32) -> impl Fn(u32) -> f32 {
+    move |influences| if influences >= k { 1.0 } else { 0.0 }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Scales the step of every growing node by ```scaling(n)```, where
+    /// ```n > 0``` is the number of its influences. Nodes with a factor of
+    /// 0 (or less) don't grow in that iteration.
+    pub fn set_step_scaling<C>(&mut self, scaling: C)
+        where C: Fn(u32) -> f32 + 'static
+    {
+        self.step_scaling = Some(Box::new(scaling));
+    }
+
+    pub fn clear_step_scaling(&mut self) {
+        self.step_scaling = None;
+    }
+}
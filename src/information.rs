@@ -0,0 +1,76 @@
+//! Passing information along the trees. An attractor only informs the node
+//! which connects with it, so the information ends up at the tips. For
+//! signaling (e.g. nutrients collected at the tips and transported to the
+//! root, or labeling whole subtrees by the class of attractors they
+//! reached), ```propagate_information``` passes the information of every
+//! node on to its parent or its children, merging it with theirs.
+//!
+//! Only the parent links are followed, not ```bridges```.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::SpaceColonization;
+
+/// Which way ```propagate_information``` passes the information.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PropagationDirection {
+    /// From every node to its parent, so that a node ends up with the
+    /// merged information of its whole subtree.
+    TowardRoot,
+
+    /// From every node to its children, so that a node ends up with the
+    /// merged information of all its ancestors.
+    TowardLeaves,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Passes the ```assigned_information``` of every node in
+    /// ```direction``` in one pass. A node without information takes the
+    /// passed one, otherwise it keeps ```merge(own, passed)```, e.g. the
+    /// sum or the maximum. Passed information is merged again further on,
+    /// so the root of a tree ends up with the information of all of its
+    /// nodes. ```received_information``` is not changed.
+    pub fn propagate_information<M>(&mut self, merge: M, direction: PropagationDirection)
+        where M: Fn(I, I) -> I
+    {
+        let merge_into = |own: Option<I>, passed: I| {
+            Some(match own {
+                Some(own) => merge(own, passed),
+                None => passed,
+            })
+        };
+        // children are always stored after their parent.
+        match direction {
+            PropagationDirection::TowardRoot => {
+                for i in (0..self.nodes.len()).rev() {
+                    let parent = self.nodes[i].parent.0 as usize;
+                    if parent == i {
+                        continue;
+                    }
+                    if let Some(passed) = self.nodes[i].assigned_information {
+                        let own = self.nodes[parent].assigned_information;
+                        self.nodes[parent].assigned_information = merge_into(own, passed);
+                    }
+                }
+            }
+            PropagationDirection::TowardLeaves => {
+                for i in 0..self.nodes.len() {
+                    let parent = self.nodes[i].parent.0 as usize;
+                    if parent == i {
+                        continue;
+                    }
+                    if let Some(passed) = self.nodes[parent].assigned_information {
+                        let own = self.nodes[i].assigned_information;
+                        self.nodes[i].assigned_information = merge_into(own, passed);
+                    }
+                }
+            }
+        }
+    }
+}
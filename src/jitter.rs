@@ -0,0 +1,70 @@
+//! Random perturbation of the growth directions, as perfectly deterministic
+//! attraction grows unnaturally straight branches. The direction of every
+//! new node is offset by a random vector before it is normalized and
+//! checked against the growth cone.
+//!
+//! Like ```LengthDecay```, the offsets are hashes of the seed, the iteration
+//! and the node index, so runs are reproducible and snapshots need no random
+//! number generator state.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, scalar};
+use decay::uniform;
+use schedule::Schedule;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Jitter<S = f32> {
+    /// The largest offset along each axis, relative to the unit direction,
+    /// either a constant or a ```Schedule``` over the iterations.
+    pub magnitude: Schedule<S>,
+
+    pub seed: u64,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Perturbs the growth directions. ```None``` disables it.
+    pub fn set_jitter(&mut self, jitter: Option<Jitter<S>>) {
+        self.jitter = jitter;
+    }
+
+    pub fn jitter(&self) -> Option<&Jitter<S>> {
+        self.jitter.as_ref()
+    }
+
+    /// The normalized ```direction``` of the node ```node``` perturbed for
+    /// ```current_iteration```.
+    pub(crate) fn jitter_direction(&self, node: usize, current_iteration: u32, direction: F) -> F {
+        let jitter = match self.jitter {
+            Some(ref jitter) => jitter,
+            None => return direction,
+        };
+        let magnitude = jitter.magnitude.value_at(current_iteration);
+        if magnitude == S::zero() {
+            return direction;
+        }
+        let mut offset: F = F::zero();
+        for axis in 0..P::dim() {
+            let e = match F::basis_element(axis) {
+                Some(e) => e,
+                None => continue,
+            };
+            // an independent draw per axis.
+            let seed = jitter.seed ^ (axis as u64 + 1).wrapping_mul(0xd1b54a32d192ed03);
+            let r = 2.0 * uniform(seed, current_iteration, node) - 1.0;
+            offset = offset + e * (scalar::<S>(r) * magnitude);
+        }
+        let jittered = direction + offset;
+        if jittered.is_zero() {
+            direction
+        } else {
+            jittered.normalize()
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Integer lattice positions.
+//!
+//! Snapping node positions to a lattice and measuring distances on the
+//! integer lattice coordinates gives exactly reproducible, quantized
+//! output (e.g. for pixel-art or grid based generators). Choose a power of
+//! two as ```spacing``` so that lattice points are exactly representable
+//! as floats.
+
+use num::NumCast;
+use position::{Coords, Scalar};
+use super::{Metric, SqDist, scalar};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Lattice {
+    /// Distance between neighbouring lattice points.
+    pub spacing: f32,
+}
+
+impl Lattice {
+    pub fn new(spacing: f32) -> Lattice {
+        assert!(spacing > 0.0);
+        Lattice { spacing }
+    }
+
+    /// The integer lattice coordinate nearest to ```x```.
+    pub fn coord<S: Scalar>(&self, x: S) -> i64 {
+        (x / scalar(self.spacing)).round().to_i64().unwrap()
+    }
+
+    /// The integer lattice coordinates of ```p```.
+    pub fn coords<P, S>(&self, p: &P) -> Vec<i64>
+        where P: Coords<S>,
+              S: Scalar
+    {
+        (0..P::dim()).map(|i| self.coord(p[i])).collect()
+    }
+
+    /// Moves ```p``` to the nearest lattice point.
+    pub fn snap<P, S>(&self, p: &P) -> P
+        where P: Coords<S>,
+              S: Scalar
+    {
+        let mut q = *p;
+        for i in 0..P::dim() {
+            q[i] = <S as NumCast>::from(self.coord(q[i])).unwrap() * scalar(self.spacing);
+        }
+        q
+    }
+}
+
+/// Distances are computed exactly on the integer lattice coordinates.
+impl<P, S> Metric<P, S> for Lattice
+    where P: Coords<S>,
+          S: Scalar
+{
+    fn sqdist(&self, a: &P, b: &P) -> SqDist<S> {
+        let mut sum: i64 = 0;
+        for i in 0..P::dim() {
+            let d = self.coord(a[i]) - self.coord(b[i]);
+            sum += d * d;
+        }
+        let spacing: S = scalar(self.spacing);
+        SqDist(<S as NumCast>::from(sum).unwrap() * spacing * spacing)
+    }
+}
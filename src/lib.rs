@@ -1,23 +1,216 @@
+#[cfg(feature = "nalgebra")]
 extern crate nalgebra as na;
+#[cfg(feature = "glam")]
+extern crate glam;
 extern crate num;
+extern crate rand;
+#[cfg(feature = "trace")]
+extern crate tracing;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+// the code generated by pyo3 refers to ```::core```.
+#[cfg(feature = "python")]
+extern crate core;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "python")]
+extern crate numpy;
+#[cfg(feature = "petgraph")]
+extern crate petgraph;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
 
-use na::{Norm, FloatPnt, FloatVec};
-use num::Zero;
+use num::NumCast;
+use position::{Position, Vector, Scalar};
 use std::cmp;
-use std::fmt::Debug;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug};
+
+// Spans and events are compiled away unless the `trace` feature is enabled.
+#[cfg(feature = "trace")]
+macro_rules! trace_span {
+    ($($arg:tt)*) => { Some(tracing::debug_span!($($arg)*).entered()) }
+}
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_span {
+    ($($arg:tt)*) => { NoSpan }
+}
+/// Stands in for an entered span, so that spans can be dropped explicitly.
+#[cfg(not(feature = "trace"))]
+struct NoSpan;
+#[cfg(feature = "trace")]
+macro_rules! trace_event {
+    ($($arg:tt)*) => { tracing::debug!($($arg)*) }
+}
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_event {
+    ($($arg:tt)*) => {}
+}
+
+pub mod adaptive;
+pub mod anastomosis;
+pub mod attractors;
+pub mod attributes;
+pub mod batch;
+pub mod builder;
+pub mod capture;
+pub mod channel;
+pub mod checkpoint;
+pub mod cluster;
+pub mod competition;
+pub mod cone;
+pub mod dataset;
+pub mod decay;
+pub mod distance;
+pub mod domain;
+pub mod dormancy;
+pub mod drift;
+pub mod export;
+pub mod falloff;
+pub mod fast2d;
+pub mod foliage;
+pub mod frames;
+pub mod frontier;
+pub mod growth;
+pub mod geodesic;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+pub mod heatmap;
+pub mod influence;
+pub mod information;
+pub mod jitter;
+pub mod lattice;
+pub mod lsystem;
+pub mod manifold;
+pub mod measure;
+pub mod merge;
+pub mod mesh;
+pub mod multires;
+pub mod nested;
+pub mod network;
+pub mod obstacle;
+pub mod observer;
+pub mod order;
+pub mod oscillation;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod parts;
+pub mod perception;
+pub mod phases;
+pub mod position;
+pub mod postprocess;
+pub mod prefilter;
+pub mod priority;
+pub mod profiles;
+pub mod projection;
+pub mod provenance;
+pub mod prune;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod radius;
+pub mod record;
+pub mod roots;
+pub mod schedule;
+pub mod skeleton;
+pub mod snapshot;
+pub mod spatial;
+pub mod stats;
+pub mod steps;
+pub mod stream;
+pub mod symmetry;
+pub mod targeting;
+pub mod traversal;
+pub mod tropism;
+#[cfg(feature = "tune")]
+pub mod tune;
+pub mod units;
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod fixtures;
+
+use lattice::Lattice;
+use stats::AttractorCounts;
+use std::collections::BTreeMap;
+use manifold::Manifold;
+use roots::RootState;
+use record::{Event, Recording};
+use std::sync::mpsc::Sender;
+use stream::SegmentMsg;
+use priority::AttractorOrder;
+use cluster::{Cluster, ClusterOptions};
+use adaptive::AdaptiveStep;
+use export::Material;
+use cone::BranchAngle;
+use targeting::NodeTargeting;
+use anastomosis::Anastomosis;
+use network::TreeConnection;
+use prefilter::ConnectPrefilter;
+use falloff::Falloff;
+use frontier::FrontierInjection;
+use decay::LengthDecay;
+use jitter::Jitter;
+use attributes::SegmentAttributes;
+use heatmap::Heatmap;
+use obstacle::ObstacleSet;
+use domain::{GrowthDomain, DomainMode};
+use observer::Observer;
+use growth::{GrowthModel, GrowthState, DefaultGrowth};
+use tropism::Tropism;
+use capture::{CaptureWatch, CaptureDistance};
+use provenance::Provenance;
+use dormancy::Dormancy;
+use oscillation::Oscillation;
+use checkpoint::History;
+use spatial::{Candidates, NodeGrid};
+use steps::{DoneConditions, DoneReason, IterationStats};
+
+pub use fast2d::SpaceColonization2D;
+pub use builder::SpaceColonizationBuilder;
+pub use profiles::Profile;
+pub use schedule::Schedule;
 
 /// Wraps a square distance.
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
-pub struct SqDist(pub f32);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SqDist<S = f32>(pub S);
 
-impl SqDist {
-    pub fn from_dist(d: f32) -> SqDist {
+impl<S: Scalar> SqDist<S> {
+    pub fn from_dist(d: S) -> SqDist<S> {
         SqDist(d.powi(2))
     }
 }
 
+/// Measures the distance between a node and an attractor position.
+pub trait Metric<P, S = f32> {
+    fn sqdist(&self, a: &P, b: &P) -> SqDist<S>;
+}
+
+impl<P, S, M> Metric<P, S> for M
+    where M: Fn(&P, &P) -> SqDist<S>
+{
+    fn sqdist(&self, a: &P, b: &P) -> SqDist<S> {
+        self(a, b)
+    }
+}
+
+/// Converts a constant or an ```f32``` parameter into the scalar type of
+/// the simulation.
+pub(crate) fn scalar<S: Scalar>(x: f32) -> S {
+    <S as NumCast>::from(x).unwrap()
+}
+
 /// What to do when a node `connects` with an attrator.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ConnectAction {
     KillAttractor,
     DisableFor {
@@ -26,17 +219,145 @@ pub enum ConnectAction {
     DisableForConnectingRoot,
 }
 
+/// Which node an attractor connects with when several are within its
+/// ```connect_dist```.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConnectPolicy {
+    /// The first node found, which depends on the node order and the
+    /// spatial index. Slightly faster.
+    FirstFound,
+
+    /// The nearest node.
+    Closest,
+}
+
+/// How a node stores information transmitted by attractors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InformationMode {
+    /// Only the most recently received information is kept
+    /// in ```assigned_information```.
+    KeepLast,
+
+    /// Additionally, every received information is recorded in
+    /// ```received_information``` together with the iteration it
+    /// was received in.
+    Accumulate,
+}
+
+/// Easing curve used by ```StrengthRamp``` and ```Schedule```.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Easing {
+    Linear,
+    Smoothstep,
+}
+
+impl Easing {
+    /// Maps ```t``` in [0, 1] onto [0, 1].
+    fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::Smoothstep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Ramps the strength of an attractor up at the start and down
+/// at the end of its active window.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StrengthRamp {
+    /// Number of iterations after ```active_from_iteration``` until
+    /// full strength is reached.
+    pub ease_in: u32,
+
+    /// Number of iterations before ```active_until_iteration``` in
+    /// which the strength fades out.
+    pub ease_out: u32,
+
+    pub easing: Easing,
+}
+
+/// How a flow field steers the direction of growth.
+#[derive(Debug, Copy, Clone)]
+pub enum FlowMode {
+    /// Linearly blends the attracted direction with the flow direction.
+    /// A weight of 0.0 ignores the flow, 1.0 follows it exclusively.
+    Blend(f32),
+
+    /// Projects the attracted direction onto the flow line, so that
+    /// growth strictly follows the flow (forwards or backwards,
+    /// whichever is closer to the attracted direction).
+    Project,
+}
+
+/// Couples the size of each tree to the resources it captures: every root
+/// owns a biomass budget, which is spent on segment length and replenished
+/// whenever one of its nodes connects with an attractor.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Biomass<S = f32> {
+    /// Initial budget of every root, in units of segment length.
+    pub initial: S,
+
+    /// Added to the budget of a root whenever one of its nodes connects.
+    pub per_connection: S,
+}
+
+// Bookkeeping that is not part of the user-facing ```Attractor```.
 #[derive(Debug, Copy, Clone)]
-pub struct Attractor<P, I: Copy> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct AttractorMeta<F> {
+    // stable across removals of other attractors.
+    id: u64,
+    // the iteration the attractor was added in.
+    added: u32,
+    // root of the tree whose node was last nearest to the attractor.
+    nearest_root: Option<NodeIdx>,
+    // whether more than one tree was nearest to the attractor over time.
+    contested: bool,
+    // the node that connected last, and when.
+    last_connection: Option<(NodeIdx, u32)>,
+    // distance moved per iteration.
+    velocity: Option<F>,
+}
+
+impl<F> AttractorMeta<F> {
+    fn new(id: u64, added: u32) -> AttractorMeta<F> {
+        AttractorMeta {
+            id,
+            added,
+            nearest_root: None,
+            contested: false,
+            last_connection: None,
+            velocity: None,
+        }
+    }
+
+    fn claim(&mut self, root: NodeIdx) {
+        match self.nearest_root {
+            Some(previous) if previous != root => self.contested = true,
+            _ => {}
+        }
+        self.nearest_root = Some(root);
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Attractor<P, I: Copy, S = f32> {
     /// The square distance within which it can influence a Node.
-    pub attract_dist: SqDist,
+    pub attract_dist: SqDist<S>,
 
     /// If there is a node closer than the square root of
     /// this distance, the information is exchanged with the
     /// node and the ```connect_action``` is performed.
     /// This can be for example: kill the attractor,
     /// or disable it for a while.
-    pub connect_dist: SqDist,
+    pub connect_dist: SqDist<S>,
 
     /// The strenght with which it influences a Node.
     pub strength: f32,
@@ -56,6 +377,12 @@ pub struct Attractor<P, I: Copy> {
     /// Starting from which iteration this attractor is active
     pub active_from_iteration: u32,
 
+    /// If set, the attractor expires (is removed) at this iteration.
+    pub active_until_iteration: Option<u32>,
+
+    /// Optionally modulates ```strength``` over the active window.
+    pub strength_ramp: Option<StrengthRamp>,
+
     /// When set, this denies nodes of trees rooted at the specified
     /// NodeIdx to be attracted by this attractor. This allows to 
     /// simultaneous grow connects from Nodes to other Nodes without
@@ -64,11 +391,58 @@ pub struct Attractor<P, I: Copy> {
 
     /// Same as not_for_root, but this is used by ConnectAction::DisableForConnectingRoot
     pub not_for_connecting_root: Option<NodeIdx>,
+
+    /// If set, only nodes with at least this ```length``` (number of
+    /// segments from their root) are influenced, e.g. to only shape fine
+    /// twigs.
+    pub min_node_length: Option<u32>,
+
+    /// If set, only nodes with at most this ```length``` are influenced,
+    /// e.g. to only shape the trunk.
+    pub max_node_length: Option<u32>,
+
+    /// Group used to break down the attractor statistics.
+    pub group: u32,
 }
 
-impl<P, I: Copy> Attractor<P, I> {
+impl<P, I: Copy, S> Attractor<P, I, S> {
+    /// Whether nodes of the given ```length``` can be influenced.
+    fn accepts_node_length(&self, length: u32) -> bool {
+        self.min_node_length.map_or(true, |min| length >= min) &&
+        self.max_node_length.map_or(true, |max| length <= max)
+    }
+
     fn is_active_in(&self, current_iteration: u32) -> bool {
-        current_iteration >= self.active_from_iteration
+        current_iteration >= self.active_from_iteration && !self.is_expired_in(current_iteration)
+    }
+
+    fn is_expired_in(&self, current_iteration: u32) -> bool {
+        match self.active_until_iteration {
+            Some(until) => current_iteration >= until,
+            None => false,
+        }
+    }
+
+    /// The strength in the given iteration, taking the ```strength_ramp```
+    /// into account.
+    pub fn strength_in(&self, current_iteration: u32) -> f32 {
+        let ramp = match self.strength_ramp {
+            Some(ramp) => ramp,
+            None => return self.strength,
+        };
+
+        let mut factor = 1.0;
+        if ramp.ease_in > 0 {
+            let since = current_iteration.saturating_sub(self.active_from_iteration);
+            factor *= ramp.easing.apply(since as f32 / ramp.ease_in as f32);
+        }
+        if let Some(until) = self.active_until_iteration {
+            if ramp.ease_out > 0 {
+                let remaining = until.saturating_sub(current_iteration);
+                factor *= ramp.easing.apply(remaining as f32 / ramp.ease_out as f32);
+            }
+        }
+        self.strength * factor
     }
 
     fn disable_until(&mut self, iteration: u32) {
@@ -76,10 +450,51 @@ impl<P, I: Copy> Attractor<P, I> {
     }
 }
 
+/// The integer type backing ```NodeIdx```. Enable the ```u64-index```
+/// feature for structures with more than 4 billion nodes.
+#[cfg(not(feature = "u64-index"))]
+pub type IndexType = u32;
+#[cfg(feature = "u64-index")]
+pub type IndexType = u64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeIdx(pub IndexType);
+
+impl NodeIdx {
+    /// The largest number of nodes that can be indexed.
+    pub fn max_count() -> usize {
+        usize::try_from(IndexType::MAX).unwrap_or(usize::MAX)
+    }
+
+    /// Checked construction from a ```usize``` index.
+    pub fn from_usize(idx: usize) -> Option<NodeIdx> {
+        IndexType::try_from(idx).ok().filter(|&idx| idx < IndexType::MAX).map(NodeIdx)
+    }
+
+    /// Like ```from_usize``` but panics if ```idx``` does not fit. Only
+    /// used for indices of existing nodes, which always fit.
+    fn of(idx: usize) -> NodeIdx {
+        NodeIdx::from_usize(idx).expect("node index overflow")
+    }
+}
+
+/// Returned when adding a node would exceed the node limit.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub struct NodeIdx(pub u32);
+pub struct CapacityError {
+    pub limit: usize,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node limit of {} reached", self.limit)
+    }
+}
+
+impl Error for CapacityError {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Node<P, F, I>
     where P: Debug,
           F: Debug,
@@ -99,6 +514,9 @@ pub struct Node<P, F, I>
     /// as parent.
     pub branches: u32,
 
+    /// Overrides the simulation's ```max_branches``` for this node.
+    branch_limit: Option<u32>,
+
     /// The node's coordinate position.
     pub position: P,
 
@@ -111,6 +529,11 @@ pub struct Node<P, F, I>
 
     /// For example an attractor could
     pub assigned_information: Option<I>,
+
+    /// All information received by this node and the iteration
+    /// of receipt, oldest first. Only filled in
+    /// ```InformationMode::Accumulate```.
+    pub received_information: Vec<(I, u32)>,
 }
 
 impl<P, F, I> Node<P, F, I>
@@ -118,8 +541,11 @@ impl<P, F, I> Node<P, F, I>
           F: Debug,
           I: Copy + Debug
 {
-    fn transmit_information(&mut self, information: I) {
+    fn transmit_information(&mut self, information: I, iteration: u32, mode: InformationMode) {
         self.assigned_information = Some(information);
+        if mode == InformationMode::Accumulate {
+            self.received_information.push((information, iteration));
+        }
     }
 
     pub fn is_leaf(&self) -> bool {
@@ -135,67 +561,648 @@ impl<P, F, I> Node<P, F, I>
         }
     }
 
-    fn is_active(&self, max_length: u32, max_branches: u32) -> bool {
-        self.length < max_length && self.branches < max_branches
+    /// Whether the node can still grow, with the limits of its tree, whose
+    /// state is in ```roots```.
+    fn is_active<S>(&self,
+                    roots: &[RootState<F, I, S>],
+                    max_length: u32,
+                    max_branches: u32)
+                    -> bool {
+        let config = &roots::find(roots, self.root).config;
+        self.length < config.max_length(max_length) &&
+        self.branches < self.branch_limit(roots, max_branches)
+    }
+
+    /// The maximum number of branches, ```max_branches``` unless
+    /// overridden for the node or its tree.
+    fn branch_limit<S>(&self, roots: &[RootState<F, I, S>], max_branches: u32) -> u32 {
+        match self.branch_limit {
+            Some(limit) => limit,
+            None => roots::find(roots, self.root).config.max_branches(max_branches),
+        }
+    }
+}
+
+/// A function of the position, e.g. a field (see ```add_attraction_field```)
+/// or the quantizer (see ```set_lattice```).
+type PositionFn<P, T> = Box<dyn Fn(&P) -> T>;
+
+/// Moves the attractors, see ```set_attractor_motion```.
+type Motion<P> = Box<dyn Fn(&P, u32) -> P>;
+
+/// The node an attractor connects with, or else the node it attracts.
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct NodeSearch {
+    connect: Option<NodeIdx>,
+    nearest: Option<NodeIdx>,
+}
+
+/// Searches all attractors of a ```SearchContext```, see ```set_parallel```.
+pub(crate) type ParallelSearch<P, F, I, S> = for<'a, 'b> fn(&'b SearchContext<'a, P, F, I, S>)
+                                                             -> Vec<NodeSearch>;
+
+/// The state the attractors search their nodes in. The nodes don't change
+/// until the new nodes are grown, so the searches of one iteration are
+/// independent of each other.
+pub(crate) struct SearchContext<'a, P, F, I, S>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Copy + Debug + 'a,
+          S: 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    roots: &'a [RootState<F, I, S>],
+    attractors: &'a [Attractor<P, I, S>],
+    attractor_meta: &'a [AttractorMeta<F>],
+    // only set with Euclidean distances.
+    grid: Option<&'a NodeGrid>,
+    current_iteration: u32,
+    start_index: usize,
+    cooldown: u32,
+    max_length: u32,
+    max_branches: u32,
+    exclude_roots: bool,
+    perception_cos: Option<S>,
+    connect_policy: ConnectPolicy,
+    dormancy: Option<&'a Dormancy>,
+    oscillation: Option<&'a Oscillation<S>>,
+}
+
+impl<'a, P, F, I, S> SearchContext<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Searches the nodes of the active attractor at ```ap_idx```.
+    pub(crate) fn search(&self,
+                         ap_idx: usize,
+                         metric: Option<&dyn Metric<P, S>>,
+                         manifold: Option<&dyn Manifold<P, F>>)
+                         -> NodeSearch {
+        let ap = &self.attractors[ap_idx];
+        if !ap.is_active_in(self.current_iteration) {
+            return NodeSearch::default();
+        }
+
+        // the node which recently connected is ignored during the cooldown.
+        let cooling_down = match self.attractor_meta[ap_idx].last_connection {
+            Some((node, since)) if self.current_iteration < since.saturating_add(self.cooldown) => {
+                Some(node)
+            }
+            _ => None,
+        };
+
+        let reach = if ap.connect_dist > ap.attract_dist {
+            ap.connect_dist
+        } else {
+            ap.attract_dist
+        };
+        let candidates = match self.grid {
+            Some(grid) => grid.candidates(&ap.position, reach, self.start_index, self.nodes.len()),
+            None => Candidates::All(self.start_index..self.nodes.len()),
+        };
+
+        // find the node nearest to the `ap` attraction point
+        let mut nearest_node: Option<NodeIdx> = None;
+        let mut nearest_distance = ap.attract_dist;
+        let mut connect_node: Option<NodeIdx> = None;
+        let mut connect_distance = ap.connect_dist;
+        for i in candidates {
+            if let Some(dormancy) = self.dormancy {
+                if dormancy.is_dormant(i, self.current_iteration) {
+                    continue;
+                }
+            }
+            if let Some(oscillation) = self.oscillation {
+                if oscillation.is_trapped(i) {
+                    continue;
+                }
+            }
+            let node = &self.nodes[i];
+            if !node.is_active(self.roots, self.max_length, self.max_branches) {
+                // The node has become inactive
+                continue;
+            }
+
+            // an excluded root only grows its stem and never connects.
+            let excluded_root = self.exclude_roots && node.is_root();
+            if excluded_root && !node.is_leaf() {
+                continue;
+            }
+
+            let node_idx = NodeIdx::of(i);
+            if cooling_down == Some(node_idx) || !ap.accepts_node_length(node.length) {
+                continue;
+            }
+
+            match ap.not_for_root {
+                Some(deny_root) if deny_root == node.root => {
+                    // The attractor is not for this tree node.
+                    continue;
+                }
+                _ => {}
+            }
+
+            match ap.not_for_connecting_root {
+                Some(deny_root) if deny_root == node.root => {
+                    // The attractor is not for this tree node.
+                    continue;
+                }
+                _ => {}
+            }
+
+            let dist = match (metric, manifold) {
+                (Some(metric), _) => metric.sqdist(&node.position, &ap.position),
+                (None, Some(manifold)) => {
+                    SqDist(manifold.log(&node.position, &ap.position).sqnorm())
+                }
+                (None, None) => SqDist(node.position.sqdist(&ap.position)),
+            };
+
+            if dist < ap.connect_dist && !excluded_root {
+                // This node is within the connect radius of a node.
+                if dist < connect_distance {
+                    connect_distance = dist;
+                    connect_node = Some(node_idx);
+                }
+                if self.connect_policy == ConnectPolicy::FirstFound {
+                    // outside the node loop, we perform some action
+                    break;
+                }
+            } else if dist < nearest_distance && self.perceives(node, ap, manifold) {
+                // ```node``` is within the influence of the attraction point,
+                // and it's closer than the currently closest node.
+                nearest_distance = dist;
+                nearest_node = Some(node_idx);
+            }
+        }
+
+        NodeSearch {
+            connect: connect_node,
+            nearest: nearest_node,
+        }
     }
 }
 
-pub struct SpaceColonization<P, F, I>
-    where P: FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug,
-          I: Copy + Default + Debug
+/// The simulation. Every node is stored after its parent (and thus after
+/// its root), so that a single pass in index order visits parents first.
+/// Growing only ever appends nodes; see ```normalize_order``` to also group
+/// the nodes by tree.
+///
+/// ```P``` and ```F``` are the position and vector types, see ```position```
+/// for the supported math libraries. Their scalar type ```S``` defaults to
+/// ```f32```. With ```f64``` (e.g. ```Point3<f64>```), large scenes with
+/// fine steps keep their precision. Growth, the analysis of the grown
+/// structure, exports, snapshots and parts work with any scalar; the few
+/// helpers which are limited to ```f32``` are listed in ```position```.
+/// Dimensionless parameters (strengths, weights, angles and factors),
+/// ```ClusterOptions``` and grid cell sizes are ```f32``` in either case.
+pub struct SpaceColonization<P, F, I, S = f32>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
 {
     nodes: Vec<Node<P, F, I>>,
-    attractors: Vec<Attractor<P, I>>,
-    default_attract_dist: SqDist,
-    default_connect_dist: SqDist,
-    move_dist: f32,
+    // mirrors the node positions and parents in contiguous buffers.
+    positions: Vec<P>,
+    parents: Vec<IndexType>,
+    // the children of every node, in the order they were added.
+    children: Vec<Vec<IndexType>>,
+    // the state of every tree, sorted by root node.
+    roots: Vec<RootState<F, I, S>>,
+    attractors: Vec<Attractor<P, I, S>>,
+    // bookkeeping of each attractor, parallel to attractors.
+    attractor_meta: Vec<AttractorMeta<F>>,
+    attractor_counts: BTreeMap<u32, AttractorCounts>,
+    default_attract_dist: SqDist<S>,
+    default_connect_dist: SqDist<S>,
+    move_dist: Schedule<S>,
     next_iteration: u32,
     max_length: u32,
     max_branches: u32,
+    node_limit: usize,
     use_last_n_nodes: Option<usize>,
+    information_mode: InformationMode,
+    connect_policy: ConnectPolicy,
+    connect_prefilter: Option<ConnectPrefilter>,
+    falloff: Falloff,
+    fields: Vec<PositionFn<P, F>>,
+    flow_field: Option<(PositionFn<P, F>, FlowMode)>,
+    metric: Option<Box<dyn Metric<P, S>>>,
+    manifold: Option<Box<dyn Manifold<P, F>>>,
+    quantizer: Option<PositionFn<P, P>>,
+    biomass: Option<Biomass<S>>,
+    recording: Option<Recording<P>>,
+    segment_sender: Option<Sender<SegmentMsg<P>>>,
+    observers: Vec<Box<dyn Observer<P, I, S>>>,
+    attractor_order: AttractorOrder,
+    attractor_order_refresh: u32,
+    connection_quota: Option<usize>,
+    clusters: Vec<Cluster<P>>,
+    cluster_options: Option<ClusterOptions>,
+    adaptive_step: Option<AdaptiveStep>,
+    connect_cooldown: Option<u32>,
+    exclude_roots: bool,
+    attractor_motion: Option<Motion<P>>,
+    step_scaling: Option<Box<dyn Fn(u32) -> f32>>,
+    growth_model: Box<dyn GrowthModel<P, F, S>>,
+    obstacles: Option<ObstacleSet<P, S>>,
+    growth_domain: Option<(GrowthDomain<P, S>, DomainMode)>,
+    tropism: Option<Tropism<F, S>>,
+    capture_watch: Option<CaptureWatch>,
+    capture_distances: Vec<CaptureDistance<S>>,
+    perception_angle: Option<f32>,
+    branch_angle: Option<BranchAngle>,
+    node_targeting: Option<NodeTargeting<S>>,
+    anastomosis: Option<Anastomosis<S>>,
+    bridges: Vec<(NodeIdx, NodeIdx)>,
+    merge_distance: Option<S>,
+    tree_connections: Vec<TreeConnection>,
+    history: Option<History<P, F, I, S>>,
+    frontier_injection: Option<FrontierInjection<S>>,
+    length_decay: Option<LengthDecay>,
+    jitter: Option<Jitter<S>>,
+    segment_attributes: SegmentAttributes,
+    heatmap: Option<Heatmap<P>>,
+    next_attractor_id: u64,
+    provenance: Option<Provenance>,
+    dormancy: Option<Dormancy>,
+    oscillation: Option<Oscillation<S>>,
+    node_grid: Option<NodeGrid>,
+    parallel_search: Option<ParallelSearch<P, F, I, S>>,
+    done_conditions: DoneConditions,
+    // consecutive iterations without new nodes.
+    quiet_iterations: u32,
+    stop_reason: Option<DoneReason>,
+    // meters per unit.
+    world_scale: f32,
 }
 
-impl<P, F, I> SpaceColonization<P, F, I>
-    where P: FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug,
-          I: Copy + Default + Debug
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
 {
-    pub fn new(default_attract_dist: SqDist,
-               default_connect_dist: SqDist,
+    pub fn new(default_attract_dist: SqDist<S>,
+               default_connect_dist: SqDist<S>,
                max_length: u32,
                max_branches: u32,
-               move_dist: f32)
-               -> SpaceColonization<P, F, I> {
+               move_dist: S)
+               -> SpaceColonization<P, F, I, S> {
         SpaceColonization {
             nodes: Vec::new(),
+            positions: Vec::new(),
+            parents: Vec::new(),
+            children: Vec::new(),
+            roots: Vec::new(),
             attractors: Vec::new(),
-            default_attract_dist: default_attract_dist,
-            default_connect_dist: default_connect_dist,
-            max_length: max_length,
-            max_branches: max_branches,
-            move_dist: move_dist,
+            attractor_meta: Vec::new(),
+            attractor_counts: BTreeMap::new(),
+            default_attract_dist,
+            default_connect_dist,
+            max_length,
+            max_branches,
+            move_dist: Schedule::new(move_dist),
             next_iteration: 0,
-            use_last_n_nodes: None, // XXX
+            node_limit: NodeIdx::max_count(),
+            use_last_n_nodes: None,
+            information_mode: InformationMode::KeepLast,
+            connect_policy: ConnectPolicy::Closest,
+            connect_prefilter: None,
+            falloff: Falloff::Constant,
+            fields: Vec::new(),
+            flow_field: None,
+            metric: None,
+            manifold: None,
+            quantizer: None,
+            biomass: None,
+            recording: None,
+            segment_sender: None,
+            observers: Vec::new(),
+            attractor_order: AttractorOrder::Insertion,
+            attractor_order_refresh: 1,
+            connection_quota: None,
+            clusters: Vec::new(),
+            cluster_options: None,
+            adaptive_step: None,
+            connect_cooldown: None,
+            exclude_roots: false,
+            attractor_motion: None,
+            step_scaling: None,
+            growth_model: Box::new(DefaultGrowth),
+            obstacles: None,
+            growth_domain: None,
+            tropism: None,
+            capture_watch: None,
+            capture_distances: Vec::new(),
+            perception_angle: None,
+            branch_angle: None,
+            node_targeting: None,
+            anastomosis: None,
+            bridges: Vec::new(),
+            merge_distance: None,
+            tree_connections: Vec::new(),
+            history: None,
+            frontier_injection: None,
+            length_decay: None,
+            jitter: None,
+            segment_attributes: SegmentAttributes::default(),
+            heatmap: None,
+            next_attractor_id: 0,
+            provenance: None,
+            dormancy: None,
+            oscillation: None,
+            node_grid: None,
+            parallel_search: None,
+            done_conditions: DoneConditions::default(),
+            quiet_iterations: 0,
+            stop_reason: None,
+            world_scale: 1.0,
+        }
+    }
+
+    /// Like ```new```, with a root at each of ```roots``` and the
+    /// ```attractors``` added in one go. Panics if there are more roots than
+    /// the default node limit.
+    pub fn with_attractors_and_roots<A, R>(default_attract_dist: SqDist<S>,
+                                           default_connect_dist: SqDist<S>,
+                                           max_length: u32,
+                                           max_branches: u32,
+                                           move_dist: S,
+                                           attractors: A,
+                                           roots: R)
+                                           -> SpaceColonization<P, F, I, S>
+        where A: IntoIterator<Item = Attractor<P, I, S>>,
+              R: IntoIterator<Item = P>
+    {
+        let mut sc = SpaceColonization::new(default_attract_dist,
+                                            default_connect_dist,
+                                            max_length,
+                                            max_branches,
+                                            move_dist);
+        let roots = roots.into_iter();
+        let (lower, _) = roots.size_hint();
+        sc.reserve_nodes(lower);
+        for position in roots {
+            sc.add_root_node(position);
+        }
+        sc.extend_attractors(attractors);
+        sc
+    }
+
+    /// Enables the biomass growth limit. The budget of all existing roots
+    /// is reset to ```biomass.initial```.
+    pub fn set_biomass(&mut self, biomass: Biomass<S>) {
+        self.biomass = Some(biomass);
+        for root in self.roots.iter_mut() {
+            root.biomass = biomass.initial;
+        }
+    }
+
+    /// The remaining biomass budget of the tree rooted at ```root```.
+    pub fn remaining_biomass(&self, root: NodeIdx) -> Option<S> {
+        match self.biomass {
+            Some(_) => self.get_node(root).map(|_| self.root_state(root).biomass),
+            None => None,
+        }
+    }
+
+    /// Spends ```amount``` of the biomass of ```root```. Returns false if the
+    /// budget is insufficient.
+    fn consume_biomass(&mut self, root: NodeIdx, amount: S) -> bool {
+        if self.biomass.is_none() {
+            return true;
+        }
+        let root = self.root_state_mut(root);
+        if root.biomass < amount {
+            false
+        } else {
+            root.biomass = root.biomass - amount;
+            true
         }
     }
 
-    pub fn add_attractor(&mut self, attractor: Attractor<P, I>) {
+    /// Snaps every newly grown node onto the ```lattice``` and uses the
+    /// exact lattice distance for attractor association. Root nodes and
+    /// attractors should be placed on lattice points as well, and
+    /// ```move_dist``` should be at least the lattice spacing.
+    pub fn set_lattice(&mut self, lattice: Lattice)
+        where P: 'static
+    {
+        self.quantizer = Some(Box::new(move |p| lattice.snap(p)));
+        self.set_metric(lattice);
+    }
+
+    /// Grows on the given manifold: attractor directions and distances are
+    /// taken along geodesics (unless a ```Metric``` is set), and new nodes
+    /// are placed by following the geodesic from their parent. Root nodes
+    /// and attractors should be placed on the manifold.
+    pub fn set_manifold<M>(&mut self, manifold: M)
+        where M: Manifold<P, F> + 'static
+    {
+        self.manifold = Some(Box::new(manifold));
+    }
+
+    /// Replaces the straight-line distance used to associate attractors
+    /// with nodes, e.g. by a ```geodesic::GeodesicGraph``` when growing on
+    /// a curved surface. The growth direction itself is still straight
+    /// towards the attractor.
+    pub fn set_metric<M>(&mut self, metric: M)
+        where M: Metric<P, S> + 'static
+    {
+        self.metric = Some(Box::new(metric));
+    }
+
+    /// Sets a hard cap on the number of nodes (clamped to
+    /// ```NodeIdx::max_count()```). Once reached, no more nodes are grown
+    /// and ```try_step``` returns an error.
+    pub fn set_node_limit(&mut self, limit: usize) {
+        self.node_limit = cmp::min(limit, NodeIdx::max_count());
+    }
+
+    /// Overrides ```max_branches``` for ```node```, e.g. to keep the first
+    /// trunk node unbranched or to let a hub branch many times. ```None```
+    /// restores the default.
+    pub fn set_node_branch_limit(&mut self, node: NodeIdx, limit: Option<u32>) {
+        self.nodes[node.0 as usize].branch_limit = limit;
+    }
+
+    pub fn node_branch_limit(&self, node: NodeIdx) -> u32 {
+        self.nodes[node.0 as usize].branch_limit(&self.roots, self.max_branches)
+    }
+
+    /// New nodes of the tree of ```root``` start with ```information```
+    /// (e.g. a species or net id) until a connect overwrites it, so that
+    /// every node carries the identity of its tree. Such nodes count as
+    /// informed, e.g. for ```prune_uninformed```. Existing nodes are not
+    /// changed.
+    pub fn set_inherited_information(&mut self, root: NodeIdx, information: Option<I>) {
+        self.root_state_mut(root).inherited_information = information;
+    }
+
+    /// The information new nodes of the tree ```node``` belongs to start
+    /// with.
+    pub fn inherited_information(&self, node: NodeIdx) -> Option<I> {
+        self.root_state(node).inherited_information
+    }
+
+    /// Sets the distance a node grows per iteration, either a constant or
+    /// a ```Schedule``` over the iterations.
+    pub fn set_move_dist<M>(&mut self, move_dist: M)
+        where M: Into<Schedule<S>>
+    {
+        self.move_dist = move_dist.into();
+    }
+
+    /// After a node connected with an attractor, the attractor ignores that
+    /// node for ```cooldown``` iterations, so that attractors which are
+    /// disabled for a while (```ConnectAction::DisableFor```) interact with
+    /// fresh growth instead of reconnecting to the same node. Should be
+    /// longer than the disabled period to have an effect.
+    pub fn set_connect_cooldown(&mut self, cooldown: Option<u32>) {
+        self.connect_cooldown = cooldown;
+    }
+
+    /// Keeps the root nodes as placed seed points: a root never connects
+    /// to (and thus never consumes) an attractor, and is only steered until
+    /// it has grown its first node, so that all branching happens further
+    /// up the tree.
+    pub fn set_exclude_roots(&mut self, exclude: bool) {
+        self.exclude_roots = exclude;
+    }
+
+    pub fn excludes_roots(&self) -> bool {
+        self.exclude_roots
+    }
+
+    pub fn set_information_mode(&mut self, mode: InformationMode) {
+        self.information_mode = mode;
+    }
+
+    pub fn set_connect_policy(&mut self, policy: ConnectPolicy) {
+        self.connect_policy = policy;
+    }
+
+    pub fn connect_policy(&self) -> ConnectPolicy {
+        self.connect_policy
+    }
+
+    /// Adds a continuous attraction field. Every iteration, the field is
+    /// sampled at the position of each active node and the resulting vector
+    /// is added to the node's growth, alongside any discrete attractors.
+    /// Return a zero vector where the field should not cause growth.
+    pub fn add_attraction_field<A>(&mut self, field: A)
+        where A: Fn(&P) -> F + 'static
+    {
+        self.fields.push(Box::new(field));
+    }
+
+    /// Sets a vector field (e.g. a painted direction map) which steers the
+    /// direction of newly grown nodes. Where the field is zero, growth is
+    /// not affected.
+    pub fn set_flow_field<A>(&mut self, field: A, mode: FlowMode)
+        where A: Fn(&P) -> F + 'static
+    {
+        self.flow_field = Some((Box::new(field), mode));
+    }
+
+    pub fn clear_flow_field(&mut self) {
+        self.flow_field = None;
+    }
+
+    /// The square distance between a node at ```node``` and an attractor at
+    /// ```position```, using the metric or manifold if set.
+    fn sqdist_to_node(&self, node: &P, position: &P) -> SqDist<S> {
+        match (self.metric.as_deref(), self.manifold.as_deref()) {
+            (Some(metric), _) => metric.sqdist(node, position),
+            (None, Some(manifold)) => SqDist(manifold.log(node, position).sqnorm()),
+            (None, None) => SqDist(node.sqdist(position)),
+        }
+    }
+
+    /// Steers the normalized growth ```direction``` of a node at ```position```
+    /// according to the flow field.
+    fn steer(&self, position: &P, direction: F) -> F {
+        let (field, mode) = match self.flow_field {
+            Some((ref field, mode)) => (field, mode),
+            None => return direction,
+        };
+
+        let flow = field(position);
+        if flow.is_zero() {
+            return direction;
+        }
+        let flow = flow.normalize();
+
+        match mode {
+            FlowMode::Blend(weight) => {
+                let weight: S = scalar(weight);
+                let blended = direction * (S::one() - weight) + flow * weight;
+                if blended.is_zero() {
+                    direction
+                } else {
+                    blended.normalize()
+                }
+            }
+            FlowMode::Project => {
+                if direction.dot(&flow) < S::zero() {
+                    -flow
+                } else {
+                    flow
+                }
+            }
+        }
+    }
+
+    /// Applies the connect action right away if the attractor is within
+    /// reach of a node, see ```set_connect_prefilter```.
+    pub fn add_attractor(&mut self, attractor: Attractor<P, I, S>) {
+        self.push_attractor(attractor);
+        self.prefilter_new_attractor();
+    }
+
+    fn push_attractor(&mut self, attractor: Attractor<P, I, S>) {
+        self.attractor_counts.entry(attractor.group).or_default().added += 1;
+        let meta = self.new_attractor_meta();
+        let event = Event::AttractorAdded { position: attractor.position };
+        self.note_attractor(&event);
+        self.attractor_meta.push(meta);
+        self.record(event);
         self.attractors.push(attractor);
     }
 
+    fn new_attractor_meta(&mut self) -> AttractorMeta<F> {
+        self.next_attractor_id += 1;
+        AttractorMeta::new(self.next_attractor_id - 1, self.next_iteration)
+    }
+
+    /// Removes the attractor at ```idx``` (swapping in the last one) and
+    /// updates the statistics.
+    fn remove_attractor(&mut self, idx: usize, killed: bool) -> Attractor<P, I, S> {
+        let event = Event::AttractorRemoved {
+            index: idx,
+            killed,
+        };
+        self.note_attractor(&event);
+        let added = self.attractor_meta.swap_remove(idx).added;
+        let attractor = self.attractors.swap_remove(idx);
+        self.record(event);
+        let counts = self.attractor_counts.entry(attractor.group).or_default();
+        if killed {
+            counts.killed += 1;
+            counts.iterations_to_consumption += self.next_iteration.saturating_sub(added) as u64;
+        } else {
+            counts.expired += 1;
+        }
+        attractor
+    }
+
     pub fn add_default_attractor(&mut self, position: P) {
-        self.attractors.push(Attractor {
-            attract_dist: self.default_attract_dist,
-            connect_dist: self.default_connect_dist,
-            strength: 1.0,
-            position: position,
-            information: I::default(),
-            connect_action: ConnectAction::KillAttractor,
-            active_from_iteration: 0,
-            not_for_root: None,
-            not_for_connecting_root: None,
-        });
+        let attractor = self.default_attractor(position);
+        self.add_attractor(attractor);
     }
 
     pub fn add_root_node(&mut self, position: P) -> NodeIdx {
@@ -203,24 +1210,108 @@ impl<P, F, I> SpaceColonization<P, F, I>
     }
 
     /// Returns the root node's index.
+    /// Panics if the node limit is reached, see ```try_add_root_node```.
     pub fn add_root_node_with_information(&mut self,
                                           position: P,
                                           information: Option<I>)
                                           -> NodeIdx {
+        self.try_add_root_node(position, information).unwrap()
+    }
+
+    /// Adds a root whose tree is tagged with ```material``` in exports.
+    /// Panics if the node limit is reached.
+    pub fn add_root_node_with_material(&mut self, position: P, material: Material) -> NodeIdx {
+        let root = self.add_root_node(position);
+        self.root_state_mut(root).material = Some(material);
+        root
+    }
+
+    /// Returns the root node's index, or an error if the node limit is
+    /// reached.
+    pub fn try_add_root_node(&mut self,
+                             position: P,
+                             information: Option<I>)
+                             -> Result<NodeIdx, CapacityError> {
+        if self.nodes.len() >= self.node_limit {
+            return Err(CapacityError { limit: self.node_limit });
+        }
+
         // A root node has it's own index as parent and root.
-        let len = self.nodes.len();
-        let root_idx = NodeIdx(len as u32);
-        self.nodes.push(Node {
+        let root_idx = NodeIdx::of(self.nodes.len());
+        self.record(Event::NodeAdded {
+            parent: None,
+            position,
+        });
+        self.notify(|o| o.on_node_added(root_idx, None, &position));
+        self.push_node(Node {
             parent: root_idx,
             root: root_idx,
             length: 0,
             branches: 0,
-            position: position,
-            growth: Zero::zero(),
+            branch_limit: None,
+            position,
+            growth: F::zero(),
             growth_count: 0,
             assigned_information: information,
+            received_information: Vec::new(),
         });
-        root_idx
+        let biomass = self.biomass.map(|b| b.initial).unwrap_or(S::zero());
+        self.roots.push(RootState::new(root_idx, biomass));
+        Ok(root_idx)
+    }
+
+    /// Makes room for ```additional``` more nodes, e.g. when the final size
+    /// of the structure is known from earlier runs.
+    pub fn reserve_nodes(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        self.positions.reserve(additional);
+        self.parents.reserve(additional);
+        self.children.reserve(additional);
+    }
+
+    fn push_node(&mut self, node: Node<P, F, I>) {
+        debug_assert!(node.parent.0 as usize <= self.nodes.len() && node.root.0 <= node.parent.0,
+                      "nodes must be stored after their parent and root");
+        if let Some(ref mut grid) = self.node_grid {
+            grid.insert(&spatial::coords(&node.position), self.nodes.len());
+        }
+        self.positions.push(node.position);
+        self.parents.push(node.parent.0);
+        if node.parent.0 as usize != self.nodes.len() {
+            self.children[node.parent.0 as usize].push(self.nodes.len() as IndexType);
+        }
+        self.children.push(Vec::new());
+        self.nodes.push(node);
+        self.segment_attributes.push_default();
+        if let Some(ref mut provenance) = self.provenance {
+            provenance.push_node();
+        }
+        if let Some(ref mut dormancy) = self.dormancy {
+            dormancy.since.push(self.next_iteration);
+        }
+        if let Some(ref mut oscillation) = self.oscillation {
+            oscillation.stalls.push(0);
+        }
+    }
+
+    /// The positions of all nodes, indexed by ```NodeIdx```. Useful to
+    /// upload the skeleton to the GPU without visiting every node.
+    pub fn positions(&self) -> &[P] {
+        self.view().positions()
+    }
+
+    /// The parent index of every node, parallel to ```positions```.
+    /// Root nodes are their own parent.
+    pub fn parents(&self) -> &[IndexType] {
+        self.view().parents()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_attractors(&self) -> usize {
+        self.attractors.len()
     }
 
     fn get_node(&self, node_idx: NodeIdx) -> Option<&Node<P, F, I>> {
@@ -237,45 +1328,211 @@ impl<P, F, I> SpaceColonization<P, F, I>
             parent_node.branches += 1;
             (parent_node.root, parent_node.length + 1)
         };
+        self.reset_dormancy(parent);
+        let information = self.root_state(root).inherited_information;
+        self.record(Event::NodeAdded {
+            parent: Some(parent),
+            position,
+        });
+        let node_idx = NodeIdx::of(self.nodes.len());
+        self.notify(|o| o.on_node_added(node_idx, Some(parent), &position));
 
-        self.nodes.push(Node {
-            parent: parent,
-            root: root,
-            length: length,
+        self.push_node(Node {
+            parent,
+            root,
+            length,
             branches: 0,
-            position: position,
-            growth: Zero::zero(),
+            branch_limit: None,
+            position,
+            growth: F::zero(),
             growth_count: 0,
-            assigned_information: None,
+            assigned_information: information,
+            received_information: Vec::new(),
         });
+
+        if self.segment_sender.is_some() {
+            let msg = SegmentMsg::Segment {
+                node: node_idx,
+                parent,
+                from: self.nodes[parent.0 as usize].position,
+                to: position,
+                iteration: self.next_iteration.saturating_sub(1),
+            };
+            self.send_segment_msg(msg);
+        }
+    }
+
+    /// Empties the buffers mirroring the nodes, before they are rebuilt.
+    fn clear_node_buffers(&mut self) {
+        self.positions.clear();
+        self.parents.clear();
+        self.children.clear();
+        if let Some(ref mut grid) = self.node_grid {
+            grid.clear();
+        }
+    }
+
+    /// Carries the per-node data over to rebuilt nodes: the new node ```i```
+    /// takes the data of the old node ```sources[i]```.
+    fn remap_node_data(&mut self, sources: &[usize]) {
+        self.segment_attributes.remap(sources);
+        if let Some(ref mut provenance) = self.provenance {
+            provenance.remap(sources);
+        }
+        if let Some(ref mut dormancy) = self.dormancy {
+            dormancy.remap(sources);
+        }
+        if let Some(ref mut oscillation) = self.oscillation {
+            oscillation.remap(sources);
+        }
+    }
+
+    /// Removes all nodes for which ```keep``` is false. The parent of a kept
+    /// node must be kept as well. Returns the new index of every old node
+    /// (```None``` if it was removed).
+    fn retain_nodes_by_mask(&mut self, keep: &[bool]) -> Vec<Option<NodeIdx>> {
+        assert!(keep.len() == self.nodes.len());
+        let sources: Vec<usize> = (0..keep.len()).filter(|&i| keep[i]).collect();
+        self.rebuild_nodes(&sources)
+    }
+
+    /// Reorders the nodes so that the nodes of every tree are stored
+    /// contiguously, in the order of the roots. Within a tree the order is
+    /// kept, so parents still precede their children. Returns the new index
+    /// of every old node.
+    pub fn normalize_order(&mut self) -> Vec<NodeIdx> {
+        let mut trees: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            trees[node.root.0 as usize].push(i);
+        }
+        let sources: Vec<usize> = trees.into_iter().flat_map(|tree| tree.into_iter()).collect();
+        if sources.iter().enumerate().all(|(new, &old)| new == old) {
+            return (0..self.nodes.len()).map(NodeIdx::of).collect();
+        }
+        self.rebuild_nodes(&sources).into_iter().map(|idx| idx.unwrap()).collect()
+    }
+
+    /// Rebuilds the nodes from the old nodes ```sources```, in this order,
+    /// and updates every reference to a node. Parents must precede their
+    /// children in ```sources```. Returns the new index of every old node
+    /// (```None``` if it was left out).
+    fn rebuild_nodes(&mut self, sources: &[usize]) -> Vec<Option<NodeIdx>> {
+        let mut mapping = vec![None; self.nodes.len()];
+        for (new, &old) in sources.iter().enumerate() {
+            mapping[old] = Some(NodeIdx::of(new));
+        }
+
+        let mut old_nodes: Vec<Option<Node<P, F, I>>> =
+            ::std::mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        let nodes = sources.iter()
+                           .map(|&old| {
+                               let mut node = old_nodes[old].take().expect("node used twice");
+                               node.parent = mapping[node.parent.0 as usize]
+                                                 .expect("parent of a kept node was removed");
+                               node.root = mapping[node.root.0 as usize]
+                                               .expect("root of a kept node was removed");
+                               node
+                           })
+                           .collect();
+        self.install_nodes(nodes, sources, &mapping);
+        mapping
+    }
+
+    /// Replaces the nodes by ```nodes```, whose parents and roots already
+    /// refer to the new indices, and updates every other reference to a
+    /// node. The new node ```i``` takes the per-node data of the old node
+    /// ```sources[i]```, ```mapping``` is the new index of every old node.
+    fn install_nodes(&mut self,
+                     nodes: Vec<Node<P, F, I>>,
+                     sources: &[usize],
+                     mapping: &[Option<NodeIdx>]) {
+        self.nodes = Vec::with_capacity(nodes.len());
+        self.clear_node_buffers();
+        for mut node in nodes {
+            node.branches = 0;
+            self.push_node(node);
+        }
+        self.remap_node_data(sources);
+
+        // recount the branches
+        for i in 0..self.nodes.len() {
+            if !self.nodes[i].is_root() {
+                let parent = self.nodes[i].parent.0 as usize;
+                self.nodes[parent].branches += 1;
+            }
+        }
+
+        let remap = |idx: Option<NodeIdx>| idx.and_then(|idx| mapping[idx.0 as usize]);
+        for attractor in self.attractors.iter_mut() {
+            attractor.not_for_root = remap(attractor.not_for_root);
+            attractor.not_for_connecting_root = remap(attractor.not_for_connecting_root);
+        }
+        for meta in self.attractor_meta.iter_mut() {
+            meta.nearest_root = remap(meta.nearest_root);
+            meta.last_connection = meta.last_connection.and_then(|(node, since)| {
+                remap(Some(node)).map(|node| (node, since))
+            });
+        }
+        self.bridges = self.bridges
+                           .iter()
+                           .filter_map(|&(tip, target)| {
+                               match (remap(Some(tip)), remap(Some(target))) {
+                                   (Some(tip), Some(target)) => Some((tip, target)),
+                                   _ => None,
+                               }
+                           })
+                           .collect();
+        self.tree_connections = self.tree_connections
+                                    .iter()
+                                    .filter_map(|c| {
+                                        Some(TreeConnection {
+                                            attractor: c.attractor,
+                                            node: remap(Some(c.node))?,
+                                            root: remap(Some(c.root))?,
+                                            other_root: remap(Some(c.other_root))?,
+                                            iteration: c.iteration,
+                                        })
+                                    })
+                                    .collect();
+        self.remap_roots(mapping);
+        self.clear_history();
+        self.update_capture_distances();
+        self.record_full_state();
     }
 
     pub fn visit_attractor_points<V>(&self, visitor: &mut V)
         where V: FnMut(&P)
     {
-        for attractor in self.attractors.iter() {
-            visitor(&attractor.position)
-        }
+        self.view().visit_attractor_points(visitor)
     }
 
     pub fn visit_attractors<V>(&self, visitor: &mut V)
-        where V: FnMut(&Attractor<P, I>)
+        where V: FnMut(&Attractor<P, I, S>)
     {
-        for attractor in self.attractors.iter() {
-            visitor(attractor)
-        }
+        self.view().visit_attractors(visitor)
     }
 
+    /// The stable id (see ```attractor_id```) of every attractor which is
+    /// inactive in the next iteration but will become active again, e.g.
+    /// after ```ConnectAction::DisableFor```, with that iteration. Roots
+    /// excluded by ```ConnectAction::DisableForConnectingRoot``` are not
+    /// reported.
+    pub fn disabled_attractors<'a>(&'a self) -> impl Iterator<Item = (u64, u32)> + 'a {
+        self.view().disabled_attractors()
+    }
 
     pub fn visit_node_segments<V>(&self, visitor: &mut V)
         where V: FnMut(&P, &P)
     {
-        for node in self.nodes.iter() {
-            if !node.is_root() {
-                visitor(&node.position,
-                        &self.get_node(node.parent).unwrap().position);
-            }
-        }
+        self.view().visit_node_segments(visitor)
+    }
+
+    /// Calls the visitor with the (tip, target) positions of every bridge,
+    /// see ```bridges```.
+    pub fn visit_bridge_segments<V>(&self, visitor: &mut V)
+        where V: FnMut(&P, &P)
+    {
+        self.view().visit_bridge_segments(visitor)
     }
 
     /// Calls the visitor for every node that has information associated.
@@ -284,47 +1541,131 @@ impl<P, F, I> SpaceColonization<P, F, I>
     pub fn visit_nodes_with_info_and_root<V>(&self, visitor: &mut V)
         where V: FnMut(&Node<P, F, I>, &Node<P, F, I>)
     {
-        for node in self.nodes.iter() {
-            if node.assigned_information.is_some() && !node.is_root() {
-                visitor(node, &self.get_node(node.root).unwrap());
-            }
-        }
+        self.view().visit_nodes_with_info_and_root(visitor)
     }
 
     pub fn visit_root_nodes<V>(&self, visitor: &mut V)
         where V: FnMut(&Node<P, F, I>)
     {
-        for node in self.nodes.iter() {
-            if node.is_root() {
-                visitor(node);
-            }
-        }
+        self.view().visit_root_nodes(visitor)
+    }
+
+    /// Iterates over all nodes that have information assigned,
+    /// together with their index. Root nodes are included.
+    pub fn nodes_with_info<'a>(&'a self)
+                               -> impl Iterator<Item = (NodeIdx, &'a Node<P, F, I>)> + 'a {
+        self.view().nodes_with_info()
+    }
+
+    /// Like ```nodes_with_info```, but only yields nodes whose
+    /// assigned information matches the predicate.
+    pub fn nodes_where_info<'a, Q>(&'a self,
+                                   predicate: Q)
+                                   -> impl Iterator<Item = (NodeIdx, &'a Node<P, F, I>)> + 'a
+        where Q: Fn(&I) -> bool + 'a
+    {
+        self.view().nodes_where_info(predicate)
     }
 }
 
-impl<P, F, I> Iterator for SpaceColonization<P, F, I>
-    where P: FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug,
-          I: Copy + Default + Debug
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
 {
-    type Item = usize;
+    fn search_context<'a>(&'a self,
+                          current_iteration: u32,
+                          start_index: usize)
+                          -> SearchContext<'a, P, F, I, S> {
+        let euclidean = self.metric.is_none() && self.manifold.is_none();
+        SearchContext {
+            nodes: &self.nodes,
+            roots: &self.roots,
+            attractors: &self.attractors,
+            attractor_meta: &self.attractor_meta,
+            grid: if euclidean { self.node_grid.as_ref() } else { None },
+            current_iteration,
+            start_index,
+            cooldown: self.connect_cooldown.unwrap_or(0),
+            max_length: self.max_length,
+            max_branches: self.max_branches,
+            exclude_roots: self.exclude_roots,
+            perception_cos: self.perception_angle.map(|angle| scalar::<S>(angle).cos()),
+            connect_policy: self.connect_policy,
+            dormancy: self.dormancy.as_ref(),
+            oscillation: self.oscillation.as_ref(),
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Performs one iteration. Returns the number of new nodes, or an error
+    /// if the node limit was reached during the iteration (the nodes grown
+    /// up to the limit are kept).
+    pub fn try_step(&mut self) -> Result<usize, CapacityError> {
+        self.iterate(None)
+    }
+
+    /// Performs one iteration, noting the connections and killed attractors
+    /// in ```stats```.
+    pub(crate) fn iterate(&mut self,
+                          mut stats: Option<&mut IterationStats<I>>)
+                          -> Result<usize, CapacityError> {
         let max_length = self.max_length;
         let max_branches = self.max_branches;
+        let information_mode = self.information_mode;
 
+        self.push_history();
         let current_iteration = self.next_iteration;
         self.next_iteration += 1;
+        self.record(Event::IterationStarted(current_iteration));
         let num_nodes = self.nodes.len();
         let use_last_nodes: usize = cmp::min(num_nodes, self.use_last_n_nodes.unwrap_or(num_nodes));
         let start_index = num_nodes - use_last_nodes;
 
+        let _step_span = trace_span!("step",
+                                     iteration = current_iteration,
+                                     nodes = num_nodes,
+                                     active_nodes = use_last_nodes,
+                                     attractors = self.attractors.len());
+
+        self.move_attractors(current_iteration);
+        self.uncluster_attractors(current_iteration, start_index);
+        self.inject_frontier_attractors(current_iteration, start_index);
+        self.prioritize_attractors(current_iteration, start_index);
+        self.heatmap_presence(current_iteration);
+        self.prefilter_attractors(current_iteration);
+
         // for each attraction_point, find the nearest node that it influences
+        let attract_span = trace_span!("attract");
+        let mut searches = match self.parallel_search {
+            Some(search_all) if self.metric.is_none() && self.manifold.is_none() => {
+                Some(search_all(&self.search_context(current_iteration, start_index)))
+            }
+            _ => None,
+        };
+        let mut connections = 0;
         let mut ap_idx = 0;
         'outer: while ap_idx < self.attractors.len() {
+            if let Some(quota) = self.connection_quota {
+                if connections >= quota {
+                    break;
+                }
+            }
+
             let ap = {
                 let ap_ref = &self.attractors[ap_idx];
 
+                if ap_ref.is_expired_in(current_iteration) {
+                    // the attractor will never become active again.
+                    let id = self.attractor_meta[ap_idx].id;
+                    let ap = self.remove_attractor(ap_idx, false);
+                    self.notify(|o| o.on_attractor_expired(&ap, id, current_iteration));
+                    if let Some(ref mut searches) = searches {
+                        searches.swap_remove(ap_idx);
+                    }
+                    continue;
+                }
+
                 if !ap_ref.is_active_in(current_iteration) {
                     // is attractor is not active in the current iteration goto next.
                     ap_idx += 1;
@@ -334,95 +1675,279 @@ impl<P, F, I> Iterator for SpaceColonization<P, F, I>
                 *ap_ref
             };
 
-            let nodes = &mut self.nodes[start_index..];
-
-            // find the node nearest to the `ap` attraction point
-            let mut nearest_node: Option<&mut Node<_, _, _>> = None;
-            let mut nearest_distance = ap.attract_dist;
-            let mut connect_node: Option<&mut Node<_, _, _>> = None;
-            for node in nodes.iter_mut() {
-                if !node.is_active(max_length, max_branches) {
-                    // The node has become inactive
-                    continue;
-                }
-
-                match ap.not_for_root {
-                    Some(deny_root) if deny_root == node.root => {
-                        // The attractor is not for this tree node.
-                        continue;
-                    }
-                    _ => {}
+            let search = match searches {
+                Some(ref searches) => searches[ap_idx],
+                None => {
+                    let metric = self.metric.as_deref();
+                    let manifold = self.manifold.as_deref();
+                    self.search_context(current_iteration, start_index)
+                        .search(ap_idx, metric, manifold)
                 }
+            };
+            let (connect_node, nearest_node) = (search.connect, search.nearest);
 
-                match ap.not_for_connecting_root {
-                    Some(deny_root) if deny_root == node.root => {
-                        // The attractor is not for this tree node.
-                        continue;
-                    }
-                    _ => {}
+            if let Some(node_idx) = connect_node {
+                self.note_attractor_state(ap_idx);
+                self.note_information(node_idx);
+                let node = &mut self.nodes[node_idx.0 as usize];
+                connections += 1;
+                if let Some(ref mut stats) = stats {
+                    stats.connections.push((node_idx, ap.information));
                 }
-
-                let dist = SqDist(node.position.sqdist(&ap.position));
-
-                if dist < ap.connect_dist {
-                    // This node is within the connect radius of a node.
-                    // XXX: There might be a closer node, but we use
-                    // the first we find.
-                    connect_node = Some(node);
-                    // outside the node loop, we perform some action
-                    break;
-                } else if dist < nearest_distance {
-                    // ```node``` is within the influence of the attraction point,
-                    // and it's closer than the currently closest node.
-                    nearest_distance = dist;
-                    nearest_node = Some(node);
+                node.transmit_information(ap.information, current_iteration, information_mode);
+                let connecting_root = node.root;
+                let kill = matches!(ap.connect_action, ConnectAction::KillAttractor);
+                self.note_connection(ap_idx, connecting_root, kill);
+                self.heatmap_influence(&ap.position);
+                self.attractor_meta[ap_idx].last_connection = Some((node_idx, current_iteration));
+                self.attractor_counts
+                    .entry(ap.group)
+                    .or_default()
+                    .connections += 1;
+                if let Some(biomass) = self.biomass {
+                    let root = self.root_state_mut(connecting_root);
+                    root.biomass = root.biomass + biomass.per_connection;
                 }
-            }
-
-            if let Some(node) = connect_node {
-                node.transmit_information(ap.information);
+                let id = self.attractor_meta[ap_idx].id;
+                self.notify(|o| o.on_attractor_connected(&ap, id, node_idx, current_iteration));
                 match ap.connect_action {
                     ConnectAction::KillAttractor => {
                         // remove attraction point
-                        self.attractors.swap_remove(ap_idx);
+                        self.remove_attractor(ap_idx, true);
+                        self.notify(|o| {
+                            o.on_attractor_killed(&ap, id, node_idx, current_iteration)
+                        });
+                        if let Some(ref mut searches) = searches {
+                            searches.swap_remove(ap_idx);
+                        }
+                        if let Some(ref mut stats) = stats {
+                            stats.killed_attractors += 1;
+                        }
                         // and continue with "next" (without increasing ap_idx)
                         continue 'outer;
                     }
                     ConnectAction::DisableFor {iterations} => {
+                        let event = Event::AttractorDisabled {
+                            index: ap_idx,
+                            until: current_iteration + iterations,
+                        };
+                        self.note_attractor(&event);
                         self.attractors[ap_idx].disable_until(current_iteration + iterations);
+                        self.record(event);
                     }
                     ConnectAction::DisableForConnectingRoot => {
-                        self.attractors[ap_idx].not_for_connecting_root = Some(node.root)
+                        self.note_tree_connection(ap_idx, node_idx, current_iteration);
+                        self.attractors[ap_idx].not_for_connecting_root = Some(connecting_root)
                     }
                 }
-            } else if let Some(node) = nearest_node {
+            } else if let Some(node_idx) = nearest_node {
+                let strength = ap.strength_in(current_iteration) *
+                               self.falloff_factor(node_idx, &ap);
+                let root = self.nodes[node_idx.0 as usize].root;
+                if self.attractor_meta[ap_idx].nearest_root != Some(root) {
+                    self.note_attractor_state(ap_idx);
+                }
+                let node = &mut self.nodes[node_idx.0 as usize];
                 // update the force with the normalized vector towards the attraction point
-                let v = (ap.position - node.position).normalize() * ap.strength;
-                node.growth = node.growth + v;
+                let towards = match self.manifold {
+                    Some(ref manifold) => manifold.log(&node.position, &ap.position),
+                    None => ap.position - node.position,
+                };
+                node.growth = self.growth_model.accumulate(node.growth, towards, strength);
                 node.growth_count += 1;
+                self.attractor_meta[ap_idx].claim(root);
+                self.heatmap_influence(&ap.position);
+                self.note_contribution(node_idx, ap_idx, strength);
             }
 
             // go to next attractor point
             ap_idx += 1;
         }
 
+        #[allow(clippy::drop_non_drop)]
+        drop(attract_span);
+        trace_event!(remaining_attractors = self.attractors.len(), "attractors processed");
+
+        self.heatmap_flush_influence();
+        self.attract_to_nodes(start_index);
+
+        // sample the continuous attraction fields
+        if !self.fields.is_empty() {
+            let _fields_span = trace_span!("fields", fields = self.fields.len());
+            for i in start_index..self.nodes.len() {
+                {
+                    let node = &self.nodes[i];
+                    if !node.is_active(&self.roots, max_length, max_branches) ||
+                       (self.exclude_roots && node.is_root() && !node.is_leaf()) {
+                        continue;
+                    }
+                }
+                let node = &mut self.nodes[i];
+                for field in self.fields.iter() {
+                    let v = field(&node.position);
+                    if !v.is_zero() {
+                        node.growth = node.growth + v;
+                        node.growth_count += 1;
+                    }
+                }
+            }
+        }
+
         // now create new nodes
+        let grow_span = trace_span!("grow");
+        let move_dist = self.adapted_move_dist(current_iteration, start_index);
+        let mut limit_reached = false;
         for i in start_index..num_nodes {
             let growth_count = self.nodes[i].growth_count;
             if growth_count > 0 {
-                let growth_factor = 1.0; //((growth_count + 1) as f32).ln();
-                let d = self.nodes[i].growth.normalize() * self.move_dist * growth_factor;
-                let new_position = self.nodes[i].position + d;
-                self.add_leaf_node(new_position, NodeIdx(i as u32));
+                let growth_factor = match self.step_scaling {
+                    Some(ref scaling) => scaling(growth_count),
+                    None => 1.0,
+                };
+                if growth_factor <= 0.0 {
+                    self.nodes[i].growth = F::zero();
+                    self.nodes[i].growth_count = 0;
+                    continue;
+                }
+                let root = self.nodes[i].root;
+                let step = self.growth_model.step(&GrowthState {
+                    position: &self.nodes[i].position,
+                    heading: self.heading(i),
+                    growth: self.apply_tropism(root, self.nodes[i].growth, current_iteration),
+                    growth_count,
+                    move_dist: move_dist * scalar(self.root_state(root).config.move_dist_factor),
+                });
+                let (direction, length) = match step {
+                    Some(step) => step,
+                    None => {
+                        self.nodes[i].growth = F::zero();
+                        self.nodes[i].growth_count = 0;
+                        continue;
+                    }
+                };
+                let direction = self.steer(&self.nodes[i].position, direction);
+                let direction = self.jitter_direction(i, current_iteration, direction);
+                let direction = match self.constrain_to_branch_angle(i, direction) {
+                    Some(direction) => direction,
+                    None => {
+                        // the direction turns too far away from the heading.
+                        self.nodes[i].growth = F::zero();
+                        self.nodes[i].growth_count = 0;
+                        continue;
+                    }
+                };
+                let direction = match self.constrain_to_cone(root, direction) {
+                    Some(direction) => direction,
+                    None => {
+                        // the direction is outside of the tree's growth cone.
+                        self.nodes[i].growth = F::zero();
+                        self.nodes[i].growth_count = 0;
+                        continue;
+                    }
+                };
+                let d = direction * length * scalar(growth_factor);
+                let new_position = match self.manifold {
+                    Some(ref manifold) => manifold.exp(&self.nodes[i].position, &d),
+                    None => self.nodes[i].position + d,
+                };
+                let new_position = match self.quantizer {
+                    Some(ref quantize) => quantize(&new_position),
+                    None => new_position,
+                };
+                let new_position = match self.constrain_to_domain(&self.nodes[i].position,
+                                                                  new_position) {
+                    Some(new_position) => new_position,
+                    None => {
+                        self.nodes[i].growth = F::zero();
+                        self.nodes[i].growth_count = 0;
+                        continue;
+                    }
+                };
+                let new_position = match self.avoid_obstacles(&self.nodes[i].position,
+                                                              new_position) {
+                    Some(new_position) => new_position,
+                    None => {
+                        self.nodes[i].growth = F::zero();
+                        self.nodes[i].growth_count = 0;
+                        continue;
+                    }
+                };
+                let step_length = new_position.dist(&self.nodes[i].position);
+                if !self.passes_length_decay(i, current_iteration) {
+                    // the branch is too long to grow in this iteration.
+                } else if let Some(target) = self.merge_target(&new_position) {
+                    self.merge_into(NodeIdx::of(i), target);
+                } else if self.nodes.len() >= self.node_limit {
+                    limit_reached = true;
+                } else if self.consume_biomass(root, step_length) {
+                    self.add_leaf_node(new_position, NodeIdx::of(i));
+                    self.assign_provenance(NodeIdx::of(i));
+                }
 
                 // and reset growth attraction forces
-                self.nodes[i].growth = Zero::zero();
+                self.nodes[i].growth = F::zero();
                 self.nodes[i].growth_count = 0;
             }
         }
 
+        self.clear_pending_provenance();
+        self.link_anastomoses(num_nodes);
+        let trapped = self.trap_oscillating_nodes(num_nodes, current_iteration);
+        if let Some(ref mut stats) = stats {
+            stats.trapped_nodes = trapped;
+        }
+        #[allow(clippy::drop_non_drop)]
+        drop(grow_span);
+        trace_event!(new_nodes = self.nodes.len() - num_nodes, "step finished");
+        self.send_segment_msg(SegmentMsg::StepFinished {
+            iteration: current_iteration,
+            new_nodes: self.nodes.len() - num_nodes,
+        });
+        let new_nodes = self.nodes.len() - num_nodes;
+        self.notify(|o| o.on_step_finished(current_iteration, new_nodes));
+        if self.nodes.len() == num_nodes {
+            self.quiet_iterations += 1;
+        } else {
+            self.quiet_iterations = 0;
+        }
+        self.update_capture_distances();
+
+        if limit_reached {
+            return Err(CapacityError { limit: self.node_limit });
+        }
+
         // Note that nodes can oscillate, between two attraction points, so
         // it's better to stop after a certain number of iterations
-        return Some(self.nodes.len() - num_nodes);
+        Ok(self.nodes.len() - num_nodes)
+    }
+}
+
+impl<P, F, I, S> Iterator for SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    type Item = usize;
+
+    /// Performs one iteration, see ```try_step```. Ends once the node limit
+    /// is reached, or once the growth is done with ```DoneConditions::stop```
+    /// set, see ```stop_reason```. See ```take_steps``` and ```until_quiet```
+    /// for other bounds.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stop_reason = None;
+        if self.done_conditions.stop {
+            if let Some(reason) = self.done_reason() {
+                self.stop_reason = Some(reason);
+                return None;
+            }
+        }
+        match self.try_step() {
+            Ok(new_nodes) => Some(new_nodes),
+            Err(_) => {
+                self.stop_reason = Some(DoneReason::NodeLimit);
+                None
+            }
+        }
     }
 }
@@ -0,0 +1,109 @@
+//! Converting the trees to turtle graphics commands, so that they can be
+//! fed into L-system renderers and editors. Headings are measured in the
+//! x/y plane; 3D structures are flattened onto it.
+//!
+//! The commands are ```F``` (move forward by one step, drawing), ```+```
+//! and ```-``` (turn left/right by one angle step) and ```[```/```]```
+//! (push/pop the turtle state). The turtle starts at the root, facing +y.
+
+use position::{Position, Vector};
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use super::SpaceColonization;
+
+/// The quanta the turns and segment lengths are rounded to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LSystemTolerance {
+    /// In degrees.
+    pub angle: f32,
+
+    /// The length of an ```F```.
+    pub length: f32,
+}
+
+enum Work {
+    Segment {
+        node: usize,
+        // the turtle's heading at the parent, in angle steps.
+        heading: i32,
+        bracket: bool,
+    },
+    Close,
+}
+
+impl<P, F, I> SpaceColonization<P, F, I>
+    where P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    /// One line of commands per tree, in the order of the roots. Turns are
+    /// rounded relative to the turtle's (rounded) heading, so angle errors
+    /// don't accumulate. A segment is at least one ```F``` long.
+    pub fn to_lsystem_string(&self, tolerance: LSystemTolerance) -> String {
+        assert!(tolerance.angle > 0.0 && tolerance.length > 0.0);
+        let step = tolerance.angle.to_radians();
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                children[node.parent.0 as usize].push(i);
+            }
+        }
+
+        let up = (PI / 2.0 / step).round() as i32;
+        let mut lines = Vec::new();
+        for (root, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                continue;
+            }
+            let mut s = String::new();
+            let mut stack = Vec::new();
+            push_children(&mut stack, &children[root], up);
+            while let Some(work) = stack.pop() {
+                let (i, heading, bracket) = match work {
+                    Work::Close => {
+                        s.push(']');
+                        continue;
+                    }
+                    Work::Segment { node, heading, bracket } => (node, heading, bracket),
+                };
+                if bracket {
+                    s.push('[');
+                    stack.push(Work::Close);
+                }
+                let node = &self.nodes[i];
+                let parent = &self.nodes[node.parent.0 as usize];
+                let d = node.position - parent.position;
+                let target = d[1].atan2(d[0]);
+                let mut turn = ((target - heading as f32 * step) / step).round() as i32;
+                // take the shorter way around.
+                let full = (2.0 * PI / step).round() as i32;
+                if full > 0 {
+                    turn = ((turn % full) + full + full / 2) % full - full / 2;
+                }
+                let command = if turn > 0 { '+' } else { '-' };
+                for _ in 0..turn.abs() {
+                    s.push(command);
+                }
+                let steps = ((d.norm() / tolerance.length).round() as usize).max(1);
+                for _ in 0..steps {
+                    s.push('F');
+                }
+                push_children(&mut stack, &children[i], heading + turn);
+            }
+            lines.push(s);
+        }
+        lines.join("\n")
+    }
+}
+
+/// Pushes the segments to ```children``` so that all but the last are
+/// bracketed and popped first.
+fn push_children(stack: &mut Vec<Work>, children: &[usize], heading: i32) {
+    for (k, &child) in children.iter().enumerate().rev() {
+        stack.push(Work::Segment {
+            node: child,
+            heading,
+            bracket: k + 1 < children.len(),
+        });
+    }
+}
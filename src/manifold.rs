@@ -0,0 +1,118 @@
+//! Growth on curved spaces.
+//!
+//! A ```Manifold``` provides the exponential and logarithmic maps, which
+//! lets the colonization keep node positions on the manifold and step
+//! along geodesics instead of straight lines. Other spaces (e.g. the
+//! hyperbolic plane) can be supported by implementing the trait.
+
+#[cfg(feature = "nalgebra")]
+use na::{Point3, Vector3};
+use std::ops::{Add, Sub};
+#[cfg(feature = "nalgebra")]
+use position::Vector;
+
+pub trait Manifold<P, F> {
+    /// The tangent vector at ```base``` pointing along the geodesic towards
+    /// ```target```. Its length is the geodesic distance.
+    fn log(&self, base: &P, target: &P) -> F;
+
+    /// Follows the geodesic starting at ```base``` in the direction of
+    /// ```tangent``` for the length of ```tangent```.
+    fn exp(&self, base: &P, tangent: &F) -> P;
+}
+
+/// Ordinary euclidean space. Equivalent to not using a manifold at all.
+#[derive(Debug, Copy, Clone)]
+pub struct Flat;
+
+impl<P, F> Manifold<P, F> for Flat
+    where P: Copy + Add<F, Output = P> + Sub<Output = F>,
+          F: Copy
+{
+    fn log(&self, base: &P, target: &P) -> F {
+        *target - *base
+    }
+
+    fn exp(&self, base: &P, tangent: &F) -> P {
+        *base + *tangent
+    }
+}
+
+/// The surface of a sphere.
+#[cfg(feature = "nalgebra")]
+#[derive(Debug, Copy, Clone)]
+pub struct Sphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+#[cfg(feature = "nalgebra")]
+impl Manifold<Point3<f32>, Vector3<f32>> for Sphere {
+    fn log(&self, base: &Point3<f32>, target: &Point3<f32>) -> Vector3<f32> {
+        let u = (*base - self.center).normalize();
+        let v = (*target - self.center).normalize();
+        let cos = u.dot(&v).clamp(-1.0, 1.0);
+        let w = v - u * cos;
+        if w.sqnorm() == 0.0 {
+            // identical or antipodal points
+            return Vector3::zeros();
+        }
+        w.normalize() * (cos.acos() * self.radius)
+    }
+
+    fn exp(&self, base: &Point3<f32>, tangent: &Vector3<f32>) -> Point3<f32> {
+        let u = (*base - self.center).normalize();
+        // remove any component normal to the surface.
+        let t = *tangent - u * u.dot(tangent);
+        let len = t.norm();
+        if len == 0.0 {
+            return self.center + u * self.radius;
+        }
+        let angle = len / self.radius;
+        self.center + (u * angle.cos() + (t / len) * angle.sin()) * self.radius
+    }
+}
+
+/// The lateral surface of an infinite cylinder.
+#[cfg(feature = "nalgebra")]
+#[derive(Debug, Copy, Clone)]
+pub struct Cylinder {
+    /// A point on the cylinder axis.
+    pub center: Point3<f32>,
+
+    /// Direction of the cylinder axis. Must be normalized.
+    pub axis: Vector3<f32>,
+
+    pub radius: f32,
+}
+
+#[cfg(feature = "nalgebra")]
+impl Cylinder {
+    /// Splits ```p``` into its height along the axis and its unit radial
+    /// direction.
+    fn decompose(&self, p: &Point3<f32>) -> (f32, Vector3<f32>) {
+        let rel = *p - self.center;
+        let height = rel.dot(&self.axis);
+        (height, (rel - self.axis * height).normalize())
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl Manifold<Point3<f32>, Vector3<f32>> for Cylinder {
+    fn log(&self, base: &Point3<f32>, target: &Point3<f32>) -> Vector3<f32> {
+        let (hb, rb) = self.decompose(base);
+        let (ht, rt) = self.decompose(target);
+        let around = self.axis.cross(&rb);
+        let angle = rt.dot(&around).atan2(rt.dot(&rb));
+        self.axis * (ht - hb) + around * (angle * self.radius)
+    }
+
+    fn exp(&self, base: &Point3<f32>, tangent: &Vector3<f32>) -> Point3<f32> {
+        let (hb, rb) = self.decompose(base);
+        let around = self.axis.cross(&rb);
+        let height = hb + tangent.dot(&self.axis);
+        let angle = tangent.dot(&around) / self.radius;
+        let radial = rb * angle.cos() + around * angle.sin();
+        self.center + self.axis * height + radial * self.radius
+    }
+}
@@ -0,0 +1,259 @@
+//! Morphometric measurements of the grown structure.
+
+use position::{Position, Vector, Scalar};
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use super::{SpaceColonization, IndexType, scalar};
+use view::SpaceColonizationView;
+
+/// A reference structure to compare with, e.g. reconstructed vasculature
+/// or a traced neuron, in the layout of ```positions``` and ```parents```.
+#[derive(Debug, Clone)]
+pub struct SkeletonRef<P> {
+    pub positions: Vec<P>,
+
+    /// The parent index of every node. Roots are their own parent.
+    pub parents: Vec<IndexType>,
+}
+
+impl<P> SkeletonRef<P> {
+    pub fn new(positions: Vec<P>, parents: Vec<IndexType>) -> SkeletonRef<P> {
+        assert!(positions.len() == parents.len());
+        assert!(parents.iter().all(|&p| (p as usize) < positions.len()));
+        SkeletonRef {
+            positions,
+            parents,
+        }
+    }
+}
+
+/// The sorted samples of one measurement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub samples: Vec<f32>,
+}
+
+impl Distribution {
+    fn new(mut samples: Vec<f32>) -> Distribution {
+        samples.retain(|s| s.is_finite());
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Distribution { samples }
+    }
+
+    pub fn mean(&self) -> Option<f32> {
+        if self.samples.is_empty() {
+            None
+        } else {
+            Some(self.samples.iter().fold(0.0, |sum, s| sum + s) / self.samples.len() as f32)
+        }
+    }
+
+    /// The two-sample Kolmogorov-Smirnov statistic, i.e. the largest
+    /// difference between the two cumulative distributions (0 to 1).
+    pub fn ks_distance(&self, other: &Distribution) -> f32 {
+        let (a, b) = (&self.samples, &other.samples);
+        if a.is_empty() || b.is_empty() {
+            return if a.is_empty() && b.is_empty() { 0.0 } else { 1.0 };
+        }
+        let (mut i, mut j) = (0, 0);
+        let mut max: f32 = 0.0;
+        while i < a.len() && j < b.len() {
+            let x = a[i].min(b[j]);
+            while i < a.len() && a[i] <= x {
+                i += 1;
+            }
+            while j < b.len() && b[j] <= x {
+                j += 1;
+            }
+            max = max.max((i as f32 / a.len() as f32 - j as f32 / b.len() as f32).abs());
+        }
+        max
+    }
+}
+
+/// One measurement of the structure next to that of the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub own: Distribution,
+    pub reference: Distribution,
+    pub ks_distance: f32,
+}
+
+impl Comparison {
+    fn new(own: Vec<f32>, reference: Vec<f32>) -> Comparison {
+        let own = Distribution::new(own);
+        let reference = Distribution::new(reference);
+        Comparison {
+            ks_distance: own.ks_distance(&reference),
+            own,
+            reference,
+        }
+    }
+}
+
+/// The result of ```compare_morphology```. Sections are the unbranched
+/// paths between roots, branch points and leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorphoMetrics {
+    /// The angles (in radians) between a parent segment and the child
+    /// segments at each branch point.
+    pub branch_angles: Comparison,
+
+    /// The path lengths of the sections.
+    pub section_lengths: Comparison,
+
+    /// Path length over straight distance of every section (1 for a
+    /// straight section).
+    pub tortuosity: Comparison,
+
+    /// The mean of the three Kolmogorov-Smirnov distances, as a single
+    /// objective for fitting parameters.
+    pub distance: f32,
+}
+
+/// Branch angles, section lengths and tortuosities of a structure.
+fn morphology<P, F, S>(positions: &[P], parents: &[IndexType]) -> (Vec<f32>, Vec<f32>, Vec<f32>)
+    where P: Position<S, F>,
+          F: Vector<S>,
+          S: Scalar
+{
+    let is_root = |i: usize| parents[i] as usize == i;
+    let mut children = vec![0; positions.len()];
+    for i in 0..positions.len() {
+        if !is_root(i) {
+            children[parents[i] as usize] += 1;
+        }
+    }
+
+    let mut angles = Vec::new();
+    let mut lengths = Vec::new();
+    let mut tortuosity = Vec::new();
+    for i in 0..positions.len() {
+        if is_root(i) {
+            continue;
+        }
+        let parent = parents[i] as usize;
+        if children[parent] >= 2 && !is_root(parent) {
+            let incoming = positions[parent] - positions[parents[parent] as usize];
+            let outgoing = positions[i] - positions[parent];
+            if incoming.sqnorm() > S::zero() && outgoing.sqnorm() > S::zero() {
+                let cos = incoming.normalize().dot(&outgoing.normalize());
+                angles.push(cos.max(-S::one()).min(S::one()).acos().to_f32().unwrap());
+            }
+        }
+
+        // walk up every section from its end.
+        if children[i] != 1 {
+            let mut length = S::zero();
+            let mut node = i;
+            loop {
+                let parent = parents[node] as usize;
+                length = length + positions[node].dist(&positions[parent]);
+                node = parent;
+                if is_root(node) || children[node] != 1 {
+                    break;
+                }
+            }
+            let chord = positions[i].dist(&positions[node]);
+            lengths.push(length.to_f32().unwrap());
+            if chord > S::zero() {
+                tortuosity.push((length / chord).to_f32().unwrap());
+            }
+        }
+    }
+    (angles, lengths, tortuosity)
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Calls the visitor with (child radius, parent radius, segment length)
+    /// for every segment.
+    fn visit_segment_frusta<V>(&self, radii: &[S], visitor: &mut V)
+        where V: FnMut(S, S, S)
+    {
+        assert!(radii.len() == self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                let parent = node.parent.0 as usize;
+                let h = node.position.dist(&self.nodes[parent].position);
+                visitor(radii[i], radii[parent], h);
+            }
+        }
+    }
+
+    /// The summed length of all segments.
+    pub fn total_length(&self) -> S {
+        self.view().total_length()
+    }
+
+    /// The lateral surface area of all segments, each modelled as a conical
+    /// frustum between the radii of its two nodes. ```radii``` is indexed by
+    /// ```NodeIdx```.
+    pub fn surface_area(&self, radii: &[S]) -> S {
+        let pi: S = scalar(PI);
+        let mut area = S::zero();
+        self.visit_segment_frusta(radii, &mut |r1, r2, h| {
+            area = area + pi * (r1 + r2) * ((r1 - r2).powi(2) + h * h).sqrt();
+        });
+        area
+    }
+
+    /// The volume of all segments, each modelled as a conical frustum
+    /// between the radii of its two nodes. ```radii``` is indexed by
+    /// ```NodeIdx```.
+    pub fn volume(&self, radii: &[S]) -> S {
+        let pi: S = scalar(PI);
+        let mut volume = S::zero();
+        self.visit_segment_frusta(radii, &mut |r1, r2, h| {
+            volume = volume + pi * h / scalar(3.0) * (r1 * r1 + r1 * r2 + r2 * r2);
+        });
+        volume
+    }
+
+    /// Compares the branch angles, section lengths and tortuosities with
+    /// those of ```reference```, e.g. to fit the parameters of the
+    /// simulation to a reconstructed structure.
+    pub fn compare_morphology(&self, reference: &SkeletonRef<P>) -> MorphoMetrics {
+        let (angles, lengths, tortuosity) = morphology(&self.positions, &self.parents);
+        let (ref_angles, ref_lengths, ref_tortuosity) = morphology(&reference.positions,
+                                                                   &reference.parents);
+        let branch_angles = Comparison::new(angles, ref_angles);
+        let section_lengths = Comparison::new(lengths, ref_lengths);
+        let tortuosity = Comparison::new(tortuosity, ref_tortuosity);
+        MorphoMetrics {
+            distance: (branch_angles.ks_distance + section_lengths.ks_distance +
+                       tortuosity.ks_distance) / 3.0,
+            branch_angles,
+            section_lengths,
+            tortuosity,
+        }
+    }
+
+    /// The structure as a reference for ```compare_morphology```.
+    pub fn skeleton_ref(&self) -> SkeletonRef<P> {
+        self.view().skeleton_ref()
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::total_length```.
+    pub fn total_length(&self) -> S {
+        let mut total = S::zero();
+        self.visit_node_segments(&mut |a, b| total = total + a.dist(b));
+        total
+    }
+
+    /// See ```SpaceColonization::skeleton_ref```.
+    pub fn skeleton_ref(&self) -> SkeletonRef<P> {
+        SkeletonRef::new(self.positions.to_vec(), self.parents.to_vec())
+    }
+}
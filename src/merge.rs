@@ -0,0 +1,80 @@
+//! Merging new nodes into existing ones. Where branches converge, nodes
+//! pile up at nearly the same position, bloating the structure and the
+//! meshes built from it. With a merge distance set, a node which would grow
+//! a child within that distance of an existing node reuses that node
+//! instead: no child is grown, and a bridge (see ```bridges```) from the
+//! growing node to the existing one is recorded, like a link of
+//! ```set_anastomosis```.
+//!
+//! No bridge is recorded to a node of the same tree within the
+//! ```min_separation``` of the anastomosis (1 without it), e.g. to an
+//! earlier child grown onto the same spot, nor twice between the same
+//! nodes. The growth is just dropped then.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, SqDist};
+use record::Event;
+use spatial::Candidates;
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Merges new nodes closer than ```distance``` to an existing node
+    /// (with the metric or manifold, if set) into it. It should be well
+    /// below the move distance, or nodes merge into their parents and
+    /// nothing grows. ```None``` disables it.
+    pub fn set_merge_distance(&mut self, distance: Option<S>) {
+        self.merge_distance = distance;
+    }
+
+    pub fn merge_distance(&self) -> Option<S> {
+        self.merge_distance
+    }
+
+    /// The nearest node a new node at ```position``` merges into.
+    pub(crate) fn merge_target(&self, position: &P) -> Option<NodeIdx> {
+        let max_dist = SqDist::from_dist(self.merge_distance?);
+        let euclidean = self.metric.is_none() && self.manifold.is_none();
+        let candidates = match self.node_grid {
+            Some(ref grid) if euclidean => grid.candidates(position, max_dist, 0, self.nodes.len()),
+            _ => Candidates::All(0..self.nodes.len()),
+        };
+
+        let mut nearest: Option<(usize, SqDist<S>)> = None;
+        for i in candidates {
+            let dist = self.sqdist_to_node(&self.positions[i], position);
+            let nearer = match nearest {
+                Some((_, d)) => dist < d,
+                None => dist < max_dist,
+            };
+            if nearer {
+                nearest = Some((i, dist));
+            }
+        }
+        nearest.map(|(i, _)| NodeIdx::of(i))
+    }
+
+    /// Lets ```node``` reuse ```target``` instead of growing a child on it.
+    pub(crate) fn merge_into(&mut self, node: NodeIdx, target: NodeIdx) {
+        let min_separation = self.anastomosis.map_or(1, |anastomosis| anastomosis.min_separation);
+        let own_tree = self.nodes[node.0 as usize].root == self.nodes[target.0 as usize].root;
+        if own_tree && self.tree_distance(node, target) <= min_separation {
+            return;
+        }
+        let linked = self.bridges.iter().any(|&bridge| {
+            bridge == (node, target) || bridge == (target, node)
+        });
+        if linked {
+            return;
+        }
+        self.bridges.push((node, target));
+        self.record(Event::BridgeAdded {
+            tip: node,
+            target,
+        });
+    }
+}
@@ -0,0 +1,411 @@
+//! Geometry generation from the node skeleton.
+
+use position::{Position, Vector, Scalar};
+use std::f32::consts::PI;
+use std::cmp;
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::ops::Index;
+use super::{SpaceColonization, scalar};
+use view::SpaceColonizationView;
+use frames::{binormal, perpendicular};
+
+/// Parameters of the cylindrical bark texture mapping.
+#[derive(Debug, Copy, Clone)]
+pub struct BarkUvOptions {
+    /// Rotation (in radians) of the texture seam around the branch,
+    /// relative to the transported reference normal.
+    pub seam_angle: f32,
+
+    /// How often the texture repeats around the circumference.
+    pub u_repeat: f32,
+
+    /// Texture ```v``` per unit of branch length.
+    pub v_scale: f32,
+}
+
+impl Default for BarkUvOptions {
+    fn default() -> BarkUvOptions {
+        BarkUvOptions {
+            seam_angle: 0.0,
+            u_repeat: 1.0,
+            v_scale: 1.0,
+        }
+    }
+}
+
+/// The texture frame of the cross section at a node.
+#[derive(Debug, Copy, Clone)]
+pub struct BarkUv<F, S = f32> {
+    /// Texture ```v``` coordinate, proportional to the arc length from the
+    /// root, so it is continuous across branch junctions.
+    pub v: S,
+
+    /// Unit radial direction at which ```u``` is 0 (the seam).
+    pub seam: F,
+
+    /// Unit radial direction at which ```u``` is a quarter turn. Zero in 2D.
+    pub binormal: F,
+
+    u_repeat: S,
+}
+
+impl<F, S> BarkUv<F, S>
+    where F: Vector<S> + Copy,
+          S: Scalar
+{
+    /// The texture ```u``` coordinate of a vertex in the unit ```radial```
+    /// direction from the branch axis, in [0, u_repeat).
+    pub fn u(&self, radial: &F) -> S {
+        let turn: S = scalar(2.0 * PI);
+        let mut angle = radial.dot(&self.binormal).atan2(radial.dot(&self.seam));
+        if angle < S::zero() {
+            angle = angle + turn;
+        }
+        angle / turn * self.u_repeat
+    }
+}
+
+/// Parameters of ```build_mesh```.
+#[derive(Debug, Clone)]
+pub struct MeshOptions<S = f32> {
+    /// Number of vertices around each cross section (at least 3).
+    pub sides: u32,
+
+    /// The radius at every node, indexed by ```NodeIdx```, e.g. from
+    /// ```radii_from_leaf_count```. If empty, all nodes get ```radius```.
+    pub radii: Vec<S>,
+
+    pub radius: S,
+
+    /// Closes the tubes at the leaves.
+    pub caps: bool,
+
+    pub uv: BarkUvOptions,
+}
+
+impl<S: Scalar> MeshOptions<S> {
+    /// Tubes of the same ```radius``` everywhere.
+    pub fn uniform(radius: S) -> MeshOptions<S> {
+        MeshOptions {
+            sides: 8,
+            radii: Vec::new(),
+            radius,
+            caps: true,
+            uv: BarkUvOptions::default(),
+        }
+    }
+
+    /// Tubes with the per node ```radii```.
+    pub fn with_radii(radii: Vec<S>) -> MeshOptions<S> {
+        MeshOptions { radii, ..MeshOptions::uniform(S::zero()) }
+    }
+
+    fn radius_at(&self, node: usize) -> S {
+        if self.radii.is_empty() {
+            self.radius
+        } else {
+            self.radii[node]
+        }
+    }
+}
+
+/// An indexed triangle mesh. In 2D the cross sections collapse to line
+/// segments, so the tubes become flat ribbons.
+#[derive(Debug, Clone)]
+pub struct Mesh<P, F, S = f32> {
+    pub positions: Vec<P>,
+
+    /// Unit vertex normals, parallel to ```positions```.
+    pub normals: Vec<F>,
+
+    /// Bark texture coordinates (see ```bark_uvs```), parallel to
+    /// ```positions```.
+    pub uvs: Vec<[S; 2]>,
+
+    /// Three vertex indices per triangle, counter-clockwise when seen from
+    /// the outside.
+    pub indices: Vec<u32>,
+}
+
+impl<P, F, S> Mesh<P, F, S>
+    where P: Position<S, F>,
+          F: Vector<S> + Copy,
+          S: Scalar
+{
+    fn new() -> Mesh<P, F, S> {
+        Mesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Adds a cross section of ```sides + 1``` vertices (the first one is
+    /// repeated at the end, so the texture wraps around) and returns the
+    /// index of its first vertex.
+    fn add_ring(&mut self,
+                center: P,
+                tangent: F,
+                seam: F,
+                radius: S,
+                v: S,
+                options: &MeshOptions<S>)
+                -> u32 {
+        let seam = unit(seam - tangent * seam.dot(&tangent)).unwrap_or_else(|| {
+            perpendicular(&tangent)
+        });
+        let mut b = binormal(&tangent, &seam);
+        // seam, binormal and tangent are right-handed, so that the winding
+        // is counter-clockwise from the outside.
+        if F::dim() == 3 && det3(&seam, &b, &tangent) < S::zero() {
+            b = -b;
+        }
+        let first = self.positions.len() as u32;
+        for k in 0..(options.sides + 1) {
+            let turns = k as f32 / options.sides as f32;
+            let angle: S = scalar(2.0 * PI * turns);
+            let radial = seam * angle.cos() + b * angle.sin();
+            self.positions.push(center + radial * radius);
+            self.normals.push(radial);
+            self.uvs.push([scalar(turns * options.uv.u_repeat), v]);
+        }
+        first
+    }
+
+    /// Connects the rings starting at ```a``` and (further along the
+    /// tangent) ```b``` with quads.
+    fn connect(&mut self, a: u32, b: u32, sides: u32) {
+        for k in 0..sides {
+            self.indices.extend_from_slice(&[a + k, a + k + 1, b + k]);
+            self.indices.extend_from_slice(&[a + k + 1, b + k + 1, b + k]);
+        }
+    }
+
+    /// Closes the end of the tube at the ring starting at ```ring```.
+    fn add_cap(&mut self, ring: u32, center: P, tangent: F, sides: u32) {
+        let first = self.positions.len() as u32;
+        for k in 0..sides {
+            let i = (ring + k) as usize;
+            let (p, uv) = (self.positions[i], self.uvs[i]);
+            self.positions.push(p);
+            self.normals.push(tangent);
+            self.uvs.push(uv);
+        }
+        let v = self.uvs[ring as usize][1];
+        self.positions.push(center);
+        self.normals.push(tangent);
+        self.uvs.push([S::zero(), v]);
+        let center = first + sides;
+        for k in 0..sides {
+            self.indices.extend_from_slice(&[first + k, first + (k + 1) % sides, center]);
+        }
+    }
+
+    /// Writes the mesh as a Wavefront OBJ (2D positions get ```z = 0```).
+    pub fn write_obj<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let dim = P::dim();
+        for p in self.positions.iter() {
+            let c = xyz(p, dim);
+            writeln!(w, "v {} {} {}", c[0], c[1], c[2])?;
+        }
+        for n in self.normals.iter() {
+            let c = xyz(n, dim);
+            writeln!(w, "vn {} {} {}", c[0], c[1], c[2])?;
+        }
+        for uv in self.uvs.iter() {
+            writeln!(w, "vt {} {}", uv[0], uv[1])?;
+        }
+        for t in self.indices.chunks(3) {
+            // OBJ indices start at 1.
+            writeln!(w,
+                     "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}",
+                     t[0] + 1,
+                     t[1] + 1,
+                     t[2] + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The normalized ```v```, or ```None``` if it is (almost) zero.
+fn unit<F, S>(v: F) -> Option<F>
+    where F: Vector<S> + Copy,
+          S: Scalar
+{
+    if v.sqnorm() > scalar(1.0e-12) {
+        Some(v.normalize())
+    } else {
+        None
+    }
+}
+
+/// The first three coordinates of ```v```, padded with zeros.
+fn xyz<V, S>(v: &V, dim: usize) -> [S; 3]
+    where V: Index<usize, Output = S>,
+          S: Scalar
+{
+    let mut c = [S::zero(); 3];
+    for i in 0..cmp::min(dim, 3) {
+        c[i] = v[i];
+    }
+    c
+}
+
+/// The determinant of the 3x3 matrix with the columns ```a```, ```b```, ```c```.
+fn det3<F, S>(a: &F, b: &F, c: &F) -> S
+    where F: Vector<S>,
+          S: Scalar
+{
+    a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0]) +
+    a[2] * (b[0] * c[1] - b[1] * c[0])
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Computes the bark texture frame at every node, indexed by
+    /// ```NodeIdx```. The seam is carried along branches and into child
+    /// branches, so tube meshes can be textured without visible jumps at
+    /// junctions. Root nodes use the frame of their first child segment.
+    pub fn bark_uvs(&self, options: &BarkUvOptions) -> Vec<BarkUv<F, S>> {
+        self.view().bark_uvs(options)
+    }
+
+    /// Sweeps a circle along every segment and returns the resulting tube
+    /// mesh. Each node gets one cross section, oriented halfway between
+    /// the incoming segment and the segment to its thickest child, which
+    /// continues the tube. The other children start a new tube inside the
+    /// parent's at the junction, so the surfaces intersect rather than
+    /// being welded. Roots without children are left out.
+    pub fn build_mesh(&self, options: &MeshOptions<S>) -> Mesh<P, F, S> {
+        self.view().build_mesh(options)
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::bark_uvs```.
+    pub fn bark_uvs(&self, options: &BarkUvOptions) -> Vec<BarkUv<F, S>> {
+        let transport = self.transport_normals();
+        let (sin, cos) = options.seam_angle.sin_cos();
+        let (sin, cos): (S, S) = (scalar(sin), scalar(cos));
+
+        let mut uvs = Vec::with_capacity(self.nodes.len());
+        for i in 0..self.nodes.len() {
+            let normal = transport.normals[i];
+            let b = binormal(&transport.directions[i], &normal);
+            uvs.push(BarkUv {
+                v: transport.arc[i] * scalar(options.v_scale),
+                seam: normal * cos + b * sin,
+                binormal: b * cos - normal * sin,
+                u_repeat: scalar(options.u_repeat),
+            });
+        }
+
+        // roots take the frame of their first child.
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                let root = node.parent.0 as usize;
+                if self.nodes[root].is_root() && uvs[root].seam.is_zero() {
+                    uvs[root].seam = uvs[i].seam;
+                    uvs[root].binormal = uvs[i].binormal;
+                }
+            }
+        }
+
+        uvs
+    }
+
+    /// See ```SpaceColonization::build_mesh```.
+    pub fn build_mesh(&self, options: &MeshOptions<S>) -> Mesh<P, F, S> {
+        assert!(options.sides >= 3);
+        let num_nodes = self.nodes.len();
+        let uvs = self.bark_uvs(&options.uv);
+        let mut children = vec![Vec::new(); num_nodes];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.is_root() {
+                children[node.parent.0 as usize].push(i);
+            }
+        }
+        // the child continuing the tube, the first of the thickest.
+        let mut main: Vec<Option<usize>> = vec![None; num_nodes];
+        for (i, c) in children.iter().enumerate() {
+            for &child in c.iter() {
+                match main[i] {
+                    Some(best) if options.radius_at(best) >= options.radius_at(child) => {}
+                    _ => main[i] = Some(child),
+                }
+            }
+        }
+        let direction = |from: usize, to: usize| {
+            unit(self.positions[to] - self.positions[from]).unwrap_or_else(F::zero)
+        };
+
+        let mut mesh = Mesh::new();
+        let mut rings = vec![None; num_nodes];
+        let mut tangents = vec![F::zero(); num_nodes];
+        for (i, node) in self.nodes.iter().enumerate() {
+            let incoming = if node.is_root() {
+                F::zero()
+            } else {
+                direction(node.parent.0 as usize, i)
+            };
+            let outgoing = main[i].map_or(F::zero(), |c| direction(i, c));
+            let tangent = match unit(incoming + outgoing) {
+                Some(tangent) => tangent,
+                None => {
+                    match unit(incoming) {
+                        Some(tangent) => tangent,
+                        None => continue,
+                    }
+                }
+            };
+            tangents[i] = tangent;
+            rings[i] = Some(mesh.add_ring(self.positions[i],
+                                          tangent,
+                                          uvs[i].seam,
+                                          options.radius_at(i),
+                                          uvs[i].v,
+                                          options));
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.is_root() {
+                continue;
+            }
+            let parent = node.parent.0 as usize;
+            let start = if main[parent] == Some(i) {
+                rings[parent]
+            } else {
+                unit(self.positions[i] - self.positions[parent]).map(|tangent| {
+                    mesh.add_ring(self.positions[parent],
+                                  tangent,
+                                  uvs[i].seam,
+                                  options.radius_at(i),
+                                  uvs[parent].v,
+                                  options)
+                })
+            };
+            if let (Some(a), Some(b)) = (start, rings[i]) {
+                mesh.connect(a, b, options.sides);
+                if options.caps && node.is_leaf() {
+                    mesh.add_cap(b, self.positions[i], tangents[i], options.sides);
+                }
+            }
+        }
+        mesh
+    }
+}
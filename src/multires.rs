@@ -0,0 +1,175 @@
+//! Coarse to fine growth: a coarse skeleton is grown with a large
+//! ```move_dist``` and few attractors, then its segments are subdivided and
+//! growth continues with finer parameters and denser attractors. Phases
+//! are run by ```grow_in_phases```, or ```run_phases``` for a report on
+//! each.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, Node, SqDist, CapacityError, scalar};
+use roots;
+use steps::{DoneConditions, DoneReason};
+
+/// The parameters of one phase of ```grow_in_phases``` and
+/// ```run_phases```.
+#[derive(Debug, Clone)]
+pub struct Phase<P, S = f32> {
+    /// Reported in ```PhaseReport::name```.
+    pub name: String,
+
+    /// Number of nodes inserted into every existing segment before the
+    /// phase starts.
+    pub subdivisions: u32,
+
+    /// Distances of the default attractors added in this phase.
+    pub attract_dist: SqDist<S>,
+    pub connect_dist: SqDist<S>,
+
+    pub move_dist: S,
+
+    /// Replace the maximum length and number of branches if set (after
+    /// the subdivision, which scales the maximum length).
+    pub max_length: Option<u32>,
+    pub max_branches: Option<u32>,
+
+    /// Removes the attractors left over from previous phases (counted as
+    /// expired) before adding the phase's own.
+    pub clear_attractors: bool,
+
+    /// Positions of the default attractors added in this phase.
+    pub attractors: Vec<P>,
+
+    /// The phase ends after this many iterations, or earlier once
+    /// ```done_conditions``` consider the growth done.
+    pub max_iterations: u32,
+
+    /// Replace those of the simulation while the phase runs. The node
+    /// budget and the maximum number of iterations count from the start of
+    /// the simulation, not of the phase.
+    pub done_conditions: DoneConditions,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Splits every segment into ```n + 1``` equally long segments. The
+    /// maximum length, the length filters of the attractors and the length
+    /// decay are scaled accordingly, so the same nodes stay active.
+    /// Returns the new index of every old node.
+    pub fn subdivide_segments(&mut self, n: u32) -> Result<Vec<NodeIdx>, CapacityError> {
+        let num_segments = self.nodes.iter().filter(|node| !node.is_root()).count();
+        let new_len = (n as usize)
+                          .checked_mul(num_segments)
+                          .and_then(|inserted| inserted.checked_add(self.nodes.len()));
+        match new_len {
+            Some(len) if len <= self.node_limit => {}
+            _ => return Err(CapacityError { limit: self.node_limit }),
+        }
+        if n == 0 {
+            return Ok((0..self.nodes.len()).map(NodeIdx::of).collect());
+        }
+
+        let mut mapping: Vec<NodeIdx> = Vec::with_capacity(self.nodes.len());
+        // the old node each new node takes its segment attributes from.
+        let mut sources = Vec::new();
+        let old_nodes = ::std::mem::take(&mut self.nodes);
+        self.clear_node_buffers();
+
+        // parents are always stored before their children.
+        for (old_idx, mut node) in old_nodes.into_iter().enumerate() {
+            if node.is_root() {
+                let idx = NodeIdx::of(self.nodes.len());
+                node.parent = idx;
+                node.root = idx;
+                mapping.push(idx);
+                sources.push(old_idx);
+                self.push_node(node);
+                continue;
+            }
+
+            // the roots are remapped only once all nodes are in place.
+            let inherited_information = roots::find(&self.roots, node.root).inherited_information;
+            let root = mapping[node.root.0 as usize];
+            let mut parent = mapping[node.parent.0 as usize];
+            let start = self.nodes[parent.0 as usize].position;
+            let step = (node.position - start) / scalar((n + 1) as f32);
+            for k in 1..(n + 1) {
+                let length = self.nodes[parent.0 as usize].length + 1;
+                let idx = NodeIdx::of(self.nodes.len());
+                self.push_node(Node {
+                    parent,
+                    root,
+                    length,
+                    branches: 1,
+                    branch_limit: None,
+                    position: start + step * scalar(k as f32),
+                    growth: F::zero(),
+                    growth_count: 0,
+                    assigned_information: inherited_information,
+                    received_information: Vec::new(),
+                });
+                sources.push(old_idx);
+                parent = idx;
+            }
+            node.length = self.nodes[parent.0 as usize].length + 1;
+            node.parent = parent;
+            node.root = root;
+            mapping.push(NodeIdx::of(self.nodes.len()));
+            sources.push(old_idx);
+            self.push_node(node);
+        }
+        self.remap_node_data(&sources);
+
+        self.max_length = self.max_length.saturating_mul(n + 1);
+        for root in self.roots.iter_mut() {
+            root.node = mapping[root.node.0 as usize];
+            if let Some(ref mut max_length) = root.config.max_length {
+                *max_length = max_length.saturating_mul(n + 1);
+            }
+        }
+        if let Some(ref mut decay) = self.length_decay {
+            decay.half_length *= (n + 1) as f32;
+        }
+        for bridge in self.bridges.iter_mut() {
+            let (tip, target) = *bridge;
+            *bridge = (mapping[tip.0 as usize], mapping[target.0 as usize]);
+        }
+        for connection in self.tree_connections.iter_mut() {
+            connection.node = mapping[connection.node.0 as usize];
+            connection.root = mapping[connection.root.0 as usize];
+            connection.other_root = mapping[connection.other_root.0 as usize];
+        }
+        self.clear_history();
+        self.record_full_state();
+
+        for attractor in self.attractors.iter_mut() {
+            attractor.not_for_root = attractor.not_for_root.map(|idx| mapping[idx.0 as usize]);
+            attractor.not_for_connecting_root = attractor.not_for_connecting_root
+                                                         .map(|idx| mapping[idx.0 as usize]);
+            attractor.min_node_length = attractor.min_node_length
+                                                 .map(|length| length.saturating_mul(n + 1));
+            attractor.max_node_length = attractor.max_node_length
+                                                 .map(|length| length.saturating_mul(n + 1));
+        }
+        for meta in self.attractor_meta.iter_mut() {
+            meta.nearest_root = meta.nearest_root.map(|idx| mapping[idx.0 as usize]);
+            meta.last_connection = meta.last_connection
+                                       .map(|(node, since)| (mapping[node.0 as usize], since));
+        }
+
+        Ok(mapping)
+    }
+
+    /// Runs the ```phases``` one after another, see ```run_phases```.
+    /// Returns the total number of iterations.
+    pub fn grow_in_phases(&mut self, phases: &[Phase<P, S>]) -> Result<u32, CapacityError> {
+        let reports = self.run_phases(phases, |_, _| {})?;
+        if reports.iter().any(|report| report.done_reason == DoneReason::NodeLimit) {
+            return Err(CapacityError { limit: self.node_limit });
+        }
+        Ok(reports.iter().map(|report| report.iterations).sum())
+    }
+}
@@ -0,0 +1,97 @@
+//! Multi-scale growth: child simulations rooted at selected leaves grow
+//! finer structures (twigs on branches, capillaries off arterioles) with
+//! scaled-down parameters, and are merged back as subtrees of their leaf.
+//!
+//! Children only inherit the scalar parameters, not fields, metrics,
+//! manifolds or biomass. Merged nodes continue the length of their leaf, so
+//! they may exceed ```max_length``` and stay inactive in the parent.
+
+use position::{Position, Vector};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, SqDist, CapacityError};
+
+#[derive(Debug, Copy, Clone)]
+pub struct SubColonization {
+    /// Factor applied to the distances and the step size of the parent.
+    pub scale: f32,
+
+    /// A child ends after this many iterations, or earlier if an iteration
+    /// produced no new nodes.
+    pub max_iterations: u32,
+
+    pub max_length: u32,
+    pub max_branches: u32,
+}
+
+/// The outcome of the child simulation of one leaf.
+#[derive(Debug, Clone)]
+pub struct SubReport {
+    pub leaf: NodeIdx,
+    pub iterations: u32,
+
+    /// The merged index of every node of the child, indexed by its
+    /// ```NodeIdx``` within the child. The child's root maps to ```leaf```.
+    pub nodes: Vec<NodeIdx>,
+}
+
+impl<P, F, I> SpaceColonization<P, F, I>
+    where P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    /// Grows a child simulation at every node of ```leaves```, one after
+    /// another, with the default attractors ```attractors(leaf,
+    /// position)```, and merges it into the structure. Fails if the node
+    /// limit is reached, keeping the children merged so far.
+    pub fn grow_nested<A>(&mut self,
+                          leaves: &[NodeIdx],
+                          options: SubColonization,
+                          mut attractors: A)
+                          -> Result<Vec<SubReport>, CapacityError>
+        where A: FnMut(NodeIdx, &P) -> Vec<P>
+    {
+        let scale2 = options.scale * options.scale;
+        let mut reports = Vec::with_capacity(leaves.len());
+        for &leaf in leaves.iter() {
+            let position = self.nodes[leaf.0 as usize].position;
+            let mut child: SpaceColonization<P, F, I> =
+                SpaceColonization::new(SqDist(self.default_attract_dist.0 * scale2),
+                                       SqDist(self.default_connect_dist.0 * scale2),
+                                       options.max_length,
+                                       options.max_branches,
+                                       self.move_dist.value_at(self.next_iteration) *
+                                       options.scale);
+            child.add_root_node(position);
+            for p in attractors(leaf, &position) {
+                child.add_default_attractor(p);
+            }
+
+            let mut iterations = 0;
+            while iterations < options.max_iterations {
+                iterations += 1;
+                if child.try_step()? == 0 {
+                    break;
+                }
+            }
+
+            // parents are stored before their children, so every parent is
+            // mapped before it is needed.
+            let mut nodes = Vec::with_capacity(child.nodes.len());
+            nodes.push(leaf);
+            for node in child.nodes.iter().skip(1) {
+                if self.nodes.len() >= self.node_limit {
+                    return Err(CapacityError { limit: self.node_limit });
+                }
+                let parent = nodes[node.parent.0 as usize];
+                self.add_leaf_node(node.position, parent);
+                nodes.push(NodeIdx::of(self.nodes.len() - 1));
+            }
+            reports.push(SubReport {
+                leaf,
+                iterations,
+                nodes,
+            });
+        }
+        Ok(reports)
+    }
+}
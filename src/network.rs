@@ -0,0 +1,83 @@
+//! Which trees got connected with each other. An attractor with
+//! ```ConnectAction::DisableForConnectingRoot``` is reached by one tree
+//! after the other, e.g. a site in a network of routes. When a tree other
+//! than the last one connects with it, the two trees meet at the attractor,
+//! and a ```TreeConnection``` is logged. Trees which keep reaching the same
+//! attractor in turns are only logged the first time.
+//!
+//! Links between the nodes of different trees (see ```bridges```) are not
+//! part of the log.
+
+use position::{Position, Vector, Scalar};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, IndexType};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TreeConnection {
+    /// The stable id of the attractor (see ```attractor_id```).
+    pub attractor: u64,
+
+    /// The node which connected with the attractor.
+    pub node: NodeIdx,
+
+    /// The root of ```node```.
+    pub root: NodeIdx,
+
+    /// The root of the tree which connected with the attractor before.
+    pub other_root: NodeIdx,
+
+    pub iteration: u32,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// All connections between trees so far, in the order they happened.
+    /// Connections whose node or roots were removed (e.g. by ```prune```)
+    /// are dropped.
+    pub fn tree_connections(&self) -> &[TreeConnection] {
+        &self.tree_connections
+    }
+
+    /// Every pair of roots whose trees got connected, once, with the lower
+    /// index first, in index order.
+    pub fn connected_root_pairs<'a>(&'a self) -> impl Iterator<Item = (NodeIdx, NodeIdx)> + 'a {
+        let pairs: BTreeSet<(IndexType, IndexType)> =
+            self.tree_connections
+                .iter()
+                .map(|c| (c.root.0.min(c.other_root.0), c.root.0.max(c.other_root.0)))
+                .collect();
+        pairs.into_iter().map(|(a, b)| (NodeIdx(a), NodeIdx(b)))
+    }
+
+    /// Logs the connection of ```node``` with the attractor ```ap_idx```,
+    /// if another tree connected with it before. Call before updating its
+    /// ```not_for_connecting_root```.
+    pub(crate) fn note_tree_connection(&mut self, ap_idx: usize, node: NodeIdx, iteration: u32) {
+        let root = self.nodes[node.0 as usize].root;
+        let other_root = match self.attractors[ap_idx].not_for_connecting_root {
+            Some(other_root) if other_root != root => other_root,
+            _ => return,
+        };
+        let attractor = self.attractor_meta[ap_idx].id;
+        let logged = self.tree_connections.iter().any(|c| {
+            c.attractor == attractor &&
+            ((c.root, c.other_root) == (root, other_root) ||
+             (c.root, c.other_root) == (other_root, root))
+        });
+        if !logged {
+            self.tree_connections.push(TreeConnection {
+                attractor,
+                node,
+                root,
+                other_root,
+                iteration,
+            });
+        }
+    }
+}
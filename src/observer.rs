@@ -0,0 +1,79 @@
+//! Callbacks from inside a step, e.g. to drive audio or visual effects when
+//! an attractor is consumed, without diffing the nodes after every step.
+//! Unlike the recording (see ```start_recording```), nothing is stored.
+//!
+//! All methods do nothing by default, so an observer only implements the
+//! events it is interested in:
+//!
+//! ```ignore
+//! struct Chime;
+//!
+//! impl<P, I: Copy> Observer<P, I> for Chime {
+//!     fn on_attractor_killed(&mut self, _: &Attractor<P, I>, _: u64, _: NodeIdx, _: u32) {
+//!         play_chime();
+//!     }
+//! }
+//!
+//! sc.add_observer(Chime);
+//! ```
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Attractor, NodeIdx};
+
+#[allow(unused_variables)]
+pub trait Observer<P, I: Copy, S = f32> {
+    /// A node was added at ```position```. Roots have no ```parent```.
+    fn on_node_added(&mut self, node: NodeIdx, parent: Option<NodeIdx>, position: &P) {}
+
+    /// The attractor with the stable id ```id``` connected with ```node```.
+    fn on_attractor_connected(&mut self,
+                              attractor: &Attractor<P, I, S>,
+                              id: u64,
+                              node: NodeIdx,
+                              iteration: u32) {
+    }
+
+    /// The attractor was removed after connecting with ```node```
+    /// (```ConnectAction::KillAttractor```). Follows
+    /// ```on_attractor_connected```.
+    fn on_attractor_killed(&mut self,
+                           attractor: &Attractor<P, I, S>,
+                           id: u64,
+                           node: NodeIdx,
+                           iteration: u32) {
+    }
+
+    /// The attractor was removed because its ```active_until_iteration```
+    /// was reached.
+    fn on_attractor_expired(&mut self, attractor: &Attractor<P, I, S>, id: u64, iteration: u32) {}
+
+    /// The iteration has finished, ```new_nodes``` nodes were grown.
+    fn on_step_finished(&mut self, iteration: u32, new_nodes: usize) {}
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Calls ```observer``` from now on, after the observers added before.
+    pub fn add_observer<O>(&mut self, observer: O)
+        where O: Observer<P, I, S> + 'static
+    {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    pub(crate) fn notify<C>(&mut self, mut event: C)
+        where C: FnMut(&mut dyn Observer<P, I, S>)
+    {
+        for observer in self.observers.iter_mut() {
+            event(&mut **observer);
+        }
+    }
+}
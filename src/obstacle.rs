@@ -0,0 +1,211 @@
+//! Solid geometry the branches grow around, e.g. columns of a building or
+//! an existing mesh given as a signed distance function. A new node which
+//! would land inside an obstacle is either deflected along the obstacle's
+//! surface or not grown in that iteration, see ```Avoidance```.
+//!
+//! Obstacles are checked at the final position of a new node, after the
+//! manifold and the quantizer. Deflected positions are not quantized again.
+//!
+//! Attractors inside an obstacle can't be reached. With ```Deflect```, the
+//! nodes they pull keep sliding along the surface, so such attractors
+//! should not be added in the first place, e.g. by sampling them with
+//! ```attractors::poisson_disk_in_region``` outside of the obstacles.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, PositionFn, scalar};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Obstacle<P, S = f32> {
+    Sphere { center: P, radius: S },
+
+    /// An axis aligned box from ```min``` to ```max```.
+    Aabb { min: P, max: P },
+}
+
+impl<P, S: Scalar> Obstacle<P, S> {
+    /// The signed distance of ```p``` to the surface, negative inside.
+    pub fn distance<F>(&self, p: &P) -> S
+        where P: Position<S, F>,
+              F: Vector<S>
+    {
+        match *self {
+            Obstacle::Sphere { ref center, radius } => p.dist(center) - radius,
+            Obstacle::Aabb { ref min, ref max } => {
+                let half: S = scalar(0.5);
+                let mut outside = S::zero();
+                let mut inside = S::neg_infinity();
+                for i in 0..P::dim() {
+                    let extent = half * (max[i] - min[i]);
+                    let q = (p[i] - half * (min[i] + max[i])).abs() - extent;
+                    outside = outside + q.max(S::zero()) * q.max(S::zero());
+                    inside = inside.max(q);
+                }
+                outside.sqrt() + inside.min(S::zero())
+            }
+        }
+    }
+}
+
+/// What happens to a new node inside an obstacle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Avoidance {
+    /// The step is projected onto the obstacle's surface (tangent plane)
+    /// and rescaled to its length. If that position is inside an obstacle
+    /// as well, the node is not grown.
+    Deflect,
+
+    /// The node is not grown in that iteration.
+    Skip,
+}
+
+pub struct ObstacleSet<P, S = f32> {
+    shapes: Vec<Obstacle<P, S>>,
+    sdfs: Vec<PositionFn<P, S>>,
+    avoidance: Avoidance,
+}
+
+impl<P, S: Scalar> ObstacleSet<P, S> {
+    pub fn new(avoidance: Avoidance) -> ObstacleSet<P, S> {
+        ObstacleSet {
+            shapes: Vec::new(),
+            sdfs: Vec::new(),
+            avoidance,
+        }
+    }
+
+    pub fn avoidance(&self) -> Avoidance {
+        self.avoidance
+    }
+
+    pub fn add(&mut self, obstacle: Obstacle<P, S>) {
+        self.shapes.push(obstacle);
+    }
+
+    pub fn add_sphere(&mut self, center: P, radius: S) {
+        assert!(radius >= S::zero());
+        self.add(Obstacle::Sphere {
+            center,
+            radius,
+        });
+    }
+
+    pub fn add_aabb(&mut self, min: P, max: P) {
+        self.add(Obstacle::Aabb { min, max });
+    }
+
+    /// Adds the region where ```sdf``` is negative. It should be a signed
+    /// distance (or at least grow with the distance to the surface), as
+    /// its gradient gives the direction of deflection.
+    pub fn add_sdf<D>(&mut self, sdf: D)
+        where D: Fn(&P) -> S + 'static
+    {
+        self.sdfs.push(Box::new(sdf));
+    }
+
+    pub fn shapes(&self) -> &[Obstacle<P, S>] {
+        &self.shapes
+    }
+
+    /// The signed distance of ```p``` to the nearest obstacle, infinite if
+    /// there is none.
+    pub fn distance<F>(&self, p: &P) -> S
+        where P: Position<S, F>,
+              F: Vector<S>
+    {
+        let shapes = self.shapes.iter().map(|shape| shape.distance(p));
+        let sdfs = self.sdfs.iter().map(|sdf| sdf(p));
+        shapes.chain(sdfs).fold(S::infinity(), S::min)
+    }
+
+    pub fn contains<F>(&self, p: &P) -> bool
+        where P: Position<S, F>,
+              F: Vector<S>
+    {
+        self.distance(p) < S::zero()
+    }
+
+    /// The outward surface normal near ```p```, by central differences of
+    /// width ```h```. ```None``` where the distance is flat.
+    pub fn normal<F>(&self, p: &P, h: S) -> Option<F>
+        where P: Position<S, F>,
+              F: Vector<S> + Copy
+    {
+        sdf_normal(|p| self.distance(p), p, h)
+    }
+}
+
+/// The normalized gradient of ```distance``` at ```p```, by central
+/// differences of width ```h```. ```None``` where the distance is flat.
+pub(crate) fn sdf_normal<P, F, S, D>(distance: D, p: &P, h: S) -> Option<F>
+    where P: Position<S, F>,
+          F: Vector<S> + Copy,
+          S: Scalar,
+          D: Fn(&P) -> S
+{
+    let mut gradient: F = F::zero();
+    for i in 0..P::dim() {
+        let e = match F::basis_element(i) {
+            Some(e) => e * h,
+            None => continue,
+        };
+        let d = distance(&(*p + e)) - distance(&(*p + -e));
+        gradient = gradient + e * (d / (scalar::<S>(2.0) * h * h));
+    }
+    let len = gradient.norm();
+    if len > S::zero() && len.is_finite() {
+        Some(gradient * (S::one() / len))
+    } else {
+        None
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Sets the obstacles the branches grow around. ```None``` removes
+    /// them.
+    pub fn set_obstacles(&mut self, obstacles: Option<ObstacleSet<P, S>>) {
+        self.obstacles = obstacles;
+    }
+
+    pub fn obstacles(&self) -> Option<&ObstacleSet<P, S>> {
+        self.obstacles.as_ref()
+    }
+
+    /// Where the node at ```from``` grows when it would grow to ```to```,
+    /// or ```None``` if it doesn't grow.
+    pub(crate) fn avoid_obstacles(&self, from: &P, to: P) -> Option<P> {
+        let obstacles = match self.obstacles {
+            Some(ref obstacles) => obstacles,
+            None => return Some(to),
+        };
+        if !obstacles.contains(&to) {
+            return Some(to);
+        }
+        if obstacles.avoidance == Avoidance::Skip {
+            return None;
+        }
+
+        let step = to - *from;
+        let length = step.norm();
+        let normal = obstacles.normal(&to, scalar::<S>(0.01) * length)?;
+        let tangent = step - normal * step.dot(&normal);
+        let tangent_length = tangent.norm();
+        if tangent_length <= S::zero() {
+            // straight into the surface.
+            return None;
+        }
+        let deflected = *from + tangent * (length / tangent_length);
+        if obstacles.contains(&deflected) {
+            None
+        } else {
+            Some(deflected)
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Horton–Strahler stream orders.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+use view::SpaceColonizationView;
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Computes the Horton–Strahler order of every node, indexed by
+    /// ```NodeIdx```. Leaves have order 1. A node has the maximum order of
+    /// its children, plus one if that maximum is reached by two or more
+    /// children.
+    pub fn stream_orders(&self) -> Vec<u32> {
+        self.view().stream_orders()
+    }
+
+    /// Removes all nodes with a stream order below ```min_order```, keeping
+    /// only the structural skeleton. Returns the new index of every old node.
+    pub fn prune_below_order(&mut self, min_order: u32) -> Vec<Option<NodeIdx>> {
+        let keep: Vec<bool> = self.stream_orders().iter().map(|&o| o >= min_order).collect();
+        self.retain_nodes_by_mask(&keep)
+    }
+
+    /// Groups all segments (child position, parent position) by the stream
+    /// order of the child node. Element 0 holds the segments of order 1.
+    pub fn segments_by_order(&self) -> Vec<Vec<(P, P)>> {
+        self.view().segments_by_order()
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::stream_orders```.
+    pub fn stream_orders(&self) -> Vec<u32> {
+        let n = self.nodes.len();
+        // (maximum child order, number of children with that order)
+        let mut max_child = vec![(0u32, 0u32); n];
+        let mut orders = vec![0; n];
+
+        // children are always stored after their parent.
+        for i in (0..n).rev() {
+            let (max, count) = max_child[i];
+            let order = if max == 0 {
+                1
+            } else if count >= 2 {
+                max + 1
+            } else {
+                max
+            };
+            orders[i] = order;
+
+            let node = &self.nodes[i];
+            if !node.is_root() {
+                let parent = &mut max_child[node.parent.0 as usize];
+                if order > parent.0 {
+                    *parent = (order, 1);
+                } else if order == parent.0 {
+                    parent.1 += 1;
+                }
+            }
+        }
+
+        orders
+    }
+
+    /// See ```SpaceColonization::segments_by_order```.
+    pub fn segments_by_order(&self) -> Vec<Vec<(P, P)>> {
+        let orders = self.stream_orders();
+        let mut groups: Vec<Vec<(P, P)>> = Vec::new();
+        for (node, &order) in self.nodes.iter().zip(orders.iter()) {
+            if node.is_root() {
+                continue;
+            }
+            while groups.len() < order as usize {
+                groups.push(Vec::new());
+            }
+            let parent = &self.nodes[node.parent.0 as usize];
+            groups[order as usize - 1].push((node.position, parent.position));
+        }
+        groups
+    }
+}
@@ -0,0 +1,178 @@
+//! Stopping nodes which are trapped between attractors. A node pulled by
+//! attractors on opposite sides never reaches one of them: it keeps
+//! growing children onto the same spot, or its child grows straight back
+//! to it, until the iteration limit is reached.
+//!
+//! With detection enabled, a node stalls whenever it grows a child within
+//! ```radius``` of its parent or of one of its earlier children. After
+//! ```iterations``` consecutive stalls it is trapped, together with the
+//! children it grew onto that spot. Like dormant nodes, trapped nodes are
+//! skipped by the attractors until they are woken (see ```wake_node```).
+//! Optionally, the attractors which reach a trapped node are disabled as
+//! well.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, IndexType};
+use record::Event;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OscillationDetection<S = f32> {
+    /// The number of consecutive stalls after which a node is trapped, at
+    /// least 1.
+    pub iterations: u32,
+
+    /// A child closer than this (with the metric or manifold, if set) to
+    /// the parent or an earlier child of the growing node is a stall.
+    pub radius: S,
+
+    /// Disables the attractors within the attraction distance of a trapped
+    /// node, until they are reactivated (see ```reactivate_all```).
+    /// Otherwise they keep pulling the nearest nodes which are not trapped.
+    pub disable_attractors: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Oscillation<S = f32> {
+    pub(crate) detection: OscillationDetection<S>,
+
+    /// The consecutive stalls of every node, indexed by ```NodeIdx```.
+    /// Trapped nodes have at least ```detection.iterations```.
+    pub(crate) stalls: Vec<u32>,
+}
+
+impl<S> Oscillation<S> {
+    pub(crate) fn is_trapped(&self, node: usize) -> bool {
+        self.stalls[node] >= self.detection.iterations
+    }
+
+    /// See ```SegmentAttributes::remap```.
+    pub(crate) fn remap(&mut self, sources: &[usize]) {
+        self.stalls = sources.iter().map(|&i| self.stalls[i]).collect();
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Traps nodes which keep growing onto the same spot. The stalls
+    /// counted so far are kept when the parameters change. ```None```
+    /// disables the detection and releases all trapped nodes.
+    pub fn set_oscillation_detection(&mut self, detection: Option<OscillationDetection<S>>) {
+        let num_nodes = self.nodes.len();
+        self.oscillation = detection.map(|detection| {
+            assert!(detection.iterations >= 1);
+            let stalls = match self.oscillation.take() {
+                Some(oscillation) => oscillation.stalls,
+                None => vec![0; num_nodes],
+            };
+            Oscillation {
+                detection,
+                stalls,
+            }
+        });
+    }
+
+    pub fn oscillation_detection(&self) -> Option<OscillationDetection<S>> {
+        self.oscillation.as_ref().map(|oscillation| oscillation.detection)
+    }
+
+    /// Whether ```node``` was trapped by the oscillation detection.
+    pub fn is_trapped(&self, node: NodeIdx) -> bool {
+        match self.oscillation {
+            Some(ref oscillation) => oscillation.is_trapped(node.0 as usize),
+            None => false,
+        }
+    }
+
+    /// Sets the stalls of ```node```, if the detection is enabled.
+    pub(crate) fn set_stalls(&mut self, node: usize, stalls: u32) {
+        let old = match self.oscillation {
+            Some(ref oscillation) if oscillation.stalls[node] != stalls => {
+                oscillation.stalls[node]
+            }
+            _ => return,
+        };
+        self.note_stalls(node, old);
+        self.oscillation.as_mut().unwrap().stalls[node] = stalls;
+    }
+
+    /// Counts the stalls of the parents of the nodes from ```start_index```
+    /// on. Returns the newly trapped nodes, in index order.
+    pub(crate) fn trap_oscillating_nodes(&mut self,
+                                         start_index: usize,
+                                         current_iteration: u32)
+                                         -> Vec<NodeIdx> {
+        let detection = match self.oscillation {
+            Some(ref oscillation) => oscillation.detection,
+            None => return Vec::new(),
+        };
+        let sqradius = detection.radius * detection.radius;
+        let mut trapped: Vec<NodeIdx> = Vec::new();
+        for i in start_index..self.nodes.len() {
+            let parent = self.parents[i] as usize;
+            let position = self.positions[i];
+            let near = |node: usize| {
+                self.sqdist_to_node(&self.positions[node], &position).0 < sqradius
+            };
+            let grandparent = self.parents[parent] as usize;
+            let back = grandparent != parent && near(grandparent);
+            // the children grown onto the same spot before.
+            let repeated: Vec<IndexType> = self.children[parent]
+                                               .iter()
+                                               .cloned()
+                                               .filter(|&child| {
+                                                   child as usize != i && near(child as usize)
+                                               })
+                                               .collect();
+
+            if !back && repeated.is_empty() {
+                self.set_stalls(parent, 0);
+                continue;
+            }
+            if self.is_trapped(NodeIdx::of(parent)) {
+                continue;
+            }
+            let stalls = self.oscillation.as_ref().unwrap().stalls[parent];
+            self.set_stalls(parent, stalls + 1);
+            if self.is_trapped(NodeIdx::of(parent)) {
+                trapped.push(NodeIdx::of(parent));
+                for node in repeated.into_iter().map(|child| child as usize).chain(Some(i)) {
+                    if !self.is_trapped(NodeIdx::of(node)) {
+                        self.set_stalls(node, detection.iterations);
+                        trapped.push(NodeIdx::of(node));
+                    }
+                }
+            }
+        }
+        trapped.sort_by_key(|node| node.0);
+
+        if detection.disable_attractors && !trapped.is_empty() {
+            for idx in 0..self.attractors.len() {
+                let ap = self.attractors[idx];
+                if !ap.is_active_in(current_iteration) {
+                    continue;
+                }
+                let reaches = trapped.iter().any(|node| {
+                    self.sqdist_to_node(&self.positions[node.0 as usize], &ap.position) <
+                    ap.attract_dist
+                });
+                if reaches {
+                    let event = Event::AttractorDisabled {
+                        index: idx,
+                        until: u32::MAX,
+                    };
+                    self.note_attractor(&event);
+                    self.attractors[idx].disable_until(u32::MAX);
+                    self.record(event);
+                }
+            }
+        }
+        trapped
+    }
+}
@@ -0,0 +1,67 @@
+//! Parallel attractor searches (```rayon``` feature). Finding the nearest
+//! node of every attractor dominates the cost of an iteration with many
+//! attractors; the searches only read the nodes, so they all run up front
+//! on the rayon thread pool. The results are applied in the usual order,
+//! so they are identical to a sequential run.
+//!
+//! Metrics and manifolds are not thread-safe, so simulations using them
+//! always search sequentially.
+
+use position::{Position, Vector, Scalar};
+use rayon::prelude::*;
+use std::fmt::Debug;
+use super::{SpaceColonization, SearchContext, NodeSearch};
+
+fn search_all<'a, 'b, P, F, I, S>(context: &'b SearchContext<'a, P, F, I, S>) -> Vec<NodeSearch>
+    where P: Position<S, F> + Debug + Send + Sync,
+          F: Vector<S> + Copy + Debug + Send + Sync,
+          I: Copy + Default + Debug + Send + Sync,
+          S: Scalar + Debug + Send + Sync
+{
+    (0..context.attractors.len())
+        .into_par_iter()
+        .map(|ap_idx| context.search(ap_idx, None, None))
+        .collect()
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug + Send + Sync,
+          F: Vector<S> + Copy + Debug + Send + Sync,
+          I: Copy + Default + Debug + Send + Sync,
+          S: Scalar + Debug + Send + Sync
+{
+    /// Searches the nearest nodes of all attractors in parallel in every
+    /// iteration. Only pays off with many attractors (thousands).
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel_search = if parallel {
+            Some(search_all::<P, F, I, S>)
+        } else {
+            None
+        };
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod tests {
+    use na::{Point3, Vector3};
+    use fixtures::simulation;
+    use SpaceColonization;
+
+    fn grow(parallel: bool) -> SpaceColonization<Point3<f32>, Vector3<f32>, ()> {
+        let roots = [Point3::new(0.0, -1.0, 0.0), Point3::new(0.0, 1.0, 0.0)];
+        let mut sc = simulation(&roots, 1000, |_, _| {});
+        sc.set_parallel(parallel);
+        sc.take_steps(25).count();
+        sc
+    }
+
+    #[test]
+    fn parallel_search_grows_the_same_nodes() {
+        let sequential = grow(false);
+        let parallel = grow(true);
+        assert!(sequential.num_nodes() > 50);
+        assert_eq!(parallel.positions(), sequential.positions());
+        assert_eq!(parallel.parents(), sequential.parents());
+        assert_eq!(parallel.num_attractors(), sequential.num_attractors());
+    }
+}
@@ -0,0 +1,416 @@
+//! Taking a simulation apart into its persistent state and putting it back
+//! together, e.g. to checkpoint it with serde (enable the ```serde```
+//! feature) instead of a text snapshot.
+//!
+//! The parts contain the same state as a snapshot: closures, recording,
+//! segment streams, heatmaps, provenance and segment attributes are dropped
+//! and have to be set up again after ```from_parts```.
+
+use position::{Position, Vector, Scalar};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Debug};
+use super::{SpaceColonization, Node, NodeIdx, Attractor, AttractorMeta, SqDist, InformationMode,
+            ConnectPolicy, Biomass};
+use adaptive::AdaptiveStep;
+use cluster::{Cluster, ClusterOptions};
+use cone::BranchAngle;
+use decay::LengthDecay;
+use falloff::Falloff;
+use dormancy::Dormancy;
+use oscillation::Oscillation;
+use jitter::Jitter;
+use frontier::FrontierInjection;
+use priority::AttractorOrder;
+use roots::RootState;
+use schedule::Schedule;
+use stats::AttractorCounts;
+use steps::DoneConditions;
+use targeting::NodeTargeting;
+use anastomosis::Anastomosis;
+use network::TreeConnection;
+use prefilter::ConnectPrefilter;
+use tropism::Tropism;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+#[cfg(feature = "serde")]
+use serde::de;
+
+/// The persistent state of a simulation, see
+/// ```SpaceColonization::into_parts```.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Parts<P, F, I, S = f32>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug,
+          S: Debug
+{
+    /// Parents are stored before their children.
+    pub nodes: Vec<Node<P, F, I>>,
+    roots: Vec<RootState<F, I, S>>,
+    pub attractors: Vec<Attractor<P, I, S>>,
+    attractor_meta: Vec<AttractorMeta<F>>,
+    attractor_counts: BTreeMap<u32, AttractorCounts>,
+    default_attract_dist: SqDist<S>,
+    default_connect_dist: SqDist<S>,
+    move_dist: Schedule<S>,
+    next_iteration: u32,
+    max_length: u32,
+    max_branches: u32,
+    node_limit: usize,
+    use_last_n_nodes: Option<usize>,
+    dormancy: Option<Dormancy>,
+    oscillation: Option<Oscillation<S>>,
+    information_mode: InformationMode,
+    connect_policy: ConnectPolicy,
+    connect_prefilter: Option<ConnectPrefilter>,
+    falloff: Falloff,
+    biomass: Option<Biomass<S>>,
+    attractor_order: AttractorOrder,
+    attractor_order_refresh: u32,
+    connection_quota: Option<usize>,
+    clusters: Vec<Cluster<P>>,
+    cluster_options: Option<ClusterOptions>,
+    adaptive_step: Option<AdaptiveStep>,
+    connect_cooldown: Option<u32>,
+    exclude_roots: bool,
+    perception_angle: Option<f32>,
+    branch_angle: Option<BranchAngle>,
+    node_targeting: Option<NodeTargeting<S>>,
+    anastomosis: Option<Anastomosis<S>>,
+    bridges: Vec<(NodeIdx, NodeIdx)>,
+    merge_distance: Option<S>,
+    tree_connections: Vec<TreeConnection>,
+    frontier_injection: Option<FrontierInjection<S>>,
+    length_decay: Option<LengthDecay>,
+    jitter: Option<Jitter<S>>,
+    next_attractor_id: u64,
+    spatial_index: Option<f32>,
+    done_conditions: DoneConditions,
+    quiet_iterations: u32,
+    world_scale: f32,
+    tropism: Option<Tropism<F, S>>,
+}
+
+/// Serializes like ```Parts```, without taking the simulation apart.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "Parts")]
+struct PartsRef<'a, P, F, I, S>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Copy + Debug + 'a,
+          S: Debug + 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    roots: &'a [RootState<F, I, S>],
+    attractors: &'a [Attractor<P, I, S>],
+    attractor_meta: &'a [AttractorMeta<F>],
+    attractor_counts: &'a BTreeMap<u32, AttractorCounts>,
+    default_attract_dist: SqDist<S>,
+    default_connect_dist: SqDist<S>,
+    move_dist: &'a Schedule<S>,
+    next_iteration: u32,
+    max_length: u32,
+    max_branches: u32,
+    node_limit: usize,
+    use_last_n_nodes: Option<usize>,
+    dormancy: Option<&'a Dormancy>,
+    oscillation: Option<&'a Oscillation<S>>,
+    information_mode: InformationMode,
+    connect_policy: ConnectPolicy,
+    connect_prefilter: Option<ConnectPrefilter>,
+    falloff: Falloff,
+    biomass: Option<Biomass<S>>,
+    attractor_order: AttractorOrder,
+    attractor_order_refresh: u32,
+    connection_quota: Option<usize>,
+    clusters: &'a [Cluster<P>],
+    cluster_options: Option<ClusterOptions>,
+    adaptive_step: Option<AdaptiveStep>,
+    connect_cooldown: Option<u32>,
+    exclude_roots: bool,
+    perception_angle: Option<f32>,
+    branch_angle: Option<BranchAngle>,
+    node_targeting: Option<NodeTargeting<S>>,
+    anastomosis: Option<Anastomosis<S>>,
+    bridges: &'a [(NodeIdx, NodeIdx)],
+    merge_distance: Option<S>,
+    tree_connections: &'a [TreeConnection],
+    frontier_injection: Option<FrontierInjection<S>>,
+    length_decay: Option<LengthDecay>,
+    jitter: Option<&'a Jitter<S>>,
+    next_attractor_id: u64,
+    spatial_index: Option<f32>,
+    done_conditions: DoneConditions,
+    quiet_iterations: u32,
+    world_scale: f32,
+    tropism: Option<&'a Tropism<F, S>>,
+}
+
+/// Returned by ```from_parts``` if the parts are inconsistent, e.g. because
+/// a node refers to a parent stored after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidParts {
+    pub message: String,
+}
+
+impl fmt::Display for InvalidParts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid simulation parts: {}", self.message)
+    }
+}
+
+impl Error for InvalidParts {
+    fn description(&self) -> &str {
+        "invalid simulation parts"
+    }
+}
+
+fn invalid<T>(message: &str) -> Result<T, InvalidParts> {
+    Err(InvalidParts { message: message.to_string() })
+}
+
+impl<P, F, I, S> Parts<P, F, I, S>
+    where P: Debug,
+          F: Debug,
+          I: Copy + Debug,
+          S: Debug
+{
+    fn validate(&self) -> Result<(), InvalidParts> {
+        let num_nodes = self.nodes.len();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.parent.0 as usize > i || node.root.0 > node.parent.0 {
+                return invalid("nodes must be stored after their parent and root");
+            }
+        }
+        let in_range = |idx: Option<NodeIdx>| idx.map_or(true, |idx| (idx.0 as usize) < num_nodes);
+        let roots = (0..num_nodes).filter(|&i| self.nodes[i].parent.0 as usize == i);
+        if !self.roots.iter().map(|state| state.node.0 as usize).eq(roots) {
+            return invalid("root states do not match the roots");
+        }
+        for ap in self.attractors.iter() {
+            if !in_range(ap.not_for_root) || !in_range(ap.not_for_connecting_root) {
+                return invalid("attractor refers to a missing node");
+            }
+        }
+        if self.attractor_meta.len() != self.attractors.len() {
+            return invalid("attractor bookkeeping does not match the attractors");
+        }
+        for meta in self.attractor_meta.iter() {
+            if !in_range(meta.nearest_root) || !in_range(meta.last_connection.map(|c| c.0)) {
+                return invalid("attractor refers to a missing node");
+            }
+        }
+        for &(tip, target) in self.bridges.iter() {
+            if !in_range(Some(tip)) || !in_range(Some(target)) {
+                return invalid("bridge refers to a missing node");
+            }
+        }
+        for c in self.tree_connections.iter() {
+            if !in_range(Some(c.node)) || !in_range(Some(c.root)) ||
+               !in_range(Some(c.other_root)) {
+                return invalid("tree connection refers to a missing node");
+            }
+        }
+        if self.move_dist.keyframes.is_empty() {
+            return invalid("empty schedule");
+        }
+        if let Some(ref dormancy) = self.dormancy {
+            if dormancy.since.len() != num_nodes {
+                return invalid("dormancy does not match the nodes");
+            }
+        }
+        if let Some(ref oscillation) = self.oscillation {
+            if oscillation.stalls.len() != num_nodes {
+                return invalid("oscillation does not match the nodes");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Takes the simulation apart, dropping everything which is not part of
+    /// its persistent state.
+    pub fn into_parts(self) -> Parts<P, F, I, S> {
+        let spatial_index = self.spatial_index_cell_size();
+        Parts {
+            nodes: self.nodes,
+            roots: self.roots,
+            attractors: self.attractors,
+            attractor_meta: self.attractor_meta,
+            attractor_counts: self.attractor_counts,
+            default_attract_dist: self.default_attract_dist,
+            default_connect_dist: self.default_connect_dist,
+            move_dist: self.move_dist,
+            next_iteration: self.next_iteration,
+            max_length: self.max_length,
+            max_branches: self.max_branches,
+            node_limit: self.node_limit,
+            use_last_n_nodes: self.use_last_n_nodes,
+            dormancy: self.dormancy,
+            oscillation: self.oscillation,
+            information_mode: self.information_mode,
+            connect_policy: self.connect_policy,
+            connect_prefilter: self.connect_prefilter,
+            falloff: self.falloff.persistent(),
+            biomass: self.biomass,
+            attractor_order: self.attractor_order,
+            attractor_order_refresh: self.attractor_order_refresh,
+            connection_quota: self.connection_quota,
+            clusters: self.clusters,
+            cluster_options: self.cluster_options,
+            adaptive_step: self.adaptive_step,
+            connect_cooldown: self.connect_cooldown,
+            exclude_roots: self.exclude_roots,
+            perception_angle: self.perception_angle,
+            branch_angle: self.branch_angle,
+            node_targeting: self.node_targeting,
+            anastomosis: self.anastomosis,
+            merge_distance: self.merge_distance,
+            bridges: self.bridges,
+            tree_connections: self.tree_connections,
+            frontier_injection: self.frontier_injection,
+            length_decay: self.length_decay,
+            jitter: self.jitter,
+            next_attractor_id: self.next_attractor_id,
+            spatial_index,
+            done_conditions: self.done_conditions,
+            quiet_iterations: self.quiet_iterations,
+            world_scale: self.world_scale,
+            tropism: self.tropism,
+        }
+    }
+
+    /// Resumes a simulation from ```parts```, continuing with identical
+    /// results.
+    pub fn from_parts(parts: Parts<P, F, I, S>)
+                      -> Result<SpaceColonization<P, F, I, S>, InvalidParts> {
+        parts.validate()?;
+        let mut sc = SpaceColonization::new(parts.default_attract_dist,
+                                            parts.default_connect_dist,
+                                            parts.max_length,
+                                            parts.max_branches,
+                                            S::zero());
+        for node in parts.nodes.into_iter() {
+            sc.push_node(node);
+        }
+        sc.roots = parts.roots;
+        sc.dormancy = parts.dormancy;
+        sc.oscillation = parts.oscillation;
+        sc.attractors = parts.attractors;
+        sc.attractor_meta = parts.attractor_meta;
+        sc.attractor_counts = parts.attractor_counts;
+        sc.move_dist = parts.move_dist;
+        sc.next_iteration = parts.next_iteration;
+        sc.set_node_limit(parts.node_limit);
+        sc.use_last_n_nodes = parts.use_last_n_nodes;
+        sc.information_mode = parts.information_mode;
+        sc.connect_policy = parts.connect_policy;
+        sc.connect_prefilter = parts.connect_prefilter;
+        sc.falloff = parts.falloff;
+        sc.biomass = parts.biomass;
+        sc.set_attractor_order(parts.attractor_order, parts.attractor_order_refresh);
+        sc.connection_quota = parts.connection_quota;
+        sc.clusters = parts.clusters;
+        sc.cluster_options = parts.cluster_options;
+        sc.adaptive_step = parts.adaptive_step;
+        sc.connect_cooldown = parts.connect_cooldown;
+        sc.exclude_roots = parts.exclude_roots;
+        sc.perception_angle = parts.perception_angle;
+        sc.branch_angle = parts.branch_angle;
+        sc.node_targeting = parts.node_targeting;
+        sc.anastomosis = parts.anastomosis;
+        sc.merge_distance = parts.merge_distance;
+        sc.tree_connections = parts.tree_connections;
+        sc.bridges = parts.bridges;
+        sc.frontier_injection = parts.frontier_injection;
+        sc.length_decay = parts.length_decay;
+        sc.jitter = parts.jitter;
+        sc.next_attractor_id = parts.next_attractor_id;
+        sc.set_spatial_index(parts.spatial_index);
+        sc.done_conditions = parts.done_conditions;
+        sc.quiet_iterations = parts.quiet_iterations;
+        sc.world_scale = parts.world_scale;
+        sc.tropism = parts.tropism;
+        Ok(sc)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<P, F, I, S> Serialize for SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug + Serialize,
+          F: Vector<S> + Copy + Debug + Serialize,
+          I: Copy + Default + Debug + Serialize,
+          S: Scalar + Debug + Serialize
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        PartsRef {
+                nodes: &self.nodes,
+                roots: &self.roots,
+                attractors: &self.attractors,
+                attractor_meta: &self.attractor_meta,
+                attractor_counts: &self.attractor_counts,
+                default_attract_dist: self.default_attract_dist,
+                default_connect_dist: self.default_connect_dist,
+                move_dist: &self.move_dist,
+                next_iteration: self.next_iteration,
+                max_length: self.max_length,
+                max_branches: self.max_branches,
+                node_limit: self.node_limit,
+                use_last_n_nodes: self.use_last_n_nodes,
+                dormancy: self.dormancy.as_ref(),
+                oscillation: self.oscillation.as_ref(),
+                information_mode: self.information_mode,
+                connect_policy: self.connect_policy,
+                connect_prefilter: self.connect_prefilter,
+                falloff: self.falloff.persistent(),
+                biomass: self.biomass,
+                attractor_order: self.attractor_order,
+                attractor_order_refresh: self.attractor_order_refresh,
+                connection_quota: self.connection_quota,
+                clusters: &self.clusters,
+                cluster_options: self.cluster_options,
+                adaptive_step: self.adaptive_step,
+                connect_cooldown: self.connect_cooldown,
+                exclude_roots: self.exclude_roots,
+                perception_angle: self.perception_angle,
+                branch_angle: self.branch_angle,
+                node_targeting: self.node_targeting,
+                anastomosis: self.anastomosis,
+                merge_distance: self.merge_distance,
+                tree_connections: &self.tree_connections,
+                bridges: &self.bridges,
+                frontier_injection: self.frontier_injection,
+                length_decay: self.length_decay,
+                jitter: self.jitter.as_ref(),
+                next_attractor_id: self.next_attractor_id,
+                spatial_index: self.spatial_index_cell_size(),
+                done_conditions: self.done_conditions,
+                quiet_iterations: self.quiet_iterations,
+                world_scale: self.world_scale,
+                tropism: self.tropism.as_ref(),
+            }
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, P, F, I, S> Deserialize<'de> for SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug + Deserialize<'de>,
+          F: Vector<S> + Copy + Debug + Deserialize<'de>,
+          I: Copy + Default + Debug + Deserialize<'de>,
+          S: Scalar + Debug + Deserialize<'de>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parts = Parts::deserialize(deserializer)?;
+        SpaceColonization::from_parts(parts).map_err(|err| de::Error::custom(err.message))
+    }
+}
@@ -0,0 +1,69 @@
+//! The perception cone: a node is only attracted by the attractors within
+//! an angle of its heading, the direction from its parent to it. This stops
+//! branches from doubling back towards attractors behind them. Roots have
+//! no heading and perceive every direction.
+//!
+//! The cone only limits the attraction. An attractor within the connect
+//! distance of a node is reached regardless of the direction.
+
+use position::{Position, Vector, Scalar};
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use super::{SpaceColonization, SearchContext, Node, Attractor};
+use manifold::Manifold;
+
+impl<'a, P, F, I, S> SearchContext<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Whether ```ap``` is within the perception cone of ```node```.
+    pub(crate) fn perceives(&self,
+                            node: &Node<P, F, I>,
+                            ap: &Attractor<P, I, S>,
+                            manifold: Option<&dyn Manifold<P, F>>)
+                            -> bool {
+        let min_cos = match self.perception_cos {
+            Some(min_cos) => min_cos,
+            None => return true,
+        };
+        if node.is_root() {
+            return true;
+        }
+        let parent = &self.nodes[node.parent.0 as usize].position;
+        let (heading, towards) = match manifold {
+            Some(manifold) => {
+                // both in the tangent space at the node.
+                (-manifold.log(&node.position, parent),
+                 manifold.log(&node.position, &ap.position))
+            }
+            None => (node.position - *parent, ap.position - node.position),
+        };
+        let norms = (heading.sqnorm() * towards.sqnorm()).sqrt();
+        norms == S::zero() || heading.dot(&towards) >= min_cos * norms
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Limits the attraction of every node to the attractors at most
+    /// ```max_angle``` radians off its heading. ```None``` (or an angle of
+    /// π) disables the cone.
+    pub fn set_perception_angle(&mut self, max_angle: Option<f32>) {
+        if let Some(max_angle) = max_angle {
+            assert!(max_angle >= 0.0);
+        }
+        self.perception_angle = max_angle.and_then(|max_angle| {
+            if max_angle >= PI { None } else { Some(max_angle) }
+        });
+    }
+
+    pub fn perception_angle(&self) -> Option<f32> {
+        self.perception_angle
+    }
+}
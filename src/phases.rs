@@ -0,0 +1,84 @@
+//! Sequences of named simulation phases, e.g. grow, prune and regrow. Each
+//! phase (a ```multires::Phase```) sets its parameters and attractors,
+//! optionally runs a setup step (such as pruning) and grows until its
+//! ```DoneConditions``` consider the growth done.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, CapacityError};
+use multires::Phase;
+use steps::DoneReason;
+
+/// The outcome of one phase of ```run_phases```.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseReport {
+    pub name: String,
+    pub iterations: u32,
+
+    /// Nodes grown during the phase.
+    pub new_nodes: usize,
+
+    /// Why the phase ended.
+    pub done_reason: DoneReason,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Runs the ```phases``` one after another and reports on each. A
+    /// phase subdivides the segments, sets its distances, clears the left
+    /// over attractors if asked to, calls ```setup``` with its index (e.g.
+    /// to prune or to change further parameters), adds its attractors and
+    /// grows with ```run_until_done```. Reaching the node limit ends a
+    /// phase with ```DoneReason::NodeLimit```; only the subdivision fails.
+    pub fn run_phases<C>(&mut self,
+                         phases: &[Phase<P, S>],
+                         mut setup: C)
+                         -> Result<Vec<PhaseReport>, CapacityError>
+        where C: FnMut(usize, &mut Self)
+    {
+        let mut reports = Vec::with_capacity(phases.len());
+        for (index, phase) in phases.iter().enumerate() {
+            self.subdivide_segments(phase.subdivisions)?;
+            self.default_attract_dist = phase.attract_dist;
+            self.default_connect_dist = phase.connect_dist;
+            self.set_move_dist(phase.move_dist);
+            if let Some(n) = phase.max_length {
+                self.max_length = n;
+            }
+            if let Some(n) = phase.max_branches {
+                self.max_branches = n;
+            }
+            if phase.clear_attractors {
+                while !self.attractors.is_empty() {
+                    let last = self.attractors.len() - 1;
+                    self.remove_attractor(last, false);
+                }
+                self.clusters.clear();
+            }
+            setup(index, self);
+            for position in phase.attractors.iter() {
+                self.add_default_attractor(*position);
+            }
+
+            // the quiet iterations of the previous phase do not count.
+            self.quiet_iterations = 0;
+            let (first_iteration, num_nodes) = (self.next_iteration, self.nodes.len());
+            let done_conditions = self.done_conditions;
+            self.done_conditions = phase.done_conditions;
+            let done_reason = self.run_until_done(phase.max_iterations);
+            self.done_conditions = done_conditions;
+
+            reports.push(PhaseReport {
+                name: phase.name.clone(),
+                iterations: self.next_iteration - first_iteration,
+                new_nodes: self.nodes.len() - num_nodes,
+                done_reason,
+            });
+        }
+        Ok(reports)
+    }
+}
@@ -0,0 +1,200 @@
+//! The geometry the simulation is generic over. Positions and vectors only
+//! need a handful of operations, so instead of depending on the traits of
+//! a particular math library, the crate defines its own and implements
+//! them for the common point and vector types:
+//!
+//! * ```nalgebra``` feature (default): ```Point2```/```Point3``` with
+//!   ```Vector2```/```Vector3```, of ```f32``` and ```f64```.
+//! * ```glam``` feature: ```Vec2```/```Vec3``` (and ```DVec2```/```DVec3```
+//!   for ```f64```), which serve as both position and vector.
+//!
+//! Other types can be used by implementing ```Coords```, ```Vector``` and
+//! ```Position```.
+//!
+//! The simulation works with either scalar type, but some helpers only
+//! support ```f32``` so far: the attractor generators,
+//! ```SpaceColonizationBuilder``` and the profiles, ```lsystem```,
+//! ```symmetry```, ```nested```, ```projection```, ```distance```,
+//! ```geodesic```, ```batch```, ```dataset``` and ```tune```.
+
+use num::Float;
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Sub, Mul, Div, Neg, Index, IndexMut};
+
+/// The scalar type of positions and vectors. ```Display``` is needed to
+/// write exports and snapshots.
+pub trait Scalar: Float + Debug + Display + Send + Sync + 'static {}
+
+impl Scalar for f32 {}
+impl Scalar for f64 {}
+
+/// A fixed number of coordinates, indexed by axis.
+pub trait Coords<S>: Copy + Index<usize, Output = S> + IndexMut<usize, Output = S> {
+    fn dim() -> usize;
+
+    /// All coordinates zero.
+    fn origin() -> Self;
+}
+
+/// The difference of two positions.
+pub trait Vector<S: Scalar>
+    : Copy + PartialEq + Add<Output = Self> + Sub<Output = Self> + Mul<S, Output = Self> +
+      Div<S, Output = Self> + Neg<Output = Self> + Index<usize, Output = S> {
+    fn dim() -> usize;
+
+    fn zero() -> Self;
+
+    /// The unit vector along ```axis```, ```None``` beyond the dimension.
+    fn basis_element(axis: usize) -> Option<Self>;
+
+    fn dot(&self, other: &Self) -> S;
+
+    fn is_zero(&self) -> bool {
+        (0..Self::dim()).all(|i| self[i] == S::zero())
+    }
+
+    fn sqnorm(&self) -> S {
+        self.dot(self)
+    }
+
+    fn norm(&self) -> S {
+        self.sqnorm().sqrt()
+    }
+
+    fn normalize(&self) -> Self {
+        *self / self.norm()
+    }
+}
+
+pub trait Position<S: Scalar, V: Vector<S>>
+    : Coords<S> + PartialEq + Add<V, Output = Self> + Sub<Self, Output = V> {
+    /// The vector from the origin to the position.
+    fn to_vector(&self) -> V {
+        *self - Self::origin()
+    }
+
+    fn sqdist(&self, other: &Self) -> S {
+        (*self - *other).sqnorm()
+    }
+
+    fn dist(&self, other: &Self) -> S {
+        self.sqdist(other).sqrt()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use na::{Point2, Point3, Vector2, Vector3};
+    use super::{Coords, Vector, Position};
+
+    macro_rules! impl_nalgebra {
+        ($point:ident, $vector:ident, $dim:expr, $s:ty) => {
+            impl Coords<$s> for $point<$s> {
+                fn dim() -> usize {
+                    $dim
+                }
+
+                fn origin() -> $point<$s> {
+                    $point::origin()
+                }
+            }
+
+            impl Vector<$s> for $vector<$s> {
+                fn dim() -> usize {
+                    $dim
+                }
+
+                fn zero() -> $vector<$s> {
+                    $vector::zeros()
+                }
+
+                fn basis_element(axis: usize) -> Option<$vector<$s>> {
+                    if axis < $dim {
+                        Some($vector::ith(axis, 1.0))
+                    } else {
+                        None
+                    }
+                }
+
+                fn dot(&self, other: &$vector<$s>) -> $s {
+                    $vector::dot(self, other)
+                }
+
+                fn norm(&self) -> $s {
+                    $vector::norm(self)
+                }
+
+                fn normalize(&self) -> $vector<$s> {
+                    $vector::normalize(self)
+                }
+            }
+
+            impl Position<$s, $vector<$s>> for $point<$s> {}
+        }
+    }
+
+    impl_nalgebra!(Point2, Vector2, 2, f32);
+    impl_nalgebra!(Point3, Vector3, 3, f32);
+    impl_nalgebra!(Point2, Vector2, 2, f64);
+    impl_nalgebra!(Point3, Vector3, 3, f64);
+}
+
+#[cfg(feature = "glam")]
+mod glam_impls {
+    use glam::{Vec2, Vec3, DVec2, DVec3};
+    use super::{Coords, Vector, Position};
+
+    macro_rules! impl_glam {
+        ($vec:ident, $dim:expr, $s:ty) => {
+            impl Coords<$s> for $vec {
+                fn dim() -> usize {
+                    $dim
+                }
+
+                fn origin() -> $vec {
+                    $vec::ZERO
+                }
+            }
+
+            impl Vector<$s> for $vec {
+                fn dim() -> usize {
+                    $dim
+                }
+
+                fn zero() -> $vec {
+                    $vec::ZERO
+                }
+
+                fn basis_element(axis: usize) -> Option<$vec> {
+                    if axis < $dim {
+                        let mut e = $vec::ZERO;
+                        e[axis] = 1.0;
+                        Some(e)
+                    } else {
+                        None
+                    }
+                }
+
+                fn dot(&self, other: &$vec) -> $s {
+                    $vec::dot(*self, *other)
+                }
+
+                fn norm(&self) -> $s {
+                    $vec::length(*self)
+                }
+
+                fn normalize(&self) -> $vec {
+                    $vec::normalize(*self)
+                }
+            }
+
+            // glam doesn't distinguish points from vectors.
+            impl Position<$s, $vec> for $vec {}
+        }
+    }
+
+    impl_glam!(Vec2, 2, f32);
+    impl_glam!(Vec3, 3, f32);
+    impl_glam!(DVec2, 2, f64);
+    impl_glam!(DVec3, 3, f64);
+}
@@ -0,0 +1,169 @@
+//! Cleaning up the grown structure, e.g. before exporting it. Nodes are
+//! placed ```move_dist``` apart and change direction abruptly where the
+//! attractors pulling them come and go. ```smooth``` evens out the kinks,
+//! ```resample``` changes the spacing of the nodes along the branches.
+//!
+//! Both only touch the nodes inside a branch, the chains of nodes with a
+//! single child. Roots, branch points, leaves and the ends of bridges stay
+//! where they are, so the shape of the structure as a graph is kept.
+//! Positions are interpolated in the ambient space, so nodes on a manifold
+//! may leave it slightly.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use std::mem;
+use super::{SpaceColonization, Node, NodeIdx, scalar};
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Laplacian smoothing: moves every node inside a branch ```lambda```
+    /// (0 to 1) of the way towards the midpoint between its parent and its
+    /// child, ```iterations``` times.
+    ///
+    /// # Panics
+    ///
+    /// If ```lambda``` is outside of 0 to 1.
+    pub fn smooth(&mut self, iterations: u32, lambda: S) {
+        assert!(lambda >= S::zero() && lambda <= S::one());
+        let fixed = self.fixed_nodes();
+        let half: S = scalar(0.5);
+        for _ in 0..iterations {
+            for i in (0..self.nodes.len()).filter(|&i| !fixed[i]) {
+                let parent = self.positions[self.parents[i] as usize];
+                let child = self.positions[self.children[i][0] as usize];
+                let midpoint = parent + (child - parent) * half;
+                let position = self.positions[i];
+                self.nodes[i].position = position + (midpoint - position) * lambda;
+            }
+            for (p, node) in self.positions.iter_mut().zip(self.nodes.iter()) {
+                *p = node.position;
+            }
+        }
+
+        let cell_size = self.spatial_index_cell_size();
+        self.set_spatial_index(cell_size);
+        self.clear_history();
+        self.update_capture_distances();
+        self.record_full_state();
+    }
+
+    /// Replaces the nodes inside every branch by nodes spaced evenly along
+    /// it, as close to ```target_segment_length``` apart as possible, so
+    /// that long branches are subdivided and short ones decimated. Every
+    /// branch keeps at least one segment. A new node takes the information,
+    /// branch limit and per-node data (e.g. segment attributes) of the old
+    /// node at the end of the segment it is placed on.
+    ///
+    /// Returns the new index of every old node, ```None``` for the nodes
+    /// inside branches, which are all replaced.
+    ///
+    /// # Panics
+    ///
+    /// If ```target_segment_length``` is not positive.
+    pub fn resample(&mut self, target_segment_length: S) -> Vec<Option<NodeIdx>> {
+        assert!(target_segment_length > S::zero());
+        let fixed = self.fixed_nodes();
+        let mut mapping: Vec<Option<NodeIdx>> = vec![None; self.nodes.len()];
+        let mut old_nodes: Vec<Option<Node<P, F, I>>> =
+            mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        let mut nodes: Vec<Node<P, F, I>> = Vec::new();
+        let mut sources: Vec<usize> = Vec::new();
+
+        for i in 0..old_nodes.len() {
+            if !fixed[i] {
+                continue;
+            }
+            let parent = self.parents[i] as usize;
+            if parent == i {
+                let mut root = old_nodes[i].take().unwrap();
+                root.parent = NodeIdx::of(nodes.len());
+                root.root = root.parent;
+                mapping[i] = Some(root.parent);
+                nodes.push(root);
+                sources.push(i);
+                continue;
+            }
+
+            // the branch from the previous fixed node down to i.
+            let mut chain = vec![i, parent];
+            while !fixed[*chain.last().unwrap()] {
+                let next = self.parents[*chain.last().unwrap()] as usize;
+                chain.push(next);
+            }
+            chain.reverse();
+            let mut lengths = vec![S::zero()];
+            for pair in chain.windows(2) {
+                let length = self.positions[pair[0]].dist(&self.positions[pair[1]]);
+                let total = *lengths.last().unwrap();
+                lengths.push(total + length);
+            }
+            let total = *lengths.last().unwrap();
+            let count = (total / target_segment_length).round().to_usize().unwrap_or(1).max(1);
+
+            let root = mapping[old_nodes[i].as_ref().unwrap().root.0 as usize].unwrap();
+            let mut previous = mapping[chain[0]].unwrap();
+            let mut segment = 1;
+            for k in 1..count {
+                let at = total * scalar(k as f32) / scalar(count as f32);
+                while segment + 1 < chain.len() && lengths[segment] < at {
+                    segment += 1;
+                }
+                let (a, b) = (chain[segment - 1], chain[segment]);
+                let length = lengths[segment] - lengths[segment - 1];
+                let t = if length > S::zero() {
+                    (at - lengths[segment - 1]) / length
+                } else {
+                    S::zero()
+                };
+                let (pa, pb) = (self.positions[a], self.positions[b]);
+                let template = old_nodes[b].as_ref().unwrap();
+                let node = Node {
+                    parent: previous,
+                    root,
+                    length: nodes[previous.0 as usize].length + 1,
+                    branches: 0,
+                    branch_limit: template.branch_limit,
+                    position: pa + (pb - pa) * t,
+                    growth: F::zero(),
+                    growth_count: 0,
+                    assigned_information: template.assigned_information,
+                    received_information: template.received_information.clone(),
+                };
+                previous = NodeIdx::of(nodes.len());
+                nodes.push(node);
+                sources.push(b);
+            }
+
+            let mut node = old_nodes[i].take().unwrap();
+            node.parent = previous;
+            node.root = root;
+            node.length = nodes[previous.0 as usize].length + 1;
+            mapping[i] = Some(NodeIdx::of(nodes.len()));
+            nodes.push(node);
+            sources.push(i);
+        }
+
+        self.install_nodes(nodes, &sources, &mapping);
+        mapping
+    }
+
+    /// Whether every node stays in place: roots, branch points, leaves and
+    /// the ends of bridges.
+    fn fixed_nodes(&self) -> Vec<bool> {
+        let mut fixed: Vec<bool> = (0..self.nodes.len())
+                                       .map(|i| {
+                                           self.parents[i] as usize == i ||
+                                           self.children[i].len() != 1
+                                       })
+                                       .collect();
+        for &(tip, target) in self.bridges.iter() {
+            fixed[tip.0 as usize] = true;
+            fixed[target.0 as usize] = true;
+        }
+        fixed
+    }
+}
@@ -0,0 +1,109 @@
+//! The kill distance pre-filter of the classic algorithm. An attractor
+//! which is already within its ```connect_dist``` of a node when it is
+//! added never takes part in the growth there. Without the pre-filter it
+//! only connects in the next iteration, if at all: the connection quota or
+//! ```use_last_n_nodes``` can keep it pulling at the nodes instead, which
+//! stalls the convergence.
+//!
+//! With the pre-filter, the connect action of such an attractor is applied
+//! right away, with all nodes and the same rules as connections (e.g.
+//! ```not_for_root``` or dormancy). Its information is not transmitted and
+//! the connection is not counted, but a removed attractor counts as killed.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, ConnectAction};
+use record::Event;
+
+/// When the connect actions of attractors within reach of a node are
+/// applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConnectPrefilter {
+    /// When they are added with ```add_attractor``` or one of the methods
+    /// built on it (e.g. ```extend_attractors```), if they are active in
+    /// the next iteration.
+    OnAdd,
+
+    /// Also for all active attractors at the start of every iteration,
+    /// e.g. those which became active or were moved.
+    EveryIteration,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// ```None``` (the default) lets attractors within reach connect in the
+    /// iterations. Existing attractors are not filtered.
+    pub fn set_connect_prefilter(&mut self, prefilter: Option<ConnectPrefilter>) {
+        self.connect_prefilter = prefilter;
+    }
+
+    pub fn connect_prefilter(&self) -> Option<ConnectPrefilter> {
+        self.connect_prefilter
+    }
+
+    /// Filters the attractor added last, if enabled.
+    pub(crate) fn prefilter_new_attractor(&mut self) {
+        if self.connect_prefilter.is_some() {
+            let ap_idx = self.attractors.len() - 1;
+            let next_iteration = self.next_iteration;
+            self.prefilter_attractor(ap_idx, next_iteration);
+        }
+    }
+
+    /// Filters all attractors at the start of ```current_iteration```, if
+    /// enabled for every iteration.
+    pub(crate) fn prefilter_attractors(&mut self, current_iteration: u32) {
+        if self.connect_prefilter != Some(ConnectPrefilter::EveryIteration) {
+            return;
+        }
+        let mut ap_idx = 0;
+        while ap_idx < self.attractors.len() {
+            if !self.prefilter_attractor(ap_idx, current_iteration) {
+                ap_idx += 1;
+            }
+        }
+    }
+
+    /// Applies the connect action of the attractor at ```ap_idx``` if it
+    /// is within reach of a node in ```iteration```. Returns whether it was
+    /// removed.
+    fn prefilter_attractor(&mut self, ap_idx: usize, iteration: u32) -> bool {
+        let node_idx = {
+            let metric = self.metric.as_deref();
+            let manifold = self.manifold.as_deref();
+            match self.search_context(iteration, 0).search(ap_idx, metric, manifold).connect {
+                Some(node_idx) => node_idx,
+                None => return false,
+            }
+        };
+        let ap = self.attractors[ap_idx];
+        match ap.connect_action {
+            ConnectAction::KillAttractor => {
+                let id = self.attractor_meta[ap_idx].id;
+                self.remove_attractor(ap_idx, true);
+                self.notify(|o| o.on_attractor_killed(&ap, id, node_idx, iteration));
+                return true;
+            }
+            ConnectAction::DisableFor { iterations } => {
+                let event = Event::AttractorDisabled {
+                    index: ap_idx,
+                    until: iteration + iterations,
+                };
+                self.note_attractor(&event);
+                self.attractors[ap_idx].disable_until(iteration + iterations);
+                self.record(event);
+            }
+            ConnectAction::DisableForConnectingRoot => {
+                let root = self.nodes[node_idx.0 as usize].root;
+                self.note_attractor_state(ap_idx);
+                self.attractors[ap_idx].not_for_connecting_root = Some(root);
+            }
+        }
+        false
+    }
+}
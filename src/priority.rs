@@ -0,0 +1,94 @@
+//! The order in which attractors are processed within a step. It matters
+//! when the number of connections per step is limited: the most influential
+//! attractors should be handled first.
+
+use position::{Position, Vector, Scalar};
+use std::cmp::{self, Ordering};
+use std::fmt::Debug;
+use super::{SpaceColonization, SqDist};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AttractorOrder {
+    /// The order in which attractors were added (attractors removed during
+    /// a step are replaced by the last one).
+    Insertion,
+
+    /// Strongest attractors first.
+    Strength,
+
+    /// Attractors nearest to an active node first.
+    FrontierProximity,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Sorts the attractors by ```order``` every ```refresh_every```
+    /// iterations (at least 1) before processing them.
+    pub fn set_attractor_order(&mut self, order: AttractorOrder, refresh_every: u32) {
+        self.attractor_order = order;
+        self.attractor_order_refresh = cmp::max(refresh_every, 1);
+    }
+
+    /// Limits the number of connections per step. Once reached, the
+    /// remaining attractors are not processed in that step.
+    pub fn set_connection_quota(&mut self, quota: Option<usize>) {
+        self.connection_quota = quota;
+    }
+
+    /// Square distance from ```position``` to the nearest active node.
+    pub(crate) fn sqdist_to_frontier(&self, position: &P, start_index: usize) -> S {
+        let mut nearest = S::infinity();
+        for node in self.nodes[start_index..].iter() {
+            if !node.is_active(&self.roots, self.max_length, self.max_branches) {
+                continue;
+            }
+            let SqDist(d) = self.sqdist_to_node(&node.position, position);
+            if d < nearest {
+                nearest = d;
+            }
+        }
+        nearest
+    }
+
+    /// Reorders the attractors if the order is due for a refresh.
+    pub(crate) fn prioritize_attractors(&mut self, current_iteration: u32, start_index: usize) {
+        if self.attractor_order == AttractorOrder::Insertion ||
+           current_iteration % self.attractor_order_refresh != 0 {
+            return;
+        }
+
+        let keys: Vec<f64> = match self.attractor_order {
+            AttractorOrder::Insertion => return,
+            AttractorOrder::Strength => {
+                self.attractors
+                    .iter()
+                    .map(|a| -a.strength_in(current_iteration) as f64)
+                    .collect()
+            }
+            AttractorOrder::FrontierProximity => {
+                self.attractors
+                    .iter()
+                    .map(|a| self.sqdist_to_frontier(&a.position, start_index))
+                    .map(|d| d.to_f64().unwrap())
+                    .collect()
+            }
+        };
+
+        let mut permutation: Vec<usize> = (0..keys.len()).collect();
+        // stable, so that ties keep their relative order.
+        permutation.sort_by(|&a, &b| keys[a].partial_cmp(&keys[b]).unwrap_or(Ordering::Equal));
+        if permutation.iter().enumerate().all(|(i, &p)| i == p) {
+            return;
+        }
+
+        self.note_reordered(&permutation);
+        self.attractors = permutation.iter().map(|&i| self.attractors[i]).collect();
+        self.attractor_meta = permutation.iter().map(|&i| self.attractor_meta[i]).collect();
+        self.record_full_state();
+    }
+}
@@ -0,0 +1,197 @@
+//! Named parameter bundles for common kinds of structures.
+//!
+//! All profiles assume attractors spread over a volume (or area) of about
+//! two units across, e.g. ```[-1, 1]``` along each axis, like in the
+//! examples. For other sizes, scale all distances together, e.g. with
+//! ```SpaceColonizationBuilder::world_scale```.
+
+use builder::SpaceColonizationBuilder;
+use super::{SqDist, Biomass};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Profile {
+    /// A single tree with a compact crown.
+    SmallTree,
+
+    /// A tree with a wide crown and many fine twigs.
+    LargeTree,
+
+    /// Many short branches from the ground, densely filled.
+    Shrub,
+
+    /// Long, sparsely branching roots, limited by a biomass budget.
+    RootSystem,
+
+    /// Thin, long reaching dendrites with few branches per node.
+    Neuron,
+
+    /// Dense, fine venation of a leaf blade (2D).
+    LeafVeins,
+
+    /// Thick, stubby and highly branched growth.
+    Coral,
+}
+
+impl Profile {
+    pub fn all() -> &'static [Profile] {
+        static ALL: [Profile; 7] = [Profile::SmallTree,
+                                    Profile::LargeTree,
+                                    Profile::Shrub,
+                                    Profile::RootSystem,
+                                    Profile::Neuron,
+                                    Profile::LeafVeins,
+                                    Profile::Coral];
+        &ALL
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Profile::SmallTree => "small-tree",
+            Profile::LargeTree => "large-tree",
+            Profile::Shrub => "shrub",
+            Profile::RootSystem => "root-system",
+            Profile::Neuron => "neuron",
+            Profile::LeafVeins => "leaf-veins",
+            Profile::Coral => "coral",
+        }
+    }
+
+    /// The profile with the given ```name```.
+    pub fn from_name(name: &str) -> Option<Profile> {
+        Profile::all().iter().cloned().find(|p| p.name() == name)
+    }
+
+    /// A reasonable number of attractors to scatter for this profile.
+    pub fn num_attractors(&self) -> usize {
+        match *self {
+            Profile::SmallTree => 1000,
+            Profile::LargeTree => 4000,
+            Profile::Shrub => 2000,
+            Profile::RootSystem => 1500,
+            Profile::Neuron => 800,
+            Profile::LeafVeins => 3000,
+            Profile::Coral => 1500,
+        }
+    }
+
+    /// A builder preset with the parameters of this profile.
+    pub fn builder(&self) -> SpaceColonizationBuilder {
+        let b = SpaceColonizationBuilder::new();
+        match *self {
+            Profile::SmallTree => {
+                b.attract_dist(SqDist::from_dist(0.25))
+                 .connect_dist(SqDist::from_dist(0.08))
+                 .move_dist(0.04)
+                 .max_length(100)
+                 .max_branches(4)
+            }
+            Profile::LargeTree => {
+                b.attract_dist(SqDist::from_dist(0.2))
+                 .connect_dist(SqDist::from_dist(0.05))
+                 .move_dist(0.025)
+                 .max_length(200)
+                 .max_branches(5)
+            }
+            Profile::Shrub => {
+                b.attract_dist(SqDist::from_dist(0.15))
+                 .connect_dist(SqDist::from_dist(0.05))
+                 .move_dist(0.03)
+                 .max_length(80)
+                 .max_branches(6)
+            }
+            Profile::RootSystem => {
+                b.attract_dist(SqDist::from_dist(0.35))
+                 .connect_dist(SqDist::from_dist(0.06))
+                 .move_dist(0.03)
+                 .max_length(300)
+                 .max_branches(3)
+                 .biomass(Biomass {
+                     initial: 20.0,
+                     per_connection: 0.05,
+                 })
+            }
+            Profile::Neuron => {
+                b.attract_dist(SqDist::from_dist(0.4))
+                 .connect_dist(SqDist::from_dist(0.05))
+                 .move_dist(0.02)
+                 .max_length(400)
+                 .max_branches(2)
+            }
+            Profile::LeafVeins => {
+                b.attract_dist(SqDist::from_dist(0.1))
+                 .connect_dist(SqDist::from_dist(0.02))
+                 .move_dist(0.01)
+                 .max_length(300)
+                 .max_branches(3)
+            }
+            Profile::Coral => {
+                b.attract_dist(SqDist::from_dist(0.12))
+                 .connect_dist(SqDist::from_dist(0.06))
+                 .move_dist(0.04)
+                 .max_length(40)
+                 .max_branches(8)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod tests {
+    use na::{Point2, Point3, Vector2, Vector3};
+    use position::{Position, Vector};
+    use std::fmt::Debug;
+    use fixtures::scattered_points;
+    use steps::DoneReason;
+    use super::Profile;
+    use SpaceColonization;
+
+    const NODE_BUDGET: usize = 500;
+    const NODE_LIMIT: usize = 10000;
+    const MAX_ITERATIONS: u32 = 60;
+
+    /// Grows ```profile``` from the origin into the fixture's attractors.
+    /// Returns the number of nodes and iterations, and why the growth is
+    /// done.
+    fn grow<P, F>(profile: Profile) -> (usize, u32, DoneReason)
+        where P: Position<f32, F> + Debug,
+              F: Vector<f32> + Copy + Debug
+    {
+        let mut sc: SpaceColonization<P, F, ()> = profile.builder()
+                                                         .node_limit(NODE_LIMIT)
+                                                         .node_budget(NODE_BUDGET)
+                                                         .max_iterations(MAX_ITERATIONS)
+                                                         .build();
+        sc.add_root_node(P::origin());
+        for position in scattered_points(profile.num_attractors()) {
+            sc.add_default_attractor(position);
+        }
+        let reason = sc.run_until_done(2 * MAX_ITERATIONS);
+        (sc.num_nodes(), sc.view().next_iteration(), reason)
+    }
+
+    #[test]
+    fn profiles_grow_within_bounds() {
+        for &profile in Profile::all() {
+            let (nodes, iterations, reason) = match profile {
+                Profile::LeafVeins => grow::<Point2<f32>, Vector2<f32>>(profile),
+                _ => grow::<Point3<f32>, Vector3<f32>>(profile),
+            };
+            assert!(nodes > 1, "{} did not grow", profile.name());
+            assert!(nodes < NODE_LIMIT && reason != DoneReason::NodeLimit,
+                    "{} reached the node limit",
+                    profile.name());
+            assert!(reason != DoneReason::NodeBudget || nodes >= NODE_BUDGET);
+            assert!(iterations <= MAX_ITERATIONS,
+                    "{} ran {} iterations",
+                    profile.name(),
+                    iterations);
+        }
+    }
+
+    #[test]
+    fn profiles_round_trip_names() {
+        for &profile in Profile::all() {
+            assert_eq!(Profile::from_name(profile.name()), Some(profile));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Projecting the skeleton onto a plane, e.g. to plot a clean 2D rendition
+//! of a 3D growth.
+
+use position::{Position, Vector};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+
+/// A plane through ```origin``` spanned by the orthonormal ```u``` and
+/// ```v```. ```normal``` points towards the viewer and is only used to
+/// determine which segments are in front.
+#[derive(Debug, Copy, Clone)]
+pub struct ProjectionPlane<P, F> {
+    pub origin: P,
+    pub u: F,
+    pub v: F,
+    pub normal: F,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum HiddenSegments<'a> {
+    /// Project all segments.
+    Keep,
+
+    /// Drop the parts of segments that are behind a nearer segment,
+    /// modelled as a tube with the radii of its two nodes (indexed by
+    /// ```NodeIdx```). Every segment is tested at ```samples``` points, so
+    /// visible pieces are split at that resolution. Quadratic in the
+    /// number of segments.
+    Remove {
+        radii: &'a [f32],
+        samples: u32,
+    },
+}
+
+/// The visible (part of the) segment from the parent of ```node``` to
+/// ```node```, in plane coordinates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProjectedSegment {
+    pub node: NodeIdx,
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+}
+
+// a segment in plane coordinates, with the depth of its ends.
+struct Flat {
+    a: [f32; 2],
+    b: [f32; 2],
+    depth_a: f32,
+    depth_b: f32,
+    radius_a: f32,
+    radius_b: f32,
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+impl Flat {
+    fn at(&self, t: f32) -> ([f32; 2], f32) {
+        ([lerp(self.a[0], self.b[0], t), lerp(self.a[1], self.b[1], t)],
+         lerp(self.depth_a, self.depth_b, t))
+    }
+
+    /// Whether the point ```q``` at ```depth``` is behind this segment.
+    fn occludes(&self, q: &[f32; 2], depth: f32) -> bool {
+        let d = [self.b[0] - self.a[0], self.b[1] - self.a[1]];
+        let len2 = d[0] * d[0] + d[1] * d[1];
+        let t = if len2 > 0.0 {
+            (((q[0] - self.a[0]) * d[0] + (q[1] - self.a[1]) * d[1]) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (p, p_depth) = self.at(t);
+        let radius = lerp(self.radius_a, self.radius_b, t);
+        let (dx, dy) = (q[0] - p[0], q[1] - p[1]);
+        // the front of the tube is ```radius``` nearer than its axis.
+        dx * dx + dy * dy < radius * radius && p_depth > depth + radius
+    }
+}
+
+impl<P, F, I> SpaceColonization<P, F, I>
+    where P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    /// Projects all segments orthographically onto ```plane```.
+    pub fn project(&self,
+                   plane: &ProjectionPlane<P, F>,
+                   hidden: HiddenSegments)
+                   -> Vec<ProjectedSegment> {
+        let flatten = |p: &P| {
+            let d = *p - plane.origin;
+            ([d.dot(&plane.u), d.dot(&plane.v)], d.dot(&plane.normal))
+        };
+
+        let mut nodes = Vec::new();
+        let mut flats = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.is_root() {
+                continue;
+            }
+            let parent = node.parent.0 as usize;
+            let (a, depth_a) = flatten(&self.nodes[parent].position);
+            let (b, depth_b) = flatten(&node.position);
+            let (radius_a, radius_b) = match hidden {
+                HiddenSegments::Keep => (0.0, 0.0),
+                HiddenSegments::Remove { radii, .. } => (radii[parent], radii[i]),
+            };
+            nodes.push(NodeIdx::of(i));
+            flats.push(Flat {
+                a,
+                b,
+                depth_a,
+                depth_b,
+                radius_a,
+                radius_b,
+            });
+        }
+
+        let samples = match hidden {
+            HiddenSegments::Keep => {
+                return nodes.iter()
+                            .zip(flats.iter())
+                            .map(|(&node, f)| {
+                                ProjectedSegment {
+                                    node,
+                                    from: f.a,
+                                    to: f.b,
+                                }
+                            })
+                            .collect();
+            }
+            HiddenSegments::Remove { radii, samples } => {
+                assert!(radii.len() == self.nodes.len());
+                ::std::cmp::max(samples, 1)
+            }
+        };
+
+        let mut segments = Vec::new();
+        for (k, flat) in flats.iter().enumerate() {
+            // start of the current visible piece.
+            let mut start: Option<f32> = None;
+            for j in 0..(samples + 1) {
+                let visible = j < samples && {
+                    let t = (j as f32 + 0.5) / samples as f32;
+                    let (q, depth) = flat.at(t);
+                    !flats.iter()
+                          .enumerate()
+                          .any(|(other, f)| other != k && f.occludes(&q, depth))
+                };
+                let t = j as f32 / samples as f32;
+                match (visible, start) {
+                    (true, None) => start = Some(t),
+                    (false, Some(s)) => {
+                        segments.push(ProjectedSegment {
+                            node: nodes[k],
+                            from: flat.at(s).0,
+                            to: flat.at(t).0,
+                        });
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        segments
+    }
+}
@@ -0,0 +1,131 @@
+//! Tracking which attractors a node grew towards, e.g. to debug why a branch
+//! went somewhere or to color a structure by the group of the attractors
+//! that shaped it. Attractors are identified by a stable id (see
+//! ```attractor_id```), as their indices change when others are removed.
+//!
+//! Only attractors are tracked, not fields or node targeting. Provenance is
+//! not part of snapshots.
+
+use position::{Position, Vector, Scalar};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+
+/// An attractor's share in the growth of a node.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Contribution {
+    pub attractor: u64,
+    pub group: u32,
+
+    /// The attractor's strength relative to all contributions to the node
+    /// (the weights of a node sum up to 1).
+    pub weight: f32,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Provenance {
+    // indexed by ```NodeIdx```.
+    nodes: Vec<Vec<Contribution>>,
+    // contributions of the current iteration, by the index of the attracted
+    // node.
+    pending: HashMap<usize, Vec<Contribution>>,
+}
+
+impl Provenance {
+    pub(crate) fn push_node(&mut self) {
+        self.nodes.push(Vec::new());
+    }
+
+    /// See ```SegmentAttributes::remap```.
+    pub(crate) fn remap(&mut self, sources: &[usize]) {
+        self.nodes = sources.iter().map(|&i| self.nodes[i].clone()).collect();
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Starts recording the contributions to every new node. Existing nodes
+    /// have none.
+    pub fn enable_provenance(&mut self) {
+        if self.provenance.is_none() {
+            self.provenance = Some(Provenance {
+                nodes: vec![Vec::new(); self.nodes.len()],
+                ..Provenance::default()
+            });
+        }
+    }
+
+    pub fn disable_provenance(&mut self) {
+        self.provenance = None;
+    }
+
+    /// The attractors ```node``` grew towards. ```None``` if provenance is
+    /// disabled.
+    pub fn provenance(&self, node: NodeIdx) -> Option<&[Contribution]> {
+        self.provenance.as_ref().map(|p| &p.nodes[node.0 as usize][..])
+    }
+
+    /// The group with the largest summed weight for every node, indexed by
+    /// ```NodeIdx```. ```None``` if provenance is disabled.
+    pub fn dominant_groups(&self) -> Option<Vec<Option<u32>>> {
+        self.provenance.as_ref().map(|p| {
+            p.nodes
+             .iter()
+             .map(|contributions| {
+                 let mut weights: HashMap<u32, f32> = HashMap::new();
+                 for c in contributions.iter() {
+                     *weights.entry(c.group).or_insert(0.0) += c.weight;
+                 }
+                 weights.into_iter()
+                        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(b.0.cmp(&a.0)))
+                        .map(|(group, _)| group)
+             })
+             .collect()
+        })
+    }
+
+    /// The stable id of the attractor at ```idx```.
+    pub fn attractor_id(&self, idx: usize) -> u64 {
+        self.attractor_meta[idx].id
+    }
+
+    /// Notes that the attractor at ```ap_idx``` attracted ```node``` with
+    /// ```strength```.
+    pub(crate) fn note_contribution(&mut self, node: NodeIdx, ap_idx: usize, strength: f32) {
+        if let Some(ref mut provenance) = self.provenance {
+            provenance.pending.entry(node.0 as usize).or_insert_with(Vec::new).push(Contribution {
+                attractor: self.attractor_meta[ap_idx].id,
+                group: self.attractors[ap_idx].group,
+                weight: strength,
+            });
+        }
+    }
+
+    /// Assigns the contributions to ```parent``` to its new (last) child.
+    pub(crate) fn assign_provenance(&mut self, parent: NodeIdx) {
+        let child = self.nodes.len() - 1;
+        if let Some(ref mut provenance) = self.provenance {
+            let mut contributions = provenance.pending
+                                              .remove(&(parent.0 as usize))
+                                              .unwrap_or_else(Vec::new);
+            let total: f32 = contributions.iter().map(|c| c.weight).sum();
+            if total > 0.0 {
+                for c in contributions.iter_mut() {
+                    c.weight /= total;
+                }
+            }
+            provenance.nodes[child] = contributions;
+        }
+    }
+
+    /// Drops the contributions to nodes which did not grow.
+    pub(crate) fn clear_pending_provenance(&mut self) {
+        if let Some(ref mut provenance) = self.provenance {
+            provenance.pending.clear();
+        }
+    }
+}
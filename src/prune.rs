@@ -0,0 +1,85 @@
+//! Removal of unwanted nodes.
+//!
+//! Removing nodes compacts the node storage, so every method returns the
+//! new index of every old node (```None``` if it was removed), to update
+//! any ```NodeIdx``` held outside of the simulation. Attractors, bridges
+//! and the per-node data are updated internally. Removing a root removes
+//! its whole tree.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Node, NodeIdx};
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Removes ```node``` and all its descendants. Its parent may grow
+    /// another branch in its place.
+    pub fn prune_subtree(&mut self, node: NodeIdx) -> Vec<Option<NodeIdx>> {
+        let node = node.0 as usize;
+        let mut keep = vec![true; self.nodes.len()];
+        keep[node] = false;
+        // children are always stored after their parent.
+        for i in (node + 1)..self.nodes.len() {
+            if !self.nodes[i].is_root() && !keep[self.nodes[i].parent.0 as usize] {
+                keep[i] = false;
+            }
+        }
+        self.retain_nodes_by_mask(&keep)
+    }
+
+    /// Removes every leaf (other than a root without children) for which
+    /// ```predicate``` returns true. The parents of the removed leaves are
+    /// not checked again, so repeat to trim whole dead branches.
+    pub fn prune_leaves<C>(&mut self, mut predicate: C) -> Vec<Option<NodeIdx>>
+        where C: FnMut(NodeIdx, &Node<P, F, I>) -> bool
+    {
+        let keep: Vec<bool> = self.nodes
+                                  .iter()
+                                  .enumerate()
+                                  .map(|(i, node)| {
+                                      node.is_root() || !node.is_leaf() ||
+                                      !predicate(NodeIdx::of(i), node)
+                                  })
+                                  .collect();
+        self.retain_nodes_by_mask(&keep)
+    }
+
+    /// Keeps only the nodes for which ```predicate``` returns true. The
+    /// descendants of a removed node are removed as well, without calling
+    /// ```predicate``` for them.
+    pub fn retain_nodes<C>(&mut self, mut predicate: C) -> Vec<Option<NodeIdx>>
+        where C: FnMut(NodeIdx, &Node<P, F, I>) -> bool
+    {
+        let mut keep = vec![false; self.nodes.len()];
+        // parents are always stored before their children.
+        for (i, node) in self.nodes.iter().enumerate() {
+            let parent_kept = node.is_root() || keep[node.parent.0 as usize];
+            keep[i] = parent_kept && predicate(NodeIdx::of(i), node);
+        }
+        self.retain_nodes_by_mask(&keep)
+    }
+
+    /// Removes all maximal subtrees in which no node has information
+    /// assigned, i.e. all the growth that never reached a target. Root
+    /// nodes are always kept. Returns the new index of every old node.
+    pub fn prune_uninformed(&mut self) -> Vec<Option<NodeIdx>> {
+        let n = self.nodes.len();
+        let mut keep: Vec<bool> = self.nodes
+                                      .iter()
+                                      .map(|node| {
+                                          node.is_root() || node.assigned_information.is_some()
+                                      })
+                                      .collect();
+        // children are always stored after their parent.
+        for i in (0..n).rev() {
+            if keep[i] && !self.nodes[i].is_root() {
+                keep[self.nodes[i].parent.0 as usize] = true;
+            }
+        }
+        self.retain_nodes_by_mask(&keep)
+    }
+}
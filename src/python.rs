@@ -0,0 +1,147 @@
+//! Python bindings. Attractors and roots are passed in as numpy arrays of
+//! shape ```(n, 2)``` or ```(n, 3)```, node positions and parents are
+//! returned as numpy arrays.
+
+use na::{Point3, Vector3};
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use super::{SpaceColonization, SqDist, IndexType};
+
+#[pyclass(name = "SpaceColonization", unsendable)]
+pub struct PySpaceColonization {
+    inner: SpaceColonization<Point3<f32>, Vector3<f32>, ()>,
+
+    /// 2 or 3, taken from the first array passed in.
+    dim: Option<usize>,
+}
+
+impl PySpaceColonization {
+    fn points(&mut self, array: PyReadonlyArray2<f32>) -> PyResult<Vec<Point3<f32>>> {
+        let array = array.as_array();
+        let dim = array.ncols();
+        if dim != 2 && dim != 3 {
+            return Err(PyValueError::new_err("expected an array of shape (n, 2) or (n, 3)"));
+        }
+        match self.dim {
+            Some(d) if d != dim => {
+                return Err(PyValueError::new_err(format!("expected {} columns", d)));
+            }
+            _ => self.dim = Some(dim),
+        }
+        Ok(array.rows()
+                .into_iter()
+                .map(|row| Point3::new(row[0], row[1], if dim == 3 { row[2] } else { 0.0 }))
+                .collect())
+    }
+}
+
+#[pymethods]
+impl PySpaceColonization {
+    /// Distances are plain (not squared) distances.
+    #[new]
+    #[pyo3(signature = (attract_dist,
+                        connect_dist,
+                        move_dist,
+                        max_length = 100,
+                        max_branches = 10))]
+    fn new(attract_dist: f32,
+           connect_dist: f32,
+           move_dist: f32,
+           max_length: u32,
+           max_branches: u32)
+           -> PySpaceColonization {
+        PySpaceColonization {
+            inner: SpaceColonization::new(SqDist::from_dist(attract_dist),
+                                          SqDist::from_dist(connect_dist),
+                                          max_length,
+                                          max_branches,
+                                          move_dist),
+            dim: None,
+        }
+    }
+
+    fn add_roots(&mut self, roots: PyReadonlyArray2<f32>) -> PyResult<()> {
+        for p in self.points(roots)? {
+            self.inner.add_root_node(p);
+        }
+        Ok(())
+    }
+
+    fn add_attractors(&mut self, attractors: PyReadonlyArray2<f32>) -> PyResult<()> {
+        let points = self.points(attractors)?;
+        self.inner.add_default_attractors_from(&points);
+        Ok(())
+    }
+
+    /// Performs one iteration. Returns the number of new nodes.
+    fn step(&mut self) -> usize {
+        self.inner.next().unwrap_or(0)
+    }
+
+    /// Iterates until no new nodes are grown, at most ```max_iterations```
+    /// times. Returns the number of iterations.
+    fn run(&mut self, max_iterations: u32) -> u32 {
+        let mut iterations = 0;
+        while iterations < max_iterations {
+            iterations += 1;
+            if self.step() == 0 {
+                break;
+            }
+        }
+        iterations
+    }
+
+    #[getter]
+    fn num_nodes(&self) -> usize {
+        self.inner.num_nodes()
+    }
+
+    #[getter]
+    fn num_attractors(&self) -> usize {
+        self.inner.num_attractors()
+    }
+
+    /// Node positions as an array of shape ```(num_nodes, dim)```.
+    fn positions<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        let dim = self.dim.unwrap_or(3);
+        let mut data = Vec::with_capacity(dim * self.inner.num_nodes());
+        for p in self.inner.positions().iter() {
+            data.push(p.x);
+            data.push(p.y);
+            if dim == 3 {
+                data.push(p.z);
+            }
+        }
+        Array2::from_shape_vec((self.inner.num_nodes(), dim), data)
+            .expect("shape matches the data")
+            .into_pyarray(py)
+    }
+
+    /// The parent index of every node. Roots are their own parent.
+    fn parents<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<IndexType>> {
+        self.inner.parents().to_vec().into_pyarray(py)
+    }
+
+    /// Attractor positions as an array of shape ```(num_attractors, dim)```.
+    fn attractors<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        let dim = self.dim.unwrap_or(3);
+        let mut data = Vec::with_capacity(dim * self.inner.num_attractors());
+        self.inner.visit_attractor_points(&mut |p| {
+            data.push(p.x);
+            data.push(p.y);
+            if dim == 3 {
+                data.push(p.z);
+            }
+        });
+        Array2::from_shape_vec((self.inner.num_attractors(), dim), data)
+            .expect("shape matches the data")
+            .into_pyarray(py)
+    }
+}
+
+#[pymodule]
+fn space_colonization(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PySpaceColonization>()
+}
@@ -0,0 +1,163 @@
+//! Branch radius models.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Node, NodeIdx, scalar};
+use view::SpaceColonizationView;
+
+/// Radius = ```scale``` · (number of descendant leaves)^```exponent```.
+#[derive(Debug, Copy, Clone)]
+pub struct LeafCountRadius<S = f32> {
+    pub scale: S,
+    pub exponent: S,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// The number of leaves in the subtree of every node (a leaf counts
+    /// itself), indexed by ```NodeIdx```.
+    pub fn descendant_leaf_counts(&self) -> Vec<u32> {
+        self.view().descendant_leaf_counts()
+    }
+
+    /// The flow through every node towards its root (canalisation), indexed
+    /// by ```NodeIdx```: every leaf contributes ```leaf_value```, which is
+    /// passed on to all nodes on the way to the root. Derive vein widths or
+    /// intensities from it, e.g. with a power law.
+    pub fn accumulate_flow(&self, leaf_value: S) -> Vec<S> {
+        self.view().accumulate_flow(leaf_value)
+    }
+
+    /// Like ```accumulate_flow```, with a contribution of every node given
+    /// by ```source```, e.g. to weight the leaves by their tree or length.
+    pub fn accumulate_flow_with<C>(&self, source: C) -> Vec<S>
+        where C: FnMut(NodeIdx, &Node<P, F, I>) -> S
+    {
+        self.view().accumulate_flow_with(source)
+    }
+
+    /// Capacity-like radii from the number of descendant leaves, indexed
+    /// by ```NodeIdx```. ```params``` returns the parameters of the tree
+    /// rooted at the given root node, so each tree can be scaled
+    /// differently.
+    pub fn radii_from_leaf_count<R>(&self, params: R) -> Vec<S>
+        where R: Fn(NodeIdx) -> LeafCountRadius<S>
+    {
+        self.view().radii_from_leaf_count(params)
+    }
+
+    /// Radii following the pipe model (Murray's law for ```exponent = 3```),
+    /// indexed by ```NodeIdx```: every leaf gets ```leaf_radius```, and the
+    /// radius of any other node ```r``` satisfies ```r^exponent = sum of
+    /// the children's r^exponent```. Da Vinci's rule is ```exponent = 2```.
+    pub fn compute_radii(&self, leaf_radius: S, exponent: S) -> Vec<S> {
+        self.view().compute_radii(leaf_radius, exponent)
+    }
+
+    /// Removes every subtree starting at a node whose radius is below
+    /// ```min_radius```, given per node ```radii``` (e.g. from
+    /// ```radii_from_leaf_count``` or ```compute_radii```). Returns the new index of every old
+    /// node. Note that radii computed before pruning are not valid
+    /// afterwards.
+    pub fn prune_thinner_than(&mut self, radii: &[S], min_radius: S) -> Vec<Option<NodeIdx>> {
+        assert!(radii.len() == self.nodes.len());
+        let mut keep = vec![false; self.nodes.len()];
+        // parents are always stored before their children.
+        for (i, node) in self.nodes.iter().enumerate() {
+            let parent_kept = node.is_root() || keep[node.parent.0 as usize];
+            keep[i] = parent_kept && radii[i] >= min_radius;
+        }
+        self.retain_nodes_by_mask(&keep)
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::descendant_leaf_counts```.
+    pub fn descendant_leaf_counts(&self) -> Vec<u32> {
+        let mut leaves = vec![0; self.nodes.len()];
+        // children are always stored after their parent.
+        for i in (0..self.nodes.len()).rev() {
+            if self.nodes[i].is_leaf() {
+                leaves[i] += 1;
+            }
+            if !self.nodes[i].is_root() {
+                let parent = self.nodes[i].parent.0 as usize;
+                leaves[parent] += leaves[i];
+            }
+        }
+        leaves
+    }
+
+    /// See ```SpaceColonization::accumulate_flow```.
+    pub fn accumulate_flow(&self, leaf_value: S) -> Vec<S> {
+        self.accumulate_flow_with(|_, node| if node.is_leaf() { leaf_value } else { S::zero() })
+    }
+
+    /// See ```SpaceColonization::accumulate_flow_with```.
+    pub fn accumulate_flow_with<C>(&self, mut source: C) -> Vec<S>
+        where C: FnMut(NodeIdx, &Node<P, F, I>) -> S
+    {
+        let mut flow: Vec<S> = self.nodes
+                                     .iter()
+                                     .enumerate()
+                                     .map(|(i, node)| source(NodeIdx::of(i), node))
+                                     .collect();
+        // children are always stored after their parent.
+        for i in (0..self.nodes.len()).rev() {
+            if !self.nodes[i].is_root() {
+                let parent = self.nodes[i].parent.0 as usize;
+                flow[parent] = flow[parent] + flow[i];
+            }
+        }
+        flow
+    }
+
+    /// See ```SpaceColonization::radii_from_leaf_count```.
+    pub fn radii_from_leaf_count<R>(&self, params: R) -> Vec<S>
+        where R: Fn(NodeIdx) -> LeafCountRadius<S>
+    {
+        let leaves = self.descendant_leaf_counts();
+        let mut per_root = vec![None; self.nodes.len()];
+        self.nodes
+            .iter()
+            .zip(leaves.iter())
+            .map(|(node, &count)| {
+                let root = node.root.0 as usize;
+                if per_root[root].is_none() {
+                    per_root[root] = Some(params(node.root));
+                }
+                let p = per_root[root].unwrap();
+                p.scale * scalar::<S>(count as f32).powf(p.exponent)
+            })
+            .collect()
+    }
+
+    /// See ```SpaceColonization::compute_radii```.
+    pub fn compute_radii(&self, leaf_radius: S, exponent: S) -> Vec<S> {
+        assert!(exponent > S::zero());
+        let mut sums = vec![S::zero(); self.nodes.len()];
+        let mut radii = vec![S::zero(); self.nodes.len()];
+        // children are always stored after their parent.
+        for i in (0..self.nodes.len()).rev() {
+            radii[i] = if self.nodes[i].is_leaf() {
+                leaf_radius
+            } else {
+                sums[i].powf(exponent.recip())
+            };
+            if !self.nodes[i].is_root() {
+                let parent = self.nodes[i].parent.0 as usize;
+                sums[parent] = sums[parent] + radii[i].powf(exponent);
+            }
+        }
+        radii
+    }
+}
@@ -0,0 +1,228 @@
+//! Recording of all structural changes during growth and replaying them,
+//! e.g. to scrub through the growth in a tool or to debug an odd result.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx};
+
+/// A structural change. Nodes and attractors are identified by their
+/// index at the time of the event.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event<P> {
+    /// A node was appended. Root nodes have no parent.
+    NodeAdded {
+        parent: Option<NodeIdx>,
+        position: P,
+    },
+
+    /// An attractor was appended.
+    AttractorAdded {
+        position: P,
+    },
+
+    /// The attractor at ```index``` was removed, and the last attractor
+    /// moved into its place. ```killed``` is false if it expired.
+    AttractorRemoved {
+        index: usize,
+        killed: bool,
+    },
+
+    /// The attractor at ```index``` moved to ```position```.
+    AttractorMoved {
+        index: usize,
+        position: P,
+    },
+
+    /// The attractor at ```index``` is disabled until the given iteration.
+    AttractorDisabled {
+        index: usize,
+        until: u32,
+    },
+
+    /// The node ```tip``` connected with ```target```, see
+    /// ```SpaceColonization::bridges```.
+    BridgeAdded {
+        tip: NodeIdx,
+        target: NodeIdx,
+    },
+
+    /// All nodes and attractors were discarded. Emitted when recording
+    /// starts and when nodes are removed or reordered, followed by the
+    /// events re-adding the remaining ones.
+    Cleared,
+
+    /// The given iteration starts.
+    IterationStarted(u32),
+}
+
+/// The events captured since ```SpaceColonization::start_recording```.
+#[derive(Debug, Clone)]
+pub struct Recording<P> {
+    /// The iteration recording started in.
+    pub first_iteration: u32,
+
+    pub events: Vec<Event<P>>,
+}
+
+/// The structure at some point of a ```Recording```.
+#[derive(Debug, Clone)]
+pub struct ReplayState<P> {
+    pub positions: Vec<P>,
+
+    /// Parent of every node. Roots are their own parent.
+    pub parents: Vec<NodeIdx>,
+
+    /// Attractor positions and the iteration they are disabled until.
+    pub attractors: Vec<(P, Option<u32>)>,
+
+    pub bridges: Vec<(NodeIdx, NodeIdx)>,
+
+    /// The iteration following the state.
+    pub iteration: u32,
+}
+
+/// Reconstructs intermediate states of a ```Recording```.
+pub struct Replayer<P> {
+    recording: Recording<P>,
+
+    /// Index of the ```IterationStarted``` event of every iteration.
+    iteration_starts: Vec<usize>,
+}
+
+impl<P: Copy> Replayer<P> {
+    pub fn new(recording: Recording<P>) -> Replayer<P> {
+        let iteration_starts = recording.events
+                                        .iter()
+                                        .enumerate()
+                                        .filter_map(|(i, e)| {
+                                            match *e {
+                                                Event::IterationStarted(_) => Some(i),
+                                                _ => None,
+                                            }
+                                        })
+                                        .collect();
+        Replayer {
+            recording,
+            iteration_starts,
+        }
+    }
+
+    /// Number of recorded iterations.
+    pub fn num_iterations(&self) -> usize {
+        self.iteration_starts.len()
+    }
+
+    /// The state after applying the first ```n``` events.
+    pub fn state_after_events(&self, n: usize) -> ReplayState<P> {
+        let mut state = ReplayState {
+            positions: Vec::new(),
+            parents: Vec::new(),
+            attractors: Vec::new(),
+            bridges: Vec::new(),
+            iteration: self.recording.first_iteration,
+        };
+        for event in self.recording.events[..n].iter() {
+            match *event {
+                Event::NodeAdded { parent, position } => {
+                    let idx = NodeIdx::of(state.positions.len());
+                    state.positions.push(position);
+                    state.parents.push(parent.unwrap_or(idx));
+                }
+                Event::AttractorAdded { position } => state.attractors.push((position, None)),
+                Event::AttractorRemoved { index, .. } => {
+                    state.attractors.swap_remove(index);
+                }
+                Event::AttractorMoved { index, position } => {
+                    state.attractors[index].0 = position;
+                }
+                Event::AttractorDisabled { index, until } => {
+                    state.attractors[index].1 = Some(until);
+                }
+                Event::BridgeAdded { tip, target } => state.bridges.push((tip, target)),
+                Event::Cleared => {
+                    state.positions.clear();
+                    state.parents.clear();
+                    state.attractors.clear();
+                    state.bridges.clear();
+                }
+                Event::IterationStarted(iteration) => state.iteration = iteration,
+            }
+        }
+        state
+    }
+
+    /// The state after the first ```n``` recorded iterations (0 is the
+    /// state when recording started).
+    pub fn state_after_iterations(&self, n: usize) -> ReplayState<P> {
+        let events = self.iteration_starts
+                         .get(n)
+                         .cloned()
+                         .unwrap_or(self.recording.events.len());
+        let mut state = self.state_after_events(events);
+        state.iteration = self.recording.first_iteration + n as u32;
+        state
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Starts capturing structural changes. The current state is recorded
+    /// as the initial state.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording {
+            first_iteration: self.next_iteration,
+            events: Vec::new(),
+        });
+        self.record_full_state();
+    }
+
+    /// Stops capturing and returns what was recorded.
+    pub fn stop_recording(&mut self) -> Option<Recording<P>> {
+        self.recording.take()
+    }
+
+    pub(crate) fn record(&mut self, event: Event<P>) {
+        if let Some(ref mut recording) = self.recording {
+            recording.events.push(event);
+        }
+    }
+
+    /// Records the current nodes and attractors after a ```Cleared```
+    /// event.
+    pub(crate) fn record_full_state(&mut self) {
+        let recording = match self.recording {
+            Some(ref mut recording) => recording,
+            None => return,
+        };
+        recording.events.push(Event::Cleared);
+        for (i, node) in self.nodes.iter().enumerate() {
+            recording.events.push(Event::NodeAdded {
+                parent: if node.parent.0 as usize == i {
+                    None
+                } else {
+                    Some(node.parent)
+                },
+                position: node.position,
+            });
+        }
+        for &(tip, target) in self.bridges.iter() {
+            recording.events.push(Event::BridgeAdded {
+                tip,
+                target,
+            });
+        }
+        for (i, attractor) in self.attractors.iter().enumerate() {
+            recording.events.push(Event::AttractorAdded { position: attractor.position });
+            if attractor.active_from_iteration > self.next_iteration {
+                recording.events.push(Event::AttractorDisabled {
+                    index: i,
+                    until: attractor.active_from_iteration,
+                });
+            }
+        }
+    }
+}
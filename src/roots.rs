@@ -0,0 +1,165 @@
+//! Growth parameters of individual trees, so that several species can grow
+//! in the same attractor field, e.g. a fine creeper among coarse shrubs.
+//! A ```RootConfig``` is attached to a root and overrides the simulation's
+//! parameters for its tree. A branch limit set on a single node (see
+//! ```set_node_branch_limit```) takes precedence over both.
+//!
+//! The state of every tree (its config, biomass, competition counters and
+//! so on) is kept in a table sorted by root node rather than on the nodes,
+//! which only refer to their root.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, CapacityError};
+use competition::RootCounts;
+use cone::{BranchAngle, GrowthCone};
+use export::Material;
+use tropism::Tropism;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RootConfig {
+    /// Scales the move distance of the tree, after the schedule and the
+    /// adaptive step.
+    pub move_dist_factor: f32,
+
+    /// Overrides ```max_length``` for the tree.
+    pub max_length: Option<u32>,
+
+    /// Overrides ```max_branches``` for the tree.
+    pub max_branches: Option<u32>,
+
+    /// Overrides the simulation's ```branch_angle``` for the tree.
+    pub branch_angle: Option<BranchAngle>,
+}
+
+impl Default for RootConfig {
+    fn default() -> RootConfig {
+        RootConfig {
+            move_dist_factor: 1.0,
+            max_length: None,
+            max_branches: None,
+            branch_angle: None,
+        }
+    }
+}
+
+impl RootConfig {
+    pub(crate) fn max_length(&self, max_length: u32) -> u32 {
+        self.max_length.unwrap_or(max_length)
+    }
+
+    pub(crate) fn max_branches(&self, max_branches: u32) -> u32 {
+        self.max_branches.unwrap_or(max_branches)
+    }
+}
+
+/// The state of one tree, see ```SpaceColonization::root_state```.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct RootState<F, I, S = f32> {
+    /// The root node of the tree.
+    pub node: NodeIdx,
+
+    /// Remaining biomass budget.
+    pub biomass: S,
+
+    /// What the tree consumed.
+    pub competition: RootCounts,
+
+    /// Material the tree is tagged with in exports.
+    pub material: Option<Material>,
+
+    /// Limits the growth directions of the tree.
+    pub growth_cone: Option<GrowthCone<F>>,
+
+    /// The information new nodes of the tree start with.
+    pub inherited_information: Option<I>,
+
+    /// Overrides the simulation's tropism for the tree.
+    pub tropism: Option<Tropism<F, S>>,
+
+    /// Overrides the simulation's growth parameters for the tree.
+    pub config: RootConfig,
+}
+
+impl<F, I, S> RootState<F, I, S> {
+    pub fn new(node: NodeIdx, biomass: S) -> Self {
+        RootState {
+            node,
+            biomass,
+            competition: RootCounts::default(),
+            material: None,
+            growth_cone: None,
+            inherited_information: None,
+            tropism: None,
+            config: RootConfig::default(),
+        }
+    }
+}
+
+/// The state of the tree rooted at ```root``` in ```roots```, which is
+/// sorted by root node.
+pub(crate) fn find<F, I, S>(roots: &[RootState<F, I, S>], root: NodeIdx) -> &RootState<F, I, S> {
+    match roots.binary_search_by_key(&root.0, |state| state.node.0) {
+        Ok(k) => &roots[k],
+        Err(_) => panic!("{:?} is not a root", root),
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Adds a root whose tree grows with ```config```. Panics if the node
+    /// limit is reached.
+    pub fn add_root_node_with_config(&mut self, position: P, config: RootConfig) -> NodeIdx {
+        self.try_add_root_node_with_config(position, config).unwrap()
+    }
+
+    pub fn try_add_root_node_with_config(&mut self,
+                                         position: P,
+                                         config: RootConfig)
+                                         -> Result<NodeIdx, CapacityError> {
+        let root = self.try_add_root_node(position, None)?;
+        self.set_root_config(root, config);
+        Ok(root)
+    }
+
+    /// Sets the parameters of the tree of ```root```. Existing nodes which
+    /// exceed the new limits stop growing.
+    pub fn set_root_config(&mut self, root: NodeIdx, config: RootConfig) {
+        assert!(config.move_dist_factor >= 0.0);
+        self.root_state_mut(root).config = config;
+    }
+
+    /// The parameters of the tree of ```node```.
+    pub fn root_config(&self, node: NodeIdx) -> RootConfig {
+        self.root_state(node).config
+    }
+
+    /// The state of the tree ```node``` belongs to.
+    pub(crate) fn root_state(&self, node: NodeIdx) -> &RootState<F, I, S> {
+        find(&self.roots, self.nodes[node.0 as usize].root)
+    }
+
+    pub(crate) fn root_state_mut(&mut self, node: NodeIdx) -> &mut RootState<F, I, S> {
+        let root = self.nodes[node.0 as usize].root;
+        match self.roots.binary_search_by_key(&root.0, |state| state.node.0) {
+            Ok(k) => &mut self.roots[k],
+            Err(_) => panic!("{:?} is not a root", root),
+        }
+    }
+
+    /// Moves the state of every tree to the new index of its root after the
+    /// nodes were rebuilt, ```mapping``` being the new index of every old
+    /// node. Trees whose root was removed are dropped.
+    pub(crate) fn remap_roots(&mut self, mapping: &[Option<NodeIdx>]) {
+        self.roots.retain(|state| mapping[state.node.0 as usize].is_some());
+        for state in self.roots.iter_mut() {
+            state.node = mapping[state.node.0 as usize].unwrap();
+        }
+    }
+}
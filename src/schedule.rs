@@ -0,0 +1,69 @@
+//! Parameters which change over the iterations of the simulation.
+
+use position::Scalar;
+use super::{Easing, scalar};
+
+/// A value keyframed over iterations. Between two keyframes the value is
+/// interpolated with the ```easing``` curve, before the first and after the
+/// last keyframe it is held constant.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Schedule<T> {
+    /// Sorted by iteration.
+    pub(crate) keyframes: Vec<(u32, T)>,
+    pub(crate) easing: Easing,
+}
+
+impl<T> Schedule<T>
+    where T: Scalar
+{
+    /// A schedule which starts out with ```value```.
+    pub fn new(value: T) -> Schedule<T> {
+        Schedule {
+            keyframes: vec![(0, value)],
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Sets ```value``` at ```iteration```, replacing an existing keyframe
+    /// at the same iteration.
+    pub fn key(mut self, iteration: u32, value: T) -> Schedule<T> {
+        match self.keyframes.binary_search_by(|&(i, _)| i.cmp(&iteration)) {
+            Ok(pos) => self.keyframes[pos].1 = value,
+            Err(pos) => self.keyframes.insert(pos, (iteration, value)),
+        }
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Schedule<T> {
+        self.easing = easing;
+        self
+    }
+
+    /// The value in ```iteration```.
+    pub fn value_at(&self, iteration: u32) -> T {
+        let pos = match self.keyframes.binary_search_by(|&(i, _)| i.cmp(&iteration)) {
+            Ok(pos) => return self.keyframes[pos].1,
+            Err(pos) => pos,
+        };
+        if pos == 0 {
+            return self.keyframes[0].1;
+        }
+        if pos == self.keyframes.len() {
+            return self.keyframes[pos - 1].1;
+        }
+        let (i0, v0) = self.keyframes[pos - 1];
+        let (i1, v1) = self.keyframes[pos];
+        let t = self.easing.apply((iteration - i0) as f32 / (i1 - i0) as f32);
+        v0 + (v1 - v0) * scalar(t)
+    }
+}
+
+impl<T> From<T> for Schedule<T>
+    where T: Scalar
+{
+    /// A constant schedule.
+    fn from(value: T) -> Schedule<T> {
+        Schedule::new(value)
+    }
+}
@@ -0,0 +1,211 @@
+//! Bone hierarchy for skinning and animating the generated structure.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, scalar};
+use view::SpaceColonizationView;
+use frames::binormal;
+
+/// A bone spans the segment from a node's parent (```head```) to the node
+/// (```tail```). Every root node gets a zero-length bone at its position,
+/// which is the root of the bone hierarchy of that tree.
+#[derive(Debug, Copy, Clone)]
+pub struct Bone<P, F> {
+    /// The node at the tail of the bone.
+    pub node: NodeIdx,
+
+    /// Index of the parent bone.
+    pub parent: Option<usize>,
+
+    pub head: P,
+    pub tail: P,
+
+    /// The bind pose orientation: unit direction from head to tail and
+    /// two unit vectors perpendicular to it (the second is zero in 2D).
+    /// Zero for root bones.
+    pub direction: F,
+    pub normal: F,
+    pub binormal: F,
+}
+
+pub struct Skeleton<P, F> {
+    /// Bones in parent-before-child order.
+    pub bones: Vec<Bone<P, F>>,
+    bone_of_node: Vec<usize>,
+}
+
+impl<P, F> Skeleton<P, F> {
+    /// The bone ending at ```node```.
+    pub fn bone_of_node(&self, node: NodeIdx) -> Option<usize> {
+        self.bone_of_node.get(node.0 as usize).cloned()
+    }
+
+    /// Skin weights of a vertex: the bone whose segment is nearest to
+    /// ```position```, blended with its parent bone for vertices lying in the
+    /// first ```blend``` fraction (0 to 1) of the bone, so joints bend
+    /// smoothly. Returns up to two (bone index, weight) pairs summing to 1.
+    pub fn skin_weights<S>(&self, position: &P, blend: S) -> Vec<(usize, S)>
+        where P: Position<S, F>,
+              F: Vector<S> + Copy,
+              S: Scalar
+    {
+        let mut nearest = None;
+        let mut nearest_dist = S::infinity();
+        for (i, bone) in self.bones.iter().enumerate() {
+            let axis = bone.tail - bone.head;
+            let sqlen = axis.sqnorm();
+            let t = if sqlen > S::zero() {
+                ((*position - bone.head).dot(&axis) / sqlen).max(S::zero()).min(S::one())
+            } else {
+                S::zero()
+            };
+            let d = position.sqdist(&(bone.head + axis * t));
+            if d < nearest_dist {
+                nearest_dist = d;
+                nearest = Some((i, t));
+            }
+        }
+
+        match nearest {
+            None => Vec::new(),
+            Some((i, t)) => {
+                match self.bones[i].parent {
+                    Some(parent) if blend > S::zero() && t < blend => {
+                        let parent_weight = scalar::<S>(0.5) * (S::one() - t / blend);
+                        vec![(i, S::one() - parent_weight), (parent, parent_weight)]
+                    }
+                    _ => vec![(i, S::one())],
+                }
+            }
+        }
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Builds the bone hierarchy with bind poses. The bind frames are
+    /// carried along branches (see ```bark_uvs```), so the bones do not
+    /// twist arbitrarily.
+    pub fn skeleton(&self) -> Skeleton<P, F> {
+        self.view().skeleton()
+    }
+
+    /// Per node flexibility in [0, 1] for wind sway, indexed by
+    /// ```NodeIdx```. Branch radii are estimated from the number of
+    /// descendant leaves (pipe model with exponent 2).
+    pub fn compute_sway_weights(&self) -> Vec<S> {
+        let radii: Vec<S> = self.descendant_leaf_counts()
+                                .iter()
+                                .map(|&l| scalar::<S>(l as f32).sqrt())
+                                .collect();
+        self.compute_sway_weights_with_radii(&radii)
+    }
+
+    /// Per node flexibility in [0, 1] for wind sway, indexed by
+    /// ```NodeIdx```, given the branch ```radii```. It is the product of
+    /// the relative depth of the node within its tree, its thinness relative
+    /// to the root, and how little mass (segment length times squared
+    /// radius) its subtree carries relative to the whole tree. Roots are 0,
+    /// thin outer twigs approach 1.
+    pub fn compute_sway_weights_with_radii(&self, radii: &[S]) -> Vec<S> {
+        let n = self.nodes.len();
+        assert!(radii.len() == n);
+
+        // subtree mass, accumulated from the leaves towards the roots.
+        let mut mass = vec![S::zero(); n];
+        for i in (0..n).rev() {
+            let node = &self.nodes[i];
+            if !node.is_root() {
+                let parent = node.parent.0 as usize;
+                let length = node.position.dist(&self.nodes[parent].position);
+                mass[i] = mass[i] + length * radii[i] * radii[i];
+                mass[parent] = mass[parent] + mass[i];
+            }
+        }
+
+        // the maximum depth and radius of each tree.
+        let mut max_length = vec![0u32; n];
+        let mut max_radius = vec![S::zero(); n];
+        for (i, node) in self.nodes.iter().enumerate() {
+            let root = node.root.0 as usize;
+            max_length[root] = max_length[root].max(node.length);
+            max_radius[root] = max_radius[root].max(radii[i]);
+        }
+
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let root = node.root.0 as usize;
+                if node.is_root() || max_length[root] == 0 {
+                    return S::zero();
+                }
+                let depth: S = scalar(node.length as f32 / max_length[root] as f32);
+                let thinness = if max_radius[root] > S::zero() {
+                    S::one() - radii[i] / max_radius[root]
+                } else {
+                    S::one()
+                };
+                let lightness = if mass[root] > S::zero() {
+                    S::one() - mass[i] / mass[root]
+                } else {
+                    S::one()
+                };
+                (depth * thinness * lightness).max(S::zero()).min(S::one())
+            })
+            .collect()
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::skeleton```.
+    pub fn skeleton(&self) -> Skeleton<P, F> {
+        let transport = self.transport_normals();
+        let mut bones = Vec::with_capacity(self.nodes.len());
+        let mut bone_of_node = Vec::with_capacity(self.nodes.len());
+
+        // parents are always stored before their children, so the bone
+        // of the parent already exists.
+        for (i, node) in self.nodes.iter().enumerate() {
+            bone_of_node.push(bones.len());
+            if node.is_root() {
+                bones.push(Bone {
+                    node: NodeIdx::of(i),
+                    parent: None,
+                    head: node.position,
+                    tail: node.position,
+                    direction: F::zero(),
+                    normal: F::zero(),
+                    binormal: F::zero(),
+                });
+            } else {
+                let parent = node.parent.0 as usize;
+                let direction = transport.directions[i];
+                let normal = transport.normals[i];
+                bones.push(Bone {
+                    node: NodeIdx::of(i),
+                    parent: Some(bone_of_node[parent]),
+                    head: self.nodes[parent].position,
+                    tail: node.position,
+                    direction,
+                    normal,
+                    binormal: binormal(&direction, &normal),
+                });
+            }
+        }
+
+        Skeleton {
+            bones,
+            bone_of_node,
+        }
+    }
+}
@@ -0,0 +1,1216 @@
+//! Saving the state of a simulation and resuming it later.
+//!
+//! The snapshot is a line based text format. It contains everything needed
+//! to continue with identical results: all nodes and attractors (including
+//! disabled ones), the iteration counter, the scalar parameters and the
+//! attractor statistics. Closures (attraction fields, flow field, metric,
+//! manifold, quantizer, step scaling and custom falloff), the growth model,
+//! obstacles and the growth domain can not be saved and have to be set
+//! again after loading.
+//!
+//! The first line names the version of the layout, which is bumped with
+//! every change of it. Snapshots of other versions are rejected.
+
+use position::{Position, Vector, Coords, Scalar};
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::IndexMut;
+use std::path::Path;
+use std::str::FromStr;
+use super::{SpaceColonization, Node, NodeIdx, IndexType, Attractor, ConnectAction, SqDist,
+            InformationMode, ConnectPolicy, Easing, StrengthRamp, Biomass, AttractorMeta};
+use adaptive::AdaptiveStep;
+use cluster::{Cluster, ClusterOptions};
+use competition::RootCounts;
+use cone::{ConeMode, GrowthCone, BranchAngle};
+use tropism::Tropism;
+use roots::{RootConfig, RootState};
+use decay::LengthDecay;
+use falloff::Falloff;
+use dormancy::Dormancy;
+use oscillation::{Oscillation, OscillationDetection};
+use jitter::Jitter;
+use export::Material;
+use frontier::FrontierInjection;
+use priority::AttractorOrder;
+use schedule::Schedule;
+use stats::AttractorCounts;
+use steps::DoneConditions;
+use targeting::NodeTargeting;
+use anastomosis::Anastomosis;
+use network::TreeConnection;
+use prefilter::ConnectPrefilter;
+
+const MAGIC: &str = "space-colonization-snapshot";
+
+/// The version of the layout written by ```write_snapshot```.
+const VERSION: u32 = 3;
+
+/// Information carried by attractors and nodes, written as a single token
+/// without whitespace.
+pub trait Token: Sized {
+    fn to_token(&self) -> String;
+    fn from_token(token: &str) -> Option<Self>;
+}
+
+impl Token for () {
+    fn to_token(&self) -> String {
+        "()".to_string()
+    }
+
+    fn from_token(token: &str) -> Option<()> {
+        if token == "()" {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+macro_rules! impl_token {
+    ($($t:ty),*) => {
+        $(
+            impl Token for $t {
+                fn to_token(&self) -> String {
+                    format!("{:?}", self)
+                }
+
+                fn from_token(token: &str) -> Option<$t> {
+                    token.parse().ok()
+                }
+            }
+        )*
+    }
+}
+
+impl_token!(bool, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+
+    /// The snapshot is malformed at the given line (starting at 1).
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::Io(ref err) => write!(f, "snapshot I/O error: {}", err),
+            SnapshotError::Parse { line, ref message } => {
+                write!(f, "invalid snapshot at line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn description(&self) -> &str {
+        match *self {
+            SnapshotError::Io(_) => "snapshot I/O error",
+            SnapshotError::Parse { .. } => "invalid snapshot",
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> SnapshotError {
+        SnapshotError::Io(err)
+    }
+}
+
+/// The whitespace separated tokens of one line.
+pub(crate) struct Tokens<'a> {
+    pub(crate) line: usize,
+    pub(crate) iter: ::std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn error<T>(&self, message: &str) -> Result<T, SnapshotError> {
+        Err(SnapshotError::Parse {
+            line: self.line,
+            message: message.to_string(),
+        })
+    }
+
+    fn token(&mut self) -> Result<&'a str, SnapshotError> {
+        match self.iter.next() {
+            Some(token) => Ok(token),
+            None => self.error("unexpected end of line"),
+        }
+    }
+
+    pub(crate) fn parse<T: FromStr>(&mut self) -> Result<T, SnapshotError> {
+        let token = self.token()?;
+        match token.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => self.error(&format!("invalid value {:?}", token)),
+        }
+    }
+
+    /// ```-``` stands for ```None```.
+    fn parse_option<T: FromStr>(&mut self) -> Result<Option<T>, SnapshotError> {
+        let token = self.token()?;
+        if token == "-" {
+            return Ok(None);
+        }
+        match token.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => self.error(&format!("invalid value {:?}", token)),
+        }
+    }
+
+    fn information<I: Token>(&mut self) -> Result<I, SnapshotError> {
+        let token = self.token()?;
+        match I::from_token(token) {
+            Some(value) => Ok(value),
+            None => self.error(&format!("invalid information {:?}", token)),
+        }
+    }
+
+    fn easing(&mut self) -> Result<Easing, SnapshotError> {
+        let token = self.token()?;
+        self.easing_of(token)
+    }
+
+    fn easing_of(&self, token: &str) -> Result<Easing, SnapshotError> {
+        match token {
+            "linear" => Ok(Easing::Linear),
+            "smoothstep" => Ok(Easing::Smoothstep),
+            _ => self.error(&format!("invalid easing {:?}", token)),
+        }
+    }
+
+    /// The fields written by ```write_schedule```.
+    fn schedule<S: FromStr>(&mut self) -> Result<Schedule<S>, SnapshotError> {
+        let easing = self.easing()?;
+        self.keyframes(easing)
+    }
+
+    /// Like ```schedule```, ```-``` stands for ```None```.
+    fn schedule_option<S: FromStr>(&mut self) -> Result<Option<Schedule<S>>, SnapshotError> {
+        let easing = match self.token()? {
+            "-" => return Ok(None),
+            token => self.easing_of(token)?,
+        };
+        self.keyframes(easing).map(Some)
+    }
+
+    fn keyframes<S: FromStr>(&mut self, easing: Easing) -> Result<Schedule<S>, SnapshotError> {
+        let count: usize = self.parse()?;
+        let mut keyframes = Vec::with_capacity(count);
+        for _ in 0..count {
+            keyframes.push((self.parse()?, self.parse()?));
+        }
+        if keyframes.is_empty() {
+            return self.error("empty schedule");
+        }
+        Ok(Schedule {
+            keyframes,
+            easing,
+        })
+    }
+
+    fn cone_mode(&mut self) -> Result<ConeMode, SnapshotError> {
+        match self.token()? {
+            "reject" => Ok(ConeMode::Reject),
+            "clamp" => Ok(ConeMode::Clamp),
+            "reflect" => Ok(ConeMode::Reflect),
+            token => self.error(&format!("invalid cone mode {:?}", token)),
+        }
+    }
+
+    fn point<P, S>(&mut self, dim: usize) -> Result<P, SnapshotError>
+        where P: Coords<S>,
+              S: FromStr
+    {
+        let mut p: P = Coords::origin();
+        for i in 0..dim {
+            p[i] = self.parse()?;
+        }
+        Ok(p)
+    }
+
+    /// The fields written by ```write_attractor```. Roots must be below
+    /// ```num_nodes```.
+    pub(crate) fn attractor<P, I, S>(&mut self,
+                                     dim: usize,
+                                     num_nodes: usize)
+                                     -> Result<Attractor<P, I, S>, SnapshotError>
+        where P: Coords<S>,
+              I: Copy + Token,
+              S: FromStr
+    {
+        let attract_dist = SqDist(self.parse()?);
+        let connect_dist = SqDist(self.parse()?);
+        let strength = self.parse()?;
+        let position = self.point(dim)?;
+        let information = self.information()?;
+        let connect_action = match self.token()? {
+            "kill" => ConnectAction::KillAttractor,
+            "disable" => ConnectAction::DisableFor { iterations: self.parse()? },
+            "disable-root" => ConnectAction::DisableForConnectingRoot,
+            _ => return self.error("invalid connect action"),
+        };
+        let active_from_iteration = self.parse()?;
+        let active_until_iteration = self.parse_option()?;
+        let strength_ramp = match self.parse_option::<u32>()? {
+            Some(ease_in) => {
+                Some(StrengthRamp {
+                    ease_in,
+                    ease_out: self.parse()?,
+                    easing: self.easing()?,
+                })
+            }
+            None => None,
+        };
+        Ok(Attractor {
+            attract_dist,
+            connect_dist,
+            strength,
+            position,
+            information,
+            connect_action,
+            active_from_iteration,
+            active_until_iteration,
+            strength_ramp,
+            not_for_root: self.node_idx(num_nodes)?,
+            not_for_connecting_root: self.node_idx(num_nodes)?,
+            min_node_length: self.parse_option()?,
+            max_node_length: self.parse_option()?,
+            group: self.parse()?,
+        })
+    }
+
+    fn node_idx(&mut self, num_nodes: usize) -> Result<Option<NodeIdx>, SnapshotError> {
+        match self.parse_option::<IndexType>()? {
+            Some(idx) if idx as usize >= num_nodes => self.error("node index out of range"),
+            idx => Ok(idx.map(NodeIdx)),
+        }
+    }
+
+    fn root<P, F, I>(&mut self, nodes: &[Node<P, F, I>]) -> Result<NodeIdx, SnapshotError>
+        where P: Debug,
+              F: Debug,
+              I: Copy + Debug
+    {
+        match self.node_idx(nodes.len())? {
+            Some(root) if nodes[root.0 as usize].is_root() => Ok(root),
+            Some(_) => self.error("not a root"),
+            None => self.error("missing root"),
+        }
+    }
+}
+
+fn option_token<T: Debug>(value: Option<T>) -> String {
+    match value {
+        Some(v) => format!("{:?}", v),
+        None => "-".to_string(),
+    }
+}
+
+fn cone_mode_token(mode: ConeMode) -> &'static str {
+    match mode {
+        ConeMode::Reject => "reject",
+        ConeMode::Clamp => "clamp",
+        ConeMode::Reflect => "reflect",
+    }
+}
+
+fn easing_token(easing: Easing) -> &'static str {
+    match easing {
+        Easing::Linear => "linear",
+        Easing::Smoothstep => "smoothstep",
+    }
+}
+
+/// Writes the easing and the keyframes of ```schedule```, each preceded by
+/// a space.
+fn write_schedule<W: Write, S: Debug>(w: &mut W, schedule: &Schedule<S>) -> io::Result<()> {
+    write!(w,
+           " {} {}",
+           easing_token(schedule.easing),
+           schedule.keyframes.len())?;
+    for &(iteration, ref value) in schedule.keyframes.iter() {
+        write!(w, " {} {:?}", iteration, value)?;
+    }
+    Ok(())
+}
+
+/// Writes the fields of ```a```, each preceded by a space.
+pub(crate) fn write_attractor<W, P, I, S>(w: &mut W,
+                                          a: &Attractor<P, I, S>,
+                                          dim: usize)
+                                          -> io::Result<()>
+    where W: Write,
+          P: IndexMut<usize, Output = S>,
+          I: Copy + Token,
+          S: Debug
+{
+    write!(w,
+           " {:?} {:?} {:?}",
+           a.attract_dist.0,
+           a.connect_dist.0,
+           a.strength)?;
+    for i in 0..dim {
+        write!(w, " {:?}", a.position[i])?;
+    }
+    write!(w, " {}", a.information.to_token())?;
+    match a.connect_action {
+        ConnectAction::KillAttractor => write!(w, " kill")?,
+        ConnectAction::DisableFor { iterations } => write!(w, " disable {}", iterations)?,
+        ConnectAction::DisableForConnectingRoot => write!(w, " disable-root")?,
+    }
+    write!(w,
+           " {} {}",
+           a.active_from_iteration,
+           option_token(a.active_until_iteration))?;
+    match a.strength_ramp {
+        Some(r) => write!(w, " {} {} {}", r.ease_in, r.ease_out, easing_token(r.easing))?,
+        None => write!(w, " -")?,
+    }
+    write!(w,
+           " {} {} {} {} {}",
+           option_token(a.not_for_root.map(|idx| idx.0)),
+           option_token(a.not_for_connecting_root.map(|idx| idx.0)),
+           option_token(a.min_node_length),
+           option_token(a.max_node_length),
+           a.group)
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug + Token,
+          S: Scalar + Debug + FromStr
+{
+    /// Writes a snapshot to the file at ```path```.
+    pub fn save<A: AsRef<Path>>(&self, path: A) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_snapshot(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Restores a simulation saved with ```save```.
+    pub fn load<A: AsRef<Path>>(path: A) -> Result<SpaceColonization<P, F, I, S>, SnapshotError> {
+        SpaceColonization::read_snapshot(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_snapshot<W: Write>(&self, w: &mut W) -> Result<(), SnapshotError> {
+        let dim = P::dim();
+        writeln!(w, "{} {}", MAGIC, VERSION)?;
+        writeln!(w, "dim {}", dim)?;
+        writeln!(w,
+                 "params {:?} {:?} {} {} {} {} {} {}",
+                 self.default_attract_dist.0,
+                 self.default_connect_dist.0,
+                 self.max_length,
+                 self.max_branches,
+                 self.next_iteration,
+                 self.node_limit,
+                 option_token(self.use_last_n_nodes),
+                 match self.information_mode {
+                     InformationMode::KeepLast => "keep-last",
+                     InformationMode::Accumulate => "accumulate",
+                 })?;
+
+        write!(w, "move_dist")?;
+        write_schedule(w, &self.move_dist)?;
+        writeln!(w)?;
+
+        match self.biomass {
+            Some(b) => writeln!(w, "biomass {:?} {:?}", b.initial, b.per_connection)?,
+            None => writeln!(w, "biomass -")?,
+        }
+
+        writeln!(w,
+                 "order {} {} {}",
+                 match self.attractor_order {
+                     AttractorOrder::Insertion => "insertion",
+                     AttractorOrder::Strength => "strength",
+                     AttractorOrder::FrontierProximity => "frontier-proximity",
+                 },
+                 self.attractor_order_refresh,
+                 option_token(self.connection_quota))?;
+        writeln!(w, "cooldown {}", option_token(self.connect_cooldown))?;
+        writeln!(w, "exclude-roots {}", self.exclude_roots as u8)?;
+        writeln!(w, "perception {}", option_token(self.perception_angle))?;
+        match self.branch_angle {
+            Some(b) => writeln!(w, "branch-angle {:?} {}", b.max_angle, cone_mode_token(b.mode))?,
+            None => writeln!(w, "branch-angle -")?,
+        }
+        writeln!(w,
+                 "connect {}",
+                 match self.connect_policy {
+                     ConnectPolicy::FirstFound => "first-found",
+                     ConnectPolicy::Closest => "closest",
+                 })?;
+        writeln!(w,
+                 "prefilter {}",
+                 match self.connect_prefilter {
+                     Some(ConnectPrefilter::OnAdd) => "add",
+                     Some(ConnectPrefilter::EveryIteration) => "iteration",
+                     None => "-",
+                 })?;
+        writeln!(w,
+                 "falloff {}",
+                 match self.falloff {
+                     Falloff::Constant | Falloff::Custom(_) => "constant",
+                     Falloff::Linear => "linear",
+                     Falloff::InverseSquare => "inverse-square",
+                     Falloff::Smoothstep => "smoothstep",
+                 })?;
+        writeln!(w,
+                 "done {} {} {} {} {}",
+                 self.done_conditions.quiet_iterations,
+                 option_token(self.done_conditions.node_budget),
+                 option_token(self.done_conditions.max_iterations),
+                 self.done_conditions.stop as u8,
+                 self.quiet_iterations)?;
+        writeln!(w, "attractor-ids {}", self.next_attractor_id)?;
+        writeln!(w, "spatial {}", option_token(self.spatial_index_cell_size()))?;
+        writeln!(w, "world-scale {:?}", self.world_scale)?;
+        match self.tropism {
+            Some(ref tropism) => {
+                write!(w, "tropism")?;
+                write_schedule(w, &tropism.weight)?;
+                let direction = P::origin() + tropism.direction;
+                for i in 0..dim {
+                    write!(w, " {:?}", direction[i])?;
+                }
+                writeln!(w)?
+            }
+            None => writeln!(w, "tropism -")?,
+        }
+        match self.node_targeting {
+            Some(t) => {
+                writeln!(w,
+                         "targeting {:?} {:?} {:?} {}",
+                         t.attract_dist.0,
+                         t.connect_dist.0,
+                         t.strength,
+                         option_token(t.own_tree_separation))?
+            }
+            None => writeln!(w, "targeting -")?,
+        }
+        match self.anastomosis {
+            Some(a) => {
+                writeln!(w, "anastomosis {:?} {}", a.distance, a.min_separation)?
+            }
+            None => writeln!(w, "anastomosis -")?,
+        }
+        writeln!(w, "merge {}", option_token(self.merge_distance))?;
+        match self.frontier_injection {
+            Some(f) => {
+                writeln!(w,
+                         "frontier {:?} {:?} {:?} {:?} {} {} {}",
+                         f.distance,
+                         f.attract_dist.0,
+                         f.connect_dist.0,
+                         f.strength,
+                         f.lifetime,
+                         f.only_unattracted as u8,
+                         f.group)?
+            }
+            None => writeln!(w, "frontier -")?,
+        }
+
+        match self.length_decay {
+            Some(d) => {
+                writeln!(w, "decay {:?} {:?} {}", d.half_length, d.min_probability, d.seed)?
+            }
+            None => writeln!(w, "decay -")?,
+        }
+        match self.jitter {
+            Some(ref j) => {
+                write!(w, "jitter")?;
+                write_schedule(w, &j.magnitude)?;
+                writeln!(w, " {}", j.seed)?
+            }
+            None => writeln!(w, "jitter -")?,
+        }
+
+        match self.adaptive_step {
+            Some(AdaptiveStep::RemainingAttractors { below, min_factor }) => {
+                writeln!(w, "adaptive remaining {} {:?}", below, min_factor)?
+            }
+            Some(AdaptiveStep::NearestDistance { fraction, min_factor }) => {
+                writeln!(w, "adaptive nearest {:?} {:?}", fraction, min_factor)?
+            }
+            None => writeln!(w, "adaptive -")?,
+        }
+
+        if let Some(options) = self.cluster_options {
+            writeln!(w,
+                     "clustering {:?} {:?} {}",
+                     options.cell_size,
+                     options.uncluster_dist,
+                     option_token(options.until_iteration))?;
+        }
+        for cluster in self.clusters.iter() {
+            write!(w, "cluster {}", cluster.members.len())?;
+            for p in Some(&cluster.centroid).into_iter().chain(cluster.members.iter()) {
+                for i in 0..dim {
+                    write!(w, " {:?}", p[i])?;
+                }
+            }
+            writeln!(w)?;
+        }
+
+        for node in self.nodes.iter() {
+            write!(w,
+                   "node {} {} {} {} {}",
+                   node.parent.0,
+                   node.root.0,
+                   node.length,
+                   node.branches,
+                   option_token(node.branch_limit))?;
+            for i in 0..dim {
+                write!(w, " {:?}", node.position[i])?;
+            }
+            match node.assigned_information {
+                Some(ref info) => write!(w, " {}", info.to_token())?,
+                None => write!(w, " -")?,
+            }
+            write!(w, " {}", node.received_information.len())?;
+            for &(ref info, iteration) in node.received_information.iter() {
+                write!(w, " {} {}", info.to_token(), iteration)?;
+            }
+            writeln!(w)?;
+        }
+
+        for (a, meta) in self.attractors.iter().zip(self.attractor_meta.iter()) {
+            write!(w, "attractor {} {}", meta.id, meta.added)?;
+            write_attractor(w, a, dim)?;
+            write!(w,
+                   " {} {} {}",
+                   option_token(meta.nearest_root.map(|idx| idx.0)),
+                   meta.contested as u8,
+                   match meta.last_connection {
+                       Some((node, iteration)) => format!("{} {}", node.0, iteration),
+                       None => "-".to_string(),
+                   })?;
+            // the velocity is stored as the position after one iteration.
+            match meta.velocity {
+                Some(velocity) => {
+                    write!(w, " v")?;
+                    let next = a.position + velocity;
+                    for i in 0..dim {
+                        write!(w, " {:?}", next[i])?;
+                    }
+                }
+                None => write!(w, " -")?,
+            }
+            writeln!(w)?;
+        }
+
+        for &(tip, target) in self.bridges.iter() {
+            writeln!(w, "bridge {} {}", tip.0, target.0)?;
+        }
+        for c in self.tree_connections.iter() {
+            writeln!(w,
+                     "tree-connection {} {} {} {} {}",
+                     c.attractor,
+                     c.node.0,
+                     c.root.0,
+                     c.other_root.0,
+                     c.iteration)?;
+        }
+
+        for state in self.roots.iter() {
+            let root = state.node.0;
+            write!(w,
+                   "root {} {:?} {} {} {}",
+                   root,
+                   state.biomass,
+                   state.competition.consumed,
+                   state.competition.connections,
+                   state.competition.contested_consumed)?;
+            match state.material {
+                Some(m) => writeln!(w, " {} {} {} {}", m.id, m.color[0], m.color[1], m.color[2])?,
+                None => writeln!(w, " -")?,
+            }
+            if let Some(cone) = state.growth_cone {
+                write!(w, "cone {} {} {:?}", root, cone_mode_token(cone.mode), cone.max_angle)?;
+                let axis = P::origin() + cone.axis;
+                for i in 0..dim {
+                    write!(w, " {:?}", axis[i])?;
+                }
+                writeln!(w)?;
+            }
+            if let Some(ref info) = state.inherited_information {
+                writeln!(w, "inherit {} {}", root, info.to_token())?;
+            }
+            if let Some(ref tropism) = state.tropism {
+                write!(w, "root-tropism {}", root)?;
+                write_schedule(w, &tropism.weight)?;
+                let direction = P::origin() + tropism.direction;
+                for i in 0..dim {
+                    write!(w, " {:?}", direction[i])?;
+                }
+                writeln!(w)?;
+            }
+            if state.config != RootConfig::default() {
+                let config = &state.config;
+                writeln!(w,
+                         "root-config {} {:?} {} {}",
+                         root,
+                         config.move_dist_factor,
+                         option_token(config.max_length),
+                         option_token(config.max_branches))?;
+                if let Some(b) = config.branch_angle {
+                    writeln!(w,
+                             "root-branch-angle {} {:?} {}",
+                             root,
+                             b.max_angle,
+                             cone_mode_token(b.mode))?;
+                }
+            }
+        }
+
+        if let Some(ref dormancy) = self.dormancy {
+            write!(w, "dormancy {} {}", dormancy.after, dormancy.since.len())?;
+            for since in dormancy.since.iter() {
+                write!(w, " {}", since)?;
+            }
+            writeln!(w)?;
+        }
+        if let Some(ref oscillation) = self.oscillation {
+            let detection = &oscillation.detection;
+            write!(w,
+                   "oscillation {} {:?} {} {}",
+                   detection.iterations,
+                   detection.radius,
+                   detection.disable_attractors as u8,
+                   oscillation.stalls.len())?;
+            for stalls in oscillation.stalls.iter() {
+                write!(w, " {}", stalls)?;
+            }
+            writeln!(w)?;
+        }
+
+        for (group, c) in self.attractor_counts.iter() {
+            writeln!(w,
+                     "counts {} {} {} {} {} {}",
+                     group,
+                     c.added,
+                     c.killed,
+                     c.expired,
+                     c.connections,
+                     c.iterations_to_consumption)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_snapshot<R: BufRead>(r: R)
+                                     -> Result<SpaceColonization<P, F, I, S>, SnapshotError> {
+        let dim = P::dim();
+        let mut sc = SpaceColonization::new(SqDist(S::zero()), SqDist(S::zero()), 0, 0, S::zero());
+        // the index is built once all nodes are read.
+        let mut spatial_index = None;
+
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let mut t = Tokens {
+                line: n + 1,
+                iter: line.split_whitespace(),
+            };
+            if n == 0 {
+                if t.iter.next() != Some(MAGIC) {
+                    return t.error("not a snapshot");
+                }
+                let version: u32 = t.parse()?;
+                if version != VERSION {
+                    return t.error(&format!("unsupported version {} (expected {})",
+                                            version,
+                                            VERSION));
+                }
+                continue;
+            }
+            let keyword = match t.iter.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            match keyword {
+                "dim" => {
+                    if t.parse::<usize>()? != dim {
+                        return t.error("dimension mismatch");
+                    }
+                }
+                "params" => {
+                    sc.default_attract_dist = SqDist(t.parse()?);
+                    sc.default_connect_dist = SqDist(t.parse()?);
+                    sc.max_length = t.parse()?;
+                    sc.max_branches = t.parse()?;
+                    sc.next_iteration = t.parse()?;
+                    sc.set_node_limit(t.parse()?);
+                    sc.use_last_n_nodes = t.parse_option()?;
+                    sc.information_mode = match t.token()? {
+                        "keep-last" => InformationMode::KeepLast,
+                        "accumulate" => InformationMode::Accumulate,
+                        _ => return t.error("invalid information mode"),
+                    };
+                }
+                "move_dist" => sc.move_dist = t.schedule()?,
+                "biomass" => {
+                    sc.biomass = match t.parse_option::<S>()? {
+                        Some(initial) => {
+                            Some(Biomass {
+                                initial,
+                                per_connection: t.parse()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "order" => {
+                    let order = match t.token()? {
+                        "insertion" => AttractorOrder::Insertion,
+                        "strength" => AttractorOrder::Strength,
+                        "frontier-proximity" => AttractorOrder::FrontierProximity,
+                        _ => return t.error("invalid attractor order"),
+                    };
+                    sc.set_attractor_order(order, t.parse()?);
+                    sc.connection_quota = t.parse_option()?;
+                }
+                "cooldown" => sc.connect_cooldown = t.parse_option()?,
+                "exclude-roots" => sc.exclude_roots = t.parse::<u8>()? != 0,
+                "perception" => sc.perception_angle = t.parse_option()?,
+                "branch-angle" => {
+                    sc.branch_angle = match t.parse_option::<f32>()? {
+                        Some(max_angle) => {
+                            Some(BranchAngle {
+                                max_angle,
+                                mode: t.cone_mode()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "connect" => {
+                    sc.connect_policy = match t.token()? {
+                        "first-found" => ConnectPolicy::FirstFound,
+                        "closest" => ConnectPolicy::Closest,
+                        _ => return t.error("invalid connect policy"),
+                    };
+                }
+                "prefilter" => {
+                    sc.connect_prefilter = match t.token()? {
+                        "add" => Some(ConnectPrefilter::OnAdd),
+                        "iteration" => Some(ConnectPrefilter::EveryIteration),
+                        "-" => None,
+                        _ => return t.error("invalid prefilter"),
+                    };
+                }
+                "falloff" => {
+                    sc.falloff = match t.token()? {
+                        "constant" => Falloff::Constant,
+                        "linear" => Falloff::Linear,
+                        "inverse-square" => Falloff::InverseSquare,
+                        "smoothstep" => Falloff::Smoothstep,
+                        _ => return t.error("invalid falloff"),
+                    };
+                }
+                "done" => {
+                    sc.done_conditions = DoneConditions {
+                        quiet_iterations: t.parse()?,
+                        node_budget: t.parse_option()?,
+                        max_iterations: t.parse_option()?,
+                        stop: t.parse::<u8>()? != 0,
+                    };
+                    sc.quiet_iterations = t.parse()?;
+                }
+                "attractor-ids" => sc.next_attractor_id = t.parse()?,
+                "spatial" => spatial_index = t.parse_option()?,
+                "world-scale" => sc.world_scale = t.parse()?,
+                "tropism" => {
+                    sc.tropism = match t.schedule_option()? {
+                        Some(weight) => {
+                            Some(Tropism {
+                                direction: t.point::<P, S>(dim)? - P::origin(),
+                                weight,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "targeting" => {
+                    sc.node_targeting = match t.parse_option()? {
+                        Some(attract_dist) => {
+                            Some(NodeTargeting {
+                                attract_dist: SqDist(attract_dist),
+                                connect_dist: SqDist(t.parse()?),
+                                strength: t.parse()?,
+                                own_tree_separation: t.parse_option()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "anastomosis" => {
+                    sc.anastomosis = match t.parse_option()? {
+                        Some(distance) => {
+                            Some(Anastomosis {
+                                distance,
+                                min_separation: t.parse()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "merge" => sc.merge_distance = t.parse_option()?,
+                "frontier" => {
+                    sc.frontier_injection = match t.parse_option()? {
+                        Some(distance) => {
+                            Some(FrontierInjection {
+                                distance,
+                                attract_dist: SqDist(t.parse()?),
+                                connect_dist: SqDist(t.parse()?),
+                                strength: t.parse()?,
+                                lifetime: t.parse()?,
+                                only_unattracted: t.parse::<u8>()? != 0,
+                                group: t.parse()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "bridge" => {
+                    let num_nodes = sc.nodes.len();
+                    match (t.node_idx(num_nodes)?, t.node_idx(num_nodes)?) {
+                        (Some(tip), Some(target)) => sc.bridges.push((tip, target)),
+                        _ => return t.error("missing bridge node"),
+                    }
+                }
+                "tree-connection" => {
+                    let attractor = t.parse()?;
+                    let num_nodes = sc.nodes.len();
+                    let nodes = (t.node_idx(num_nodes)?,
+                                 t.node_idx(num_nodes)?,
+                                 t.node_idx(num_nodes)?);
+                    match nodes {
+                        (Some(node), Some(root), Some(other_root)) => {
+                            sc.tree_connections.push(TreeConnection {
+                                attractor,
+                                node,
+                                root,
+                                other_root,
+                                iteration: t.parse()?,
+                            })
+                        }
+                        _ => return t.error("missing tree connection node"),
+                    }
+                }
+                "cone" => {
+                    let root = t.root(&sc.nodes)?;
+                    let mode = t.cone_mode()?;
+                    let max_angle = t.parse()?;
+                    let axis = t.point::<P, S>(dim)? - P::origin();
+                    sc.root_state_mut(root).growth_cone = Some(GrowthCone {
+                        axis,
+                        max_angle,
+                        mode,
+                    });
+                }
+                "inherit" => {
+                    let root = t.root(&sc.nodes)?;
+                    sc.root_state_mut(root).inherited_information = Some(t.information()?);
+                }
+                "root-tropism" => {
+                    let root = t.root(&sc.nodes)?;
+                    let weight = t.schedule()?;
+                    sc.root_state_mut(root).tropism = Some(Tropism {
+                        direction: t.point::<P, S>(dim)? - P::origin(),
+                        weight,
+                    });
+                }
+                "root-config" => {
+                    let root = t.root(&sc.nodes)?;
+                    sc.root_state_mut(root).config = RootConfig {
+                        move_dist_factor: t.parse()?,
+                        max_length: t.parse_option()?,
+                        max_branches: t.parse_option()?,
+                        branch_angle: None,
+                    };
+                }
+                "root-branch-angle" => {
+                    let root = t.root(&sc.nodes)?;
+                    let max_angle = t.parse()?;
+                    sc.root_state_mut(root).config.branch_angle = Some(BranchAngle {
+                        max_angle,
+                        mode: t.cone_mode()?,
+                    });
+                }
+                "dormancy" => {
+                    let after = t.parse()?;
+                    let count: usize = t.parse()?;
+                    if count != sc.nodes.len() {
+                        return t.error("dormancy does not match the nodes");
+                    }
+                    let mut since = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        since.push(t.parse()?);
+                    }
+                    sc.dormancy = Some(Dormancy {
+                        after,
+                        since,
+                    });
+                }
+                "oscillation" => {
+                    let detection = OscillationDetection {
+                        iterations: t.parse()?,
+                        radius: t.parse()?,
+                        disable_attractors: t.parse::<u8>()? != 0,
+                    };
+                    let count: usize = t.parse()?;
+                    if count != sc.nodes.len() {
+                        return t.error("oscillation does not match the nodes");
+                    }
+                    let mut stalls = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        stalls.push(t.parse()?);
+                    }
+                    sc.oscillation = Some(Oscillation {
+                        detection,
+                        stalls,
+                    });
+                }
+                "decay" => {
+                    sc.length_decay = match t.parse_option()? {
+                        Some(half_length) => {
+                            Some(LengthDecay {
+                                half_length,
+                                min_probability: t.parse()?,
+                                seed: t.parse()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "jitter" => {
+                    sc.jitter = match t.schedule_option()? {
+                        Some(magnitude) => {
+                            Some(Jitter {
+                                magnitude,
+                                seed: t.parse()?,
+                            })
+                        }
+                        None => None,
+                    };
+                }
+                "adaptive" => {
+                    sc.adaptive_step = match t.token()? {
+                        "-" => None,
+                        "remaining" => {
+                            Some(AdaptiveStep::RemainingAttractors {
+                                below: t.parse()?,
+                                min_factor: t.parse()?,
+                            })
+                        }
+                        "nearest" => {
+                            Some(AdaptiveStep::NearestDistance {
+                                fraction: t.parse()?,
+                                min_factor: t.parse()?,
+                            })
+                        }
+                        _ => return t.error("invalid adaptive step"),
+                    };
+                }
+                "clustering" => {
+                    sc.cluster_options = Some(ClusterOptions {
+                        cell_size: t.parse()?,
+                        uncluster_dist: t.parse()?,
+                        until_iteration: t.parse_option()?,
+                    });
+                }
+                "cluster" => {
+                    let count: usize = t.parse()?;
+                    let centroid = t.point(dim)?;
+                    let mut members = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        members.push(t.point(dim)?);
+                    }
+                    sc.clusters.push(Cluster {
+                        centroid,
+                        members,
+                    });
+                }
+                "node" => {
+                    // parents are always stored before their children.
+                    let num_nodes = sc.nodes.len() + 1;
+                    let parent = t.node_idx(num_nodes)?;
+                    let root = t.node_idx(num_nodes)?;
+                    let (parent, root) = match (parent, root) {
+                        (Some(parent), Some(root)) => (parent, root),
+                        _ => return t.error("missing parent or root"),
+                    };
+                    let length = t.parse()?;
+                    let branches = t.parse()?;
+                    let branch_limit = t.parse_option()?;
+                    let position = t.point(dim)?;
+                    let assigned_information = match t.token()? {
+                        "-" => None,
+                        token => {
+                            match I::from_token(token) {
+                                Some(info) => Some(info),
+                                None => return t.error("invalid information"),
+                            }
+                        }
+                    };
+                    let count: usize = t.parse()?;
+                    let mut received_information = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        received_information.push((t.information()?, t.parse()?));
+                    }
+                    sc.push_node(Node {
+                        parent,
+                        root,
+                        length,
+                        branches,
+                        branch_limit,
+                        position,
+                        growth: F::zero(),
+                        growth_count: 0,
+                        assigned_information,
+                        received_information,
+                    });
+                    if parent.0 as usize == num_nodes - 1 {
+                        sc.roots.push(RootState::new(parent, S::zero()));
+                    }
+                }
+                "root" => {
+                    let root = t.root(&sc.nodes)?;
+                    let state = sc.root_state_mut(root);
+                    state.biomass = t.parse()?;
+                    state.competition = RootCounts {
+                        consumed: t.parse()?,
+                        connections: t.parse()?,
+                        contested_consumed: t.parse()?,
+                    };
+                    state.material = match t.parse_option()? {
+                        Some(id) => {
+                            Some(Material::new(id, [t.parse()?, t.parse()?, t.parse()?]))
+                        }
+                        None => None,
+                    };
+                }
+                "attractor" => {
+                    let id = t.parse()?;
+                    let added = t.parse()?;
+                    let num_nodes = sc.nodes.len();
+                    let attractor: Attractor<P, I, S> = t.attractor(dim, num_nodes)?;
+                    let nearest_root = t.node_idx(num_nodes)?;
+                    let contested: u8 = t.parse()?;
+                    let last_connection = match t.node_idx(num_nodes)? {
+                        Some(node) => Some((node, t.parse()?)),
+                        None => None,
+                    };
+                    let velocity = match t.token()? {
+                        "-" => None,
+                        "v" => Some(t.point::<P, S>(dim)? - attractor.position),
+                        _ => return t.error("invalid velocity"),
+                    };
+                    sc.attractors.push(attractor);
+                    sc.attractor_meta.push(AttractorMeta {
+                        id,
+                        added,
+                        nearest_root,
+                        contested: contested != 0,
+                        last_connection,
+                        velocity,
+                    });
+                }
+                "counts" => {
+                    let group = t.parse()?;
+                    let counts = AttractorCounts {
+                        added: t.parse()?,
+                        killed: t.parse()?,
+                        expired: t.parse()?,
+                        connections: t.parse()?,
+                        iterations_to_consumption: t.parse()?,
+                    };
+                    sc.attractor_counts.insert(group, counts);
+                }
+                _ => return t.error(&format!("unknown keyword {:?}", keyword)),
+            }
+        }
+
+        sc.set_spatial_index(spatial_index);
+        Ok(sc)
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod tests {
+    use na::{Point2, Vector2};
+    use fixtures::{self, snapshot};
+    use {SpaceColonization, ConnectAction};
+
+    fn simulation() -> SpaceColonization<Point2<f32>, Vector2<f32>, u32> {
+        fixtures::simulation(&[Point2::new(0.0, 0.0)], 500, |i, attractor| {
+            attractor.information = i as u32;
+            if i % 7 == 0 {
+                attractor.connect_action = ConnectAction::DisableFor { iterations: 5 };
+            }
+        })
+    }
+
+    #[test]
+    fn resumes_with_identical_nodes() {
+        let mut sc = simulation();
+        sc.by_ref().take(10).count();
+        let saved = snapshot(&sc);
+        let mut resumed: SpaceColonization<Point2<f32>, Vector2<f32>, u32> =
+            SpaceColonization::read_snapshot(saved.as_bytes()).unwrap();
+        assert_eq!(snapshot(&resumed), saved);
+
+        let num_nodes = sc.num_nodes();
+        sc.by_ref().take(30).count();
+        resumed.by_ref().take(30).count();
+        assert!(sc.num_nodes() > num_nodes);
+        assert_eq!(resumed.positions(), sc.positions());
+        assert_eq!(resumed.parents(), sc.parents());
+        assert_eq!(snapshot(&resumed), snapshot(&sc));
+    }
+
+    #[test]
+    fn resumes_after_pruning() {
+        let mut sc = simulation();
+        sc.take_steps(40).count();
+        for _ in 0..3 {
+            sc.prune_leaves(|_, _| true);
+        }
+        let saved = snapshot(&sc);
+        let resumed: SpaceColonization<Point2<f32>, Vector2<f32>, u32> =
+            SpaceColonization::read_snapshot(saved.as_bytes()).unwrap();
+        assert_eq!(snapshot(&resumed), saved);
+        let rebuilt = SpaceColonization::from_parts(sc.into_parts()).unwrap();
+        assert_eq!(snapshot(&rebuilt), saved);
+    }
+
+    #[test]
+    fn resumes_f64_simulations() {
+        let mut sc: SpaceColonization<Point2<f64>, Vector2<f64>, u32, f64> =
+            fixtures::simulation(&[Point2::new(0.0, 0.0)], 500, |i, attractor| {
+                attractor.information = i as u32;
+            });
+        sc.take_steps(10).count();
+        let saved = snapshot(&sc);
+        let mut resumed: SpaceColonization<Point2<f64>, Vector2<f64>, u32, f64> =
+            SpaceColonization::read_snapshot(saved.as_bytes()).unwrap();
+        assert_eq!(snapshot(&resumed), saved);
+
+        sc.take_steps(30).count();
+        resumed.take_steps(30).count();
+        assert_eq!(resumed.positions(), sc.positions());
+        let rebuilt = SpaceColonization::from_parts(sc.into_parts()).unwrap();
+        assert_eq!(snapshot(&rebuilt), snapshot(&resumed));
+    }
+}
@@ -0,0 +1,208 @@
+//! A uniform grid over the node positions, so that an attractor only looks
+//! at the nodes in the cells within its reach instead of scanning all
+//! nodes. The grid is updated as nodes are appended.
+//!
+//! It only applies to Euclidean distances; with a metric or a manifold all
+//! nodes are scanned. Candidates are visited in index order, so the result
+//! is the same as with a full scan. Positions have at most three
+//! coordinates, the cells of 2D positions have a third coordinate of 0.
+
+use position::{Position, Vector, Scalar};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Range;
+use std::vec;
+use super::{SpaceColonization, SqDist};
+
+/// The most coordinates of a position in the grid.
+const MAX_DIM: usize = 3;
+
+type Cell = [i64; MAX_DIM];
+
+#[derive(Debug, Clone)]
+pub(crate) struct NodeGrid {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+}
+
+impl NodeGrid {
+    fn new(cell_size: f32) -> NodeGrid {
+        NodeGrid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, coords: &[f64; MAX_DIM]) -> Cell {
+        let cell_size = self.cell_size as f64;
+        let mut cell = [0; MAX_DIM];
+        for (c, &x) in cell.iter_mut().zip(coords.iter()) {
+            *c = (x / cell_size).floor() as i64;
+        }
+        cell
+    }
+
+    pub(crate) fn insert(&mut self, coords: &[f64; MAX_DIM], idx: usize) {
+        let cell = self.cell(coords);
+        self.cells.entry(cell).or_default().push(idx);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// The nodes from ```start_index``` to ```num_nodes``` which may be
+    /// within ```reach``` of ```position```.
+    pub(crate) fn candidates<P, F, S>(&self,
+                                      position: &P,
+                                      reach: SqDist<S>,
+                                      start_index: usize,
+                                      num_nodes: usize)
+                                      -> Candidates
+        where P: Position<S, F>,
+              F: Vector<S>,
+              S: Scalar
+    {
+        let all = Candidates::All(start_index..num_nodes);
+        let r = reach.0.sqrt().to_f64().unwrap();
+        let coords = coords(position);
+        let (mut lo, mut hi) = (coords, coords);
+        for axis in 0..P::dim() {
+            lo[axis] -= r;
+            hi[axis] += r;
+        }
+        let (lo, hi) = (self.cell(&lo), self.cell(&hi));
+
+        // visiting the box must not cost more than visiting every cell.
+        let mut num_cells: f64 = 1.0;
+        for (l, h) in lo.iter().zip(hi.iter()) {
+            num_cells *= (h - l + 1) as f64;
+        }
+        if !r.is_finite() || num_cells > self.cells.len() as f64 {
+            return all;
+        }
+
+        let mut near = Vec::new();
+        let mut cell = lo;
+        'cells: loop {
+            if let Some(nodes) = self.cells.get(&cell) {
+                near.extend(nodes.iter().cloned().filter(|&i| i >= start_index));
+            }
+            for axis in 0..cell.len() {
+                if cell[axis] < hi[axis] {
+                    cell[axis] += 1;
+                    continue 'cells;
+                }
+                cell[axis] = lo[axis];
+            }
+            break;
+        }
+        near.sort();
+        Candidates::Near(near.into_iter())
+    }
+}
+
+/// The nodes an attractor has to look at, in index order.
+pub(crate) enum Candidates {
+    All(Range<usize>),
+    Near(vec::IntoIter<usize>),
+}
+
+impl Iterator for Candidates {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match *self {
+            Candidates::All(ref mut range) => range.next(),
+            Candidates::Near(ref mut iter) => iter.next(),
+        }
+    }
+}
+
+/// The coordinates of ```p```, in double precision for any scalar type,
+/// padded with 0.
+pub(crate) fn coords<P, F, S>(p: &P) -> [f64; MAX_DIM]
+    where P: Position<S, F>,
+          F: Vector<S>,
+          S: Scalar
+{
+    let mut coords = [0.0; MAX_DIM];
+    for (axis, c) in coords.iter_mut().enumerate().take(P::dim()) {
+        *c = p[axis].to_f64().unwrap();
+    }
+    coords
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Buckets the nodes in a grid of ```cell_size```, which should be
+    /// about the square root of the attract distance. ```None``` scans all
+    /// nodes for every attractor, which is faster for small simulations.
+    pub fn set_spatial_index(&mut self, cell_size: Option<f32>) {
+        self.node_grid = cell_size.map(|cell_size| {
+            assert!(cell_size > 0.0);
+            assert!(P::dim() <= MAX_DIM, "the spatial index supports up to 3 dimensions");
+            let mut grid = NodeGrid::new(cell_size);
+            for (i, p) in self.positions.iter().enumerate() {
+                grid.insert(&coords(p), i);
+            }
+            grid
+        });
+    }
+
+    pub fn spatial_index_cell_size(&self) -> Option<f32> {
+        self.node_grid.as_ref().map(|grid| grid.cell_size)
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod tests {
+    use na::{Point2, Point3, Vector2, Vector3};
+    use position::{Position, Vector};
+    use std::fmt::Debug;
+    use fixtures::simulation;
+    use SpaceColonization;
+
+    /// Grows from two roots into the fixture's attractors, with the spatial
+    /// index of ```cell_size```.
+    fn grow<P, F>(cell_size: Option<f32>) -> SpaceColonization<P, F, ()>
+        where P: Position<f32, F> + Debug,
+              F: Vector<f32> + Copy + Debug
+    {
+        let mut corner = P::origin();
+        for i in 0..P::dim() {
+            corner[i] = -1.0;
+        }
+        let mut sc = simulation(&[corner, P::origin()], 1000, |_, _| {});
+        sc.set_spatial_index(cell_size);
+        sc.take_steps(25).count();
+        sc
+    }
+
+    fn assert_same_nodes<P, F>()
+        where P: Position<f32, F> + Debug,
+              F: Vector<f32> + Copy + Debug
+    {
+        let full_scan = grow::<P, F>(None);
+        assert!(full_scan.num_nodes() > 50);
+        for &cell_size in [0.1, 0.3, 0.7].iter() {
+            let indexed = grow::<P, F>(Some(cell_size));
+            assert_eq!(indexed.positions(), full_scan.positions());
+            assert_eq!(indexed.parents(), full_scan.parents());
+        }
+    }
+
+    #[test]
+    fn grid_grows_the_same_nodes_as_a_full_scan_2d() {
+        assert_same_nodes::<Point2<f32>, Vector2<f32>>();
+    }
+
+    #[test]
+    fn grid_grows_the_same_nodes_as_a_full_scan_3d() {
+        assert_same_nodes::<Point3<f32>, Vector3<f32>>();
+    }
+}
@@ -0,0 +1,258 @@
+//! Statistics about the simulation.
+
+use position::{Position, Vector, Scalar};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, IndexType, scalar};
+use view::SpaceColonizationView;
+
+/// Counters of the attractors of one group (or of all groups).
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AttractorCounts {
+    pub added: usize,
+
+    /// Removed because of ```ConnectAction::KillAttractor```.
+    pub killed: usize,
+
+    /// Removed because ```active_until_iteration``` was reached.
+    pub expired: usize,
+
+    /// Number of times a node connected with an attractor.
+    pub connections: usize,
+
+    /// Sum over all killed attractors of the number of iterations they
+    /// took part in until being killed.
+    pub iterations_to_consumption: u64,
+}
+
+impl AttractorCounts {
+    /// Average number of iterations from adding an attractor until it was
+    /// killed.
+    pub fn average_iterations_to_consumption(&self) -> Option<f32> {
+        if self.killed == 0 {
+            None
+        } else {
+            Some(self.iterations_to_consumption as f32 / self.killed as f32)
+        }
+    }
+
+    fn accumulate(&mut self, other: &AttractorCounts) {
+        self.added += other.added;
+        self.killed += other.killed;
+        self.expired += other.expired;
+        self.connections += other.connections;
+        self.iterations_to_consumption += other.iterations_to_consumption;
+    }
+}
+
+/// Why a leaf is not growing (any more).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LeafFate {
+    /// It reached ```max_length```.
+    MaxLength,
+
+    /// It reached its branch limit (only possible if the limit is 0).
+    MaxBranches,
+
+    /// The biomass budget of its tree is too low for another segment.
+    Biomass,
+
+    /// No active attractor is within reach.
+    NoAttractors,
+
+    /// An active attractor is within reach, so it may still grow.
+    Growing,
+}
+
+/// Number of leaves per ```LeafFate```.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DeadEndCounts {
+    pub max_length: usize,
+    pub max_branches: usize,
+    pub biomass: usize,
+    pub no_attractors: usize,
+    pub growing: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AttractorStats {
+    pub total: AttractorCounts,
+
+    /// Attractors still present, but not active in the next iteration.
+    pub currently_disabled: usize,
+
+    /// Attractors still present.
+    pub remaining: usize,
+
+    /// Breakdown by ```Attractor::group```.
+    pub groups: BTreeMap<u32, AttractorCounts>,
+}
+
+/// The shape of one tree, see ```stats_per_root```.
+#[derive(Debug, Clone)]
+pub struct TreeStats<P, S = f32> {
+    /// Including the root.
+    pub num_nodes: usize,
+
+    /// The sum of the (Euclidean) lengths of all segments.
+    pub total_length: S,
+
+    /// The largest ```length``` of a node, the number of segments from the
+    /// root.
+    pub max_depth: u32,
+
+    /// Nodes without children. A root without children counts as a leaf.
+    pub num_leaves: usize,
+
+    /// The number of nodes per number of children: entry ```k``` counts
+    /// the nodes with ```k``` children.
+    pub branching_degrees: Vec<usize>,
+
+    /// The corners of the bounding box of the nodes.
+    pub min: P,
+    pub max: P,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    pub fn attractor_stats(&self) -> AttractorStats {
+        let mut total = AttractorCounts::default();
+        for counts in self.attractor_counts.values() {
+            total.accumulate(counts);
+        }
+        let next_iteration = self.next_iteration;
+        AttractorStats {
+            total,
+            currently_disabled: self.attractors
+                                    .iter()
+                                    .filter(|a| !a.is_active_in(next_iteration))
+                                    .count(),
+            remaining: self.attractors.len(),
+            groups: self.attractor_counts.clone(),
+        }
+    }
+
+    /// ```TreeStats``` of every tree, in the order of the roots.
+    pub fn stats_per_root(&self) -> Vec<(NodeIdx, TreeStats<P, S>)> {
+        self.view().stats_per_root()
+    }
+
+    /// Whether an attractor active in ```iteration``` is within reach of
+    /// ```node```.
+    pub(crate) fn is_attracted(&self, node: NodeIdx, iteration: u32) -> bool {
+        let node = &self.nodes[node.0 as usize];
+        self.attractors.iter().any(|a| {
+            a.is_active_in(iteration) && a.not_for_root != Some(node.root) &&
+            a.not_for_connecting_root != Some(node.root) &&
+            a.accepts_node_length(node.length) &&
+            self.sqdist_to_node(&node.position, &a.position) < a.attract_dist
+        })
+    }
+
+    /// Classifies every leaf by why it stopped growing, so that one can
+    /// tell whether the limits or the attractor layout shaped the result.
+    /// Limits take precedence over the attractors.
+    pub fn leaf_fates(&self) -> Vec<(NodeIdx, LeafFate)> {
+        let next_iteration = self.next_iteration;
+        let move_dist = self.move_dist.value_at(next_iteration);
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|&(_, node)| node.is_leaf())
+            .map(|(i, node)| {
+                let root = self.root_state(node.root);
+                let starved = self.biomass.is_some() &&
+                              root.biomass < move_dist * scalar(root.config.move_dist_factor);
+                let fate = if node.length >= root.config.max_length(self.max_length) {
+                    LeafFate::MaxLength
+                } else if node.branches >= node.branch_limit(&self.roots, self.max_branches) {
+                    LeafFate::MaxBranches
+                } else if starved {
+                    LeafFate::Biomass
+                } else if self.is_attracted(NodeIdx::of(i), next_iteration) {
+                    LeafFate::Growing
+                } else {
+                    LeafFate::NoAttractors
+                };
+                (NodeIdx::of(i), fate)
+            })
+            .collect()
+    }
+
+    /// Counts the leaves per ```LeafFate```.
+    pub fn dead_end_counts(&self) -> DeadEndCounts {
+        let mut counts = DeadEndCounts::default();
+        for (_, fate) in self.leaf_fates() {
+            match fate {
+                LeafFate::MaxLength => counts.max_length += 1,
+                LeafFate::MaxBranches => counts.max_branches += 1,
+                LeafFate::Biomass => counts.biomass += 1,
+                LeafFate::NoAttractors => counts.no_attractors += 1,
+                LeafFate::Growing => counts.growing += 1,
+            }
+        }
+        counts
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::stats_per_root```.
+    pub fn stats_per_root(&self) -> Vec<(NodeIdx, TreeStats<P, S>)> {
+        let mut slots: BTreeMap<IndexType, usize> = BTreeMap::new();
+        let mut stats: Vec<(NodeIdx, TreeStats<P, S>)> = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.is_root() {
+                let tree = TreeStats {
+                    num_nodes: 0,
+                    total_length: S::zero(),
+                    max_depth: 0,
+                    num_leaves: 0,
+                    branching_degrees: Vec::new(),
+                    min: node.position,
+                    max: node.position,
+                };
+                slots.insert(i as IndexType, stats.len());
+                stats.push((NodeIdx::of(i), tree));
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let tree = &mut stats[slots[&node.root.0]].1;
+            tree.num_nodes += 1;
+            if !node.is_root() {
+                let parent = node.parent.0 as usize;
+                tree.total_length = tree.total_length +
+                                    self.positions[parent].dist(&self.positions[i]);
+            }
+            tree.max_depth = tree.max_depth.max(node.length);
+            if node.is_leaf() {
+                tree.num_leaves += 1;
+            }
+            let degree = node.branches as usize;
+            if tree.branching_degrees.len() <= degree {
+                tree.branching_degrees.resize(degree + 1, 0);
+            }
+            tree.branching_degrees[degree] += 1;
+            for axis in 0..P::dim() {
+                let x = node.position[axis];
+                if x < tree.min[axis] {
+                    tree.min[axis] = x;
+                }
+                if x > tree.max[axis] {
+                    tree.max[axis] = x;
+                }
+            }
+        }
+        stats
+    }
+}
@@ -0,0 +1,274 @@
+//! Bounded iteration. ```SpaceColonization``` is an iterator which only ends
+//! at the node limit, so ```for _ in sc {}``` may never terminate, e.g. once
+//! nodes oscillate between attractors. The adaptors here borrow the
+//! simulation and end on their own:
+//!
+//! ```ignore
+//! for new_nodes in sc.until_quiet(3).take(1000) { ... }
+//! ```
+//!
+//! ```step``` reports the details of a single iteration instead of only the
+//! number of new nodes.
+//!
+//! ```run_until_done``` iterates until the growth has converged, see
+//! ```DoneConditions```, and tells why it stopped. With
+//! ```DoneConditions::stop``` set, the simulation itself ends there as an
+//! iterator, which guards ```for _ in sc {}``` against runaway growth, and
+//! ```stop_reason``` tells why it ended.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, CapacityError};
+
+/// What happened in one iteration, see ```SpaceColonization::step```.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationStats<I> {
+    /// The nodes grown in the iteration, in index order.
+    pub new_nodes: Vec<NodeIdx>,
+
+    /// The number of attractors removed by a connection (expired ones are
+    /// not counted).
+    pub killed_attractors: usize,
+
+    /// The nodes which connected with an attractor, together with the
+    /// attractor's information, in the order of connection.
+    pub connections: Vec<(NodeIdx, I)>,
+
+    /// The number of attractors active in the next iteration.
+    pub active_attractors: usize,
+
+    /// The nodes trapped by the oscillation detection in the iteration, in
+    /// index order.
+    pub trapped_nodes: Vec<NodeIdx>,
+
+    /// The number of nodes the attractors skip in the next iteration, see
+    /// ```num_dormant```.
+    pub dormant_nodes: usize,
+}
+
+/// When ```SpaceColonization::is_finished``` considers the growth done.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DoneConditions {
+    /// Done after this many consecutive iterations without new nodes.
+    pub quiet_iterations: u32,
+
+    /// Done once the structure has this many nodes. Unlike the node limit,
+    /// it is only checked between iterations and may thus be exceeded.
+    pub node_budget: Option<usize>,
+
+    /// Done once this many iterations were performed in total.
+    pub max_iterations: Option<u32>,
+
+    /// Whether ```next``` returns ```None``` once the growth is done.
+    /// Otherwise the iterator only ends at the node limit.
+    pub stop: bool,
+}
+
+impl Default for DoneConditions {
+    fn default() -> DoneConditions {
+        DoneConditions {
+            quiet_iterations: 3,
+            node_budget: None,
+            max_iterations: None,
+            stop: false,
+        }
+    }
+}
+
+/// Why ```SpaceColonization::run_until_done``` stopped, or why the growth
+/// is done.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DoneReason {
+    /// Every remaining attractor has expired (or none is left), and there
+    /// are no fields or frontier injection which could still cause growth.
+    NoActiveAttractors,
+
+    /// ```DoneConditions::quiet_iterations``` passed without new nodes.
+    Quiet,
+
+    /// ```DoneConditions::node_budget``` was reached.
+    NodeBudget,
+
+    /// The node limit was reached (see ```set_node_limit```), possibly
+    /// during an iteration.
+    NodeLimit,
+
+    /// ```DoneConditions::max_iterations``` or the ```max_iterations``` of
+    /// ```run_until_done``` passed before any of the above.
+    MaxIterations,
+
+    /// Only reported by ```batch::run_all```: the run took longer than
+    /// ```Budget::max_duration```.
+    MaxDuration,
+}
+
+/// See ```SpaceColonization::take_steps```.
+pub struct Steps<'a, P, F, I, S = f32>
+    where P: Position<S, F> + Debug + 'a,
+          F: Vector<S> + Copy + Debug + 'a,
+          I: Copy + Default + Debug + 'a,
+          S: Scalar + Debug + 'a
+{
+    sc: &'a mut SpaceColonization<P, F, I, S>,
+    remaining: usize,
+}
+
+impl<'a, P, F, I, S> Iterator for Steps<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.sc.next()
+    }
+}
+
+/// See ```SpaceColonization::until_quiet```.
+pub struct UntilQuiet<'a, P, F, I, S = f32>
+    where P: Position<S, F> + Debug + 'a,
+          F: Vector<S> + Copy + Debug + 'a,
+          I: Copy + Default + Debug + 'a,
+          S: Scalar + Debug + 'a
+{
+    sc: &'a mut SpaceColonization<P, F, I, S>,
+    quiet: usize,
+    max_quiet: usize,
+}
+
+impl<'a, P, F, I, S> Iterator for UntilQuiet<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.quiet >= self.max_quiet {
+            return None;
+        }
+        let new_nodes = self.sc.next()?;
+        if new_nodes == 0 {
+            self.quiet += 1;
+        } else {
+            self.quiet = 0;
+        }
+        Some(new_nodes)
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Performs one iteration like ```try_step```, but reports what
+    /// changed, so that callers can update visualizations or decide when to
+    /// stop without scanning the whole state. Fails at the node limit like
+    /// ```try_step```.
+    pub fn step(&mut self) -> Result<IterationStats<I>, CapacityError> {
+        let num_nodes = self.nodes.len();
+        let mut stats = IterationStats {
+            new_nodes: Vec::new(),
+            killed_attractors: 0,
+            connections: Vec::new(),
+            active_attractors: 0,
+            trapped_nodes: Vec::new(),
+            dormant_nodes: 0,
+        };
+        self.iterate(Some(&mut stats))?;
+        stats.new_nodes = (num_nodes..self.nodes.len()).map(NodeIdx::of).collect();
+        let next_iteration = self.next_iteration;
+        stats.active_attractors = self.attractors
+                                      .iter()
+                                      .filter(|ap| ap.is_active_in(next_iteration))
+                                      .count();
+        stats.dormant_nodes = self.num_dormant();
+        Ok(stats)
+    }
+
+    pub fn set_done_conditions(&mut self, conditions: DoneConditions) {
+        self.done_conditions = conditions;
+    }
+
+    pub fn done_conditions(&self) -> DoneConditions {
+        self.done_conditions
+    }
+
+    /// Why the growth is done, or ```None``` if it may continue.
+    pub fn done_reason(&self) -> Option<DoneReason> {
+        let next_iteration = self.next_iteration;
+        let nodes = self.nodes.len();
+        if nodes >= self.node_limit {
+            Some(DoneReason::NodeLimit)
+        } else if self.done_conditions.node_budget.is_some_and(|budget| nodes >= budget) {
+            Some(DoneReason::NodeBudget)
+        } else if self.fields.is_empty() && self.frontier_injection.is_none() &&
+                  self.attractors.iter().all(|ap| ap.is_expired_in(next_iteration)) {
+            Some(DoneReason::NoActiveAttractors)
+        } else if self.quiet_iterations >= self.done_conditions.quiet_iterations {
+            Some(DoneReason::Quiet)
+        } else if self.done_conditions.max_iterations.is_some_and(|max| next_iteration >= max) {
+            Some(DoneReason::MaxIterations)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done_reason().is_some()
+    }
+
+    /// Why ```next``` last returned ```None```, the iterator having ended
+    /// at the node limit or (with ```DoneConditions::stop```) because the
+    /// growth is done. ```None``` while it continues.
+    pub fn stop_reason(&self) -> Option<DoneReason> {
+        self.stop_reason
+    }
+
+    /// Iterates until the growth is done (see ```done_reason```), but at
+    /// most ```max_iterations``` times, which also bounds oscillating
+    /// nodes.
+    pub fn run_until_done(&mut self, max_iterations: u32) -> DoneReason {
+        for _ in 0..max_iterations {
+            if let Some(reason) = self.done_reason() {
+                return reason;
+            }
+            if self.try_step().is_err() {
+                return DoneReason::NodeLimit;
+            }
+        }
+        self.done_reason().unwrap_or(DoneReason::MaxIterations)
+    }
+
+    /// Performs at most ```n``` iterations, yielding the number of new
+    /// nodes of each. Ends early at the node limit.
+    pub fn take_steps<'a>(&'a mut self, n: usize) -> Steps<'a, P, F, I, S> {
+        Steps {
+            sc: self,
+            remaining: n,
+        }
+    }
+
+    /// Iterates until ```k``` consecutive iterations produced no new nodes
+    /// (or the node limit is reached). Oscillating nodes keep producing
+    /// nodes, so combine it with ```take``` to bound the number of
+    /// iterations.
+    pub fn until_quiet<'a>(&'a mut self, k: usize) -> UntilQuiet<'a, P, F, I, S> {
+        assert!(k > 0);
+        UntilQuiet {
+            sc: self,
+            quiet: 0,
+            max_quiet: k,
+        }
+    }
+}
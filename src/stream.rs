@@ -0,0 +1,53 @@
+//! Streaming new segments to another thread while a step is running, so
+//! that renderers can draw new growth of huge scenes early.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use std::sync::mpsc::Sender;
+use super::{SpaceColonization, NodeIdx};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SegmentMsg<P> {
+    /// A node was grown from ```parent```.
+    Segment {
+        node: NodeIdx,
+        parent: NodeIdx,
+        from: P,
+        to: P,
+        iteration: u32,
+    },
+
+    /// The iteration has finished, ```new_nodes``` nodes were grown.
+    StepFinished {
+        iteration: u32,
+        new_nodes: usize,
+    },
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Sends a message for every new segment as soon as it is created, and
+    /// one after each step. Replaces a previously set sender. Streaming
+    /// stops when the receiver hangs up.
+    pub fn stream_segments(&mut self, sender: Sender<SegmentMsg<P>>) {
+        self.segment_sender = Some(sender);
+    }
+
+    pub fn stop_streaming(&mut self) {
+        self.segment_sender = None;
+    }
+
+    pub(crate) fn send_segment_msg(&mut self, msg: SegmentMsg<P>) {
+        let hung_up = match self.segment_sender {
+            Some(ref sender) => sender.send(msg).is_err(),
+            None => false,
+        };
+        if hung_up {
+            self.segment_sender = None;
+        }
+    }
+}
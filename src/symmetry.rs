@@ -0,0 +1,263 @@
+//! Building symmetric setups from one authored attractor cloud: the
+//! attractors (and optionally the trees grown so far) are transformed in
+//! place or copied through a ```Transform```, e.g. a nalgebra isometry for
+//! rotated modules or a ```Mirror``` for bilateral symmetry. ```transform```
+//! moves the whole simulation, e.g. to place it into world space.
+//!
+//! Directions (drift velocities, growth cone axes, tropisms) are transformed
+//! as the difference of two transformed points, which is exact for any
+//! affine transform.
+
+use position::{Position, Vector, Coords};
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, CapacityError};
+use cluster::Cluster;
+use record::Event;
+use tropism::Tropism;
+
+/// A transform of positions, e.g. an isometry.
+pub trait Transform<P> {
+    fn transform(&self, p: &P) -> P;
+
+    fn inv_transform(&self, p: &P) -> P;
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use na::{Point2, Point3, Isometry2, Isometry3};
+    use super::Transform;
+
+    macro_rules! impl_isometry {
+        ($isometry:ident, $point:ident, $s:ty) => {
+            impl Transform<$point<$s>> for $isometry<$s> {
+                fn transform(&self, p: &$point<$s>) -> $point<$s> {
+                    self.transform_point(p)
+                }
+
+                fn inv_transform(&self, p: &$point<$s>) -> $point<$s> {
+                    self.inverse_transform_point(p)
+                }
+            }
+        }
+    }
+
+    impl_isometry!(Isometry2, Point2, f32);
+    impl_isometry!(Isometry3, Point3, f32);
+}
+
+/// The reflection at the plane (or line, in 2D) through ```point``` with
+/// the given ```normal```. It is its own inverse.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mirror<P, F> {
+    pub point: P,
+    pub normal: F,
+}
+
+impl<P, F> Transform<P> for Mirror<P, F>
+    where P: Position<f32, F>,
+          F: Vector<f32> + Copy
+{
+    fn transform(&self, p: &P) -> P {
+        let offset = (*p - self.point).dot(&self.normal) / self.normal.sqnorm();
+        *p + self.normal * (-2.0 * offset)
+    }
+
+    fn inv_transform(&self, p: &P) -> P {
+        self.transform(p)
+    }
+}
+
+/// The image of the direction ```v``` at ```p```.
+fn transform_direction<T, P, F>(transform: &T, p: &P, v: F) -> F
+    where T: Transform<P>,
+          P: Position<f32, F>,
+          F: Vector<f32>
+{
+    transform.transform(&(*p + v)) - transform.transform(p)
+}
+
+impl<P, F, I> SpaceColonization<P, F, I>
+    where P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    /// Moves every attractor, including the members of clusters, to its
+    /// image under ```transform```. The nodes stay where they are.
+    pub fn transform_attractors<T>(&mut self, transform: &T)
+        where T: Transform<P>
+    {
+        self.move_attractors_by(transform);
+        for idx in 0..self.attractors.len() {
+            let position = self.attractors[idx].position;
+            self.record(Event::AttractorMoved {
+                index: idx,
+                position,
+            });
+        }
+    }
+
+    fn move_attractors_by<T>(&mut self, transform: &T)
+        where T: Transform<P>
+    {
+        for (ap, meta) in self.attractors.iter_mut().zip(self.attractor_meta.iter_mut()) {
+            if let Some(velocity) = meta.velocity {
+                meta.velocity = Some(transform_direction(transform, &ap.position, velocity));
+            }
+            ap.position = transform.transform(&ap.position);
+        }
+        for cluster in self.clusters.iter_mut() {
+            cluster.centroid = transform.transform(&cluster.centroid);
+            for p in cluster.members.iter_mut() {
+                *p = transform.transform(p);
+            }
+        }
+    }
+
+    /// Moves the nodes and attractors to their images under ```transform```,
+    /// a rigid transform or a similarity. The distance-like parameters are
+    /// scaled along with a similarity (see ```set_world_scale```), and the
+    /// growth cones and tropisms are turned with the trees. The heatmap
+    /// is left untouched: its grid keeps its origin and spacing.
+    pub fn transform<T>(&mut self, transform: &T)
+        where T: Transform<P>
+    {
+        // the scale of a similarity is the same in every direction.
+        let origin: P = Coords::origin();
+        let factor = F::basis_element(0)
+                         .map_or(1.0, |e| transform_direction(transform, &origin, e).norm());
+
+        for root in self.roots.iter_mut() {
+            let position = self.nodes[root.node.0 as usize].position;
+            if let Some(ref mut cone) = root.growth_cone {
+                cone.axis = transform_direction(transform, &position, cone.axis);
+            }
+            if let Some(tropism) = root.tropism.take() {
+                let direction = transform_direction(transform, &position, tropism.direction);
+                root.tropism = Some(Tropism::new(direction, tropism.weight));
+            }
+        }
+        for node in self.nodes.iter_mut() {
+            node.position = transform.transform(&node.position);
+        }
+        for (p, node) in self.positions.iter_mut().zip(self.nodes.iter()) {
+            *p = node.position;
+        }
+        if let Some(tropism) = self.tropism.take() {
+            let direction = transform_direction(transform, &origin, tropism.direction);
+            self.tropism = Some(Tropism::new(direction, tropism.weight));
+        }
+        self.move_attractors_by(transform);
+        // also rebuilds the spatial index.
+        self.scale_distances(factor);
+        self.clear_history();
+        self.record_full_state();
+    }
+
+    /// Adds a copy of every attractor (and cluster) at its image under
+    /// ```transform```, e.g. the other half of a bilateral setup. With
+    /// ```with_nodes```, every tree is copied as well, as a new tree with
+    /// the information, material, branch limits, growth cone, tropism and
+    /// root config of its original, and the copied attractors which exclude
+    /// a root exclude its copy instead. Fails if the node limit is reached,
+    /// keeping the nodes copied so far but no attractors.
+    pub fn add_transformed_copy<T>(&mut self,
+                                   transform: &T,
+                                   with_nodes: bool)
+                                   -> Result<(), CapacityError>
+        where T: Transform<P>
+    {
+        let mut copies: Vec<NodeIdx> = Vec::new();
+        if with_nodes {
+            let num_nodes = self.nodes.len();
+            copies.reserve(num_nodes);
+            // parents are stored before their children.
+            for i in 0..num_nodes {
+                let position = transform.transform(&self.nodes[i].position);
+                let copy = if self.nodes[i].is_root() {
+                    self.try_add_root_node(position, None)?
+                } else {
+                    if self.nodes.len() >= self.node_limit {
+                        return Err(CapacityError { limit: self.node_limit });
+                    }
+                    let parent = copies[self.nodes[i].parent.0 as usize];
+                    self.add_leaf_node(position, parent);
+                    NodeIdx::of(self.nodes.len() - 1)
+                };
+                if self.nodes[i].is_root() {
+                    let original_position = self.nodes[i].position;
+                    let original = self.root_state(NodeIdx::of(i)).clone();
+                    let state = self.root_state_mut(copy);
+                    state.growth_cone = original.growth_cone.map(|mut cone| {
+                        cone.axis = transform_direction(transform, &original_position, cone.axis);
+                        cone
+                    });
+                    state.tropism = original.tropism.map(|tropism| {
+                        let direction =
+                            transform_direction(transform, &original_position, tropism.direction);
+                        Tropism::new(direction, tropism.weight)
+                    });
+                    state.material = original.material;
+                    state.inherited_information = original.inherited_information;
+                    state.config = original.config;
+                }
+                let branch_limit = self.nodes[i].branch_limit;
+                let assigned_information = self.nodes[i].assigned_information;
+                let node = &mut self.nodes[copy.0 as usize];
+                node.assigned_information = assigned_information;
+                node.branch_limit = branch_limit;
+                copies.push(copy);
+            }
+        }
+        let remap = |root: Option<NodeIdx>| {
+            match root {
+                Some(root) if with_nodes => Some(copies[root.0 as usize]),
+                _ => root,
+            }
+        };
+
+        for idx in 0..self.attractors.len() {
+            if self.is_super_attractor(idx) {
+                // added along with the copied clusters.
+                continue;
+            }
+            let mut attractor = self.attractors[idx];
+            attractor.position = transform.transform(&attractor.position);
+            attractor.not_for_root = remap(attractor.not_for_root);
+            attractor.not_for_connecting_root = remap(attractor.not_for_connecting_root);
+            let velocity = self.attractor_meta[idx].velocity.map(|velocity| {
+                transform_direction(transform, &self.attractors[idx].position, velocity)
+            });
+            match velocity {
+                Some(velocity) => self.add_moving_attractor(attractor, velocity),
+                None => self.add_attractor(attractor),
+            }
+        }
+
+        for i in 0..self.clusters.len() {
+            let original = self.clusters[i].centroid;
+            let found = self.attractors
+                            .iter()
+                            .position(|a| a.connect_dist.0 == 0.0 && a.position == original);
+            let idx = match found {
+                Some(idx) => idx,
+                None => continue,
+            };
+            let cluster = Cluster {
+                centroid: transform.transform(&original),
+                members: self.clusters[i].members.iter().map(|p| transform.transform(p)).collect(),
+            };
+            let mut attractor = self.attractors[idx];
+            attractor.position = cluster.centroid;
+            attractor.not_for_connecting_root = remap(attractor.not_for_connecting_root);
+            let event = Event::AttractorAdded { position: cluster.centroid };
+            self.note_attractor(&event);
+            self.record(event);
+            let meta = self.new_attractor_meta();
+            self.attractor_meta.push(meta);
+            self.attractors.push(attractor);
+            self.note_cluster_added();
+            self.clusters.push(cluster);
+        }
+        Ok(())
+    }
+}
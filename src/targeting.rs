@@ -0,0 +1,134 @@
+//! Nodes as attractors: growing tips are attracted by the nodes of other
+//! trees (and optionally by distant parts of their own tree) and connect to
+//! them, so that networks densify by bridging between trees. A connection
+//! is stored as a bridge, since every node keeps its single parent.
+
+use position::{Position, Vector, Scalar};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use super::{SpaceColonization, NodeIdx, IndexType, SqDist};
+use record::Event;
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeTargeting<S = f32> {
+    /// The square distance within which a node attracts a tip.
+    pub attract_dist: SqDist<S>,
+
+    /// A tip closer than the square root of this distance to a node
+    /// connects with it. Every tip connects at most once.
+    pub connect_dist: SqDist<S>,
+
+    /// The strength with which a node attracts a tip, relative to the
+    /// attractors.
+    pub strength: f32,
+
+    /// If set, nodes of the tip's own tree are targets as well, if they are
+    /// more than this many segments away along the tree.
+    pub own_tree_separation: Option<u32>,
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Lets existing nodes attract the growing tips. ```None``` disables
+    /// it. Costs a pass over all nodes per active tip and iteration.
+    pub fn set_node_targeting(&mut self, targeting: Option<NodeTargeting<S>>) {
+        self.node_targeting = targeting;
+    }
+
+    /// The connections between a tip and the node it connected with.
+    pub fn bridges(&self) -> &[(NodeIdx, NodeIdx)] {
+        &self.bridges
+    }
+
+    /// The number of segments between ```a``` and ```b```, which belong to
+    /// the same tree.
+    pub(crate) fn tree_distance(&self, a: NodeIdx, b: NodeIdx) -> u32 {
+        let parent = |idx: usize| self.nodes[idx].parent.0 as usize;
+        let length = |idx: usize| self.nodes[idx].length;
+        let (mut a, mut b) = (a.0 as usize, b.0 as usize);
+        let mut distance = 0;
+        while length(a) > length(b) {
+            a = parent(a);
+            distance += 1;
+        }
+        while length(b) > length(a) {
+            b = parent(b);
+            distance += 1;
+        }
+        while a != b {
+            a = parent(a);
+            b = parent(b);
+            distance += 2;
+        }
+        distance
+    }
+
+    /// Attracts the active tips from ```start_index``` on towards their
+    /// nearest target node, and bridges those within the connect distance.
+    pub(crate) fn attract_to_nodes(&mut self, start_index: usize) {
+        let targeting = match self.node_targeting {
+            Some(targeting) => targeting,
+            None => return,
+        };
+        let bridged: HashSet<IndexType> = self.bridges.iter().map(|&(tip, _)| tip.0).collect();
+
+        for i in start_index..self.nodes.len() {
+            let tip = &self.nodes[i];
+            if !tip.is_leaf() || !tip.is_active(&self.roots, self.max_length, self.max_branches) ||
+               (self.exclude_roots && tip.is_root()) ||
+               bridged.contains(&(i as IndexType)) {
+                continue;
+            }
+
+            let mut nearest: Option<(usize, SqDist<S>)> = None;
+            for (j, node) in self.nodes.iter().enumerate() {
+                let own_tree = node.root == tip.root;
+                if own_tree && targeting.own_tree_separation.is_none() {
+                    continue;
+                }
+                let dist = self.sqdist_to_node(&tip.position, &node.position);
+                let nearer = match nearest {
+                    Some((_, d)) => dist < d,
+                    None => dist < targeting.attract_dist,
+                };
+                if !nearer {
+                    continue;
+                }
+                // the walk along the tree is only done for candidates.
+                let separation = targeting.own_tree_separation.unwrap_or(0);
+                if own_tree && self.tree_distance(NodeIdx::of(i), NodeIdx::of(j)) <= separation {
+                    continue;
+                }
+                nearest = Some((j, dist));
+            }
+
+            match nearest {
+                Some((j, dist)) if dist < targeting.connect_dist => {
+                    let bridge = (NodeIdx::of(i), NodeIdx::of(j));
+                    self.bridges.push(bridge);
+                    self.record(Event::BridgeAdded {
+                        tip: bridge.0,
+                        target: bridge.1,
+                    });
+                }
+                Some((j, _)) => {
+                    let target = self.nodes[j].position;
+                    let tip = &mut self.nodes[i];
+                    let towards = match self.manifold {
+                        Some(ref manifold) => manifold.log(&tip.position, &target),
+                        None => target - tip.position,
+                    };
+                    tip.growth = self.growth_model
+                        .accumulate(tip.growth, towards, targeting.strength);
+                    tip.growth_count += 1;
+                }
+                None => {}
+            }
+        }
+    }
+}
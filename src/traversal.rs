@@ -0,0 +1,86 @@
+//! Walking the trees: parents, children and subtrees. The children of
+//! every node are kept up to date as nodes are added, so none of these
+//! scan all nodes.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use std::iter;
+use super::{SpaceColonization, NodeIdx};
+use view::SpaceColonizationView;
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// The parent of ```node```, ```None``` for roots.
+    pub fn parent(&self, node: NodeIdx) -> Option<NodeIdx> {
+        self.view().parent(node)
+    }
+
+    /// The children of ```node```, in the order they were grown.
+    pub fn children<'a>(&'a self, node: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        self.view().children(node)
+    }
+
+    /// The parent of ```node```, its parent and so on up to the root.
+    pub fn ancestors<'a>(&'a self, node: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        self.view().ancestors(node)
+    }
+
+    /// ```node``` and all its descendants, every node before its children.
+    pub fn subtree<'a>(&'a self, node: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        self.view().subtree(node)
+    }
+
+    /// All nodes of the tree ```root``` belongs to, starting with its root.
+    pub fn nodes_of_root<'a>(&'a self, root: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        self.view().nodes_of_root(root)
+    }
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// See ```SpaceColonization::parent```.
+    pub fn parent(&self, node: NodeIdx) -> Option<NodeIdx> {
+        let parent = self.parents[node.0 as usize];
+        if parent == node.0 {
+            None
+        } else {
+            Some(NodeIdx(parent))
+        }
+    }
+
+    /// See ```SpaceColonization::children```.
+    pub fn children(&self, node: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        self.children[node.0 as usize].iter().map(|&child| NodeIdx(child))
+    }
+
+    /// See ```SpaceColonization::ancestors```.
+    pub fn ancestors(&self, node: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        let view = *self;
+        iter::successors(view.parent(node), move |&node| view.parent(node))
+    }
+
+    /// See ```SpaceColonization::subtree```.
+    pub fn subtree(&self, node: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        let children = self.children;
+        let mut stack = vec![node.0];
+        iter::from_fn(move || {
+            let node = stack.pop()?;
+            // reversed, so that the first child is visited first.
+            stack.extend(children[node as usize].iter().rev());
+            Some(NodeIdx(node))
+        })
+    }
+
+    /// See ```SpaceColonization::nodes_of_root```.
+    pub fn nodes_of_root(&self, root: NodeIdx) -> impl Iterator<Item = NodeIdx> + 'a {
+        self.subtree(self.nodes[root.0 as usize].root)
+    }
+}
@@ -0,0 +1,99 @@
+//! A bias of the growth direction, e.g. gravity for drooping
+//! willows or light for canopies reaching for the sun. The tropism is added
+//! to the attraction of every growing node before the direction is
+//! normalized, so its effect is relative to the number and strength of the
+//! attractors pulling the node. It doesn't make nodes grow on its own. Its
+//! weight can change over the iterations (see ```Schedule```).
+//!
+//! A tropism set on a root overrides the global one for its tree.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use schedule::Schedule;
+use super::{SpaceColonization, NodeIdx, scalar};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tropism<F, S = f32> {
+    /// The unit direction of the bias, e.g. down for gravity.
+    pub direction: F,
+
+    /// The bias relative to one attractor of strength 1, either a constant
+    /// or a ```Schedule``` over the iterations. Negative weights bias away
+    /// from ```direction```.
+    pub weight: Schedule<S>,
+}
+
+impl<F, S> Tropism<F, S>
+    where F: Vector<S> + Copy,
+          S: Scalar
+{
+    /// A tropism towards the normalized ```direction```.
+    pub fn new<W>(direction: F, weight: W) -> Tropism<F, S>
+        where W: Into<Schedule<S>>
+    {
+        Tropism {
+            direction: if direction.is_zero() {
+                direction
+            } else {
+                direction.normalize()
+            },
+            weight: weight.into(),
+        }
+    }
+
+    /// The tropism along ```coords```, which has one coordinate per
+    /// dimension.
+    pub(crate) fn from_coords(coords: &[f32], weight: Schedule<S>) -> Tropism<F, S> {
+        let mut direction: F = F::zero();
+        for (i, &c) in coords.iter().enumerate() {
+            if let Some(e) = F::basis_element(i) {
+                direction = direction + e * scalar(c);
+            }
+        }
+        Tropism::new(direction, weight)
+    }
+
+    /// The bias in ```iteration```.
+    fn bias(&self, iteration: u32) -> F {
+        self.direction * self.weight.value_at(iteration)
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Sets the tropism of all trees without their own. ```None``` removes
+    /// it.
+    pub fn set_tropism(&mut self, tropism: Option<Tropism<F, S>>) {
+        self.tropism = tropism;
+    }
+
+    pub fn tropism(&self) -> Option<&Tropism<F, S>> {
+        self.tropism.as_ref()
+    }
+
+    /// Overrides the global tropism for the tree of ```root```. ```None```
+    /// returns to the global tropism; a weight of 0 disables it for the
+    /// tree.
+    pub fn set_root_tropism(&mut self, root: NodeIdx, tropism: Option<Tropism<F, S>>) {
+        self.root_state_mut(root).tropism = tropism;
+    }
+
+    /// The tropism acting on the tree of ```node```.
+    pub fn tropism_of(&self, node: NodeIdx) -> Option<&Tropism<F, S>> {
+        self.root_state(node).tropism.as_ref().or(self.tropism.as_ref())
+    }
+
+    /// ```growth``` of a node of the tree of ```root``` with the tropism in
+    /// ```current_iteration```.
+    pub(crate) fn apply_tropism(&self, root: NodeIdx, growth: F, current_iteration: u32) -> F {
+        match self.tropism_of(root) {
+            Some(tropism) => growth + tropism.bias(current_iteration),
+            None => growth,
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Searching the parameters which minimize a user supplied loss, e.g. the
+//! morphometric distance to a reference structure (see
+//! ```compare_morphology```). Every candidate is run once per seed with
+//! ```batch::run_all``` and scored by its mean loss.
+//!
+//! The grid search is always available, the (separable) CMA-ES search needs
+//! the ```cmaes``` feature.
+
+use position::{Position, Vector};
+use std::cmp::Ordering;
+use std::f32;
+use std::fmt::Debug;
+use super::{SpaceColonization, SqDist};
+use batch::{self, Budget};
+use builder::SpaceColonizationBuilder;
+
+/// The tuned parameters.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Params {
+    pub move_dist: f32,
+
+    /// Not squared.
+    pub attract_dist: f32,
+
+    /// Not squared.
+    pub connect_dist: f32,
+
+    /// The magnitude of the jitter of the growth directions, see
+    /// ```set_jitter```.
+    pub jitter: f32,
+}
+
+impl Params {
+    /// A builder with the distances and the jitter set, the jitter drawn
+    /// with ```seed```, e.g. the seed of the run.
+    pub fn builder(&self, seed: u64) -> SpaceColonizationBuilder {
+        SpaceColonizationBuilder::new()
+            .move_dist(self.move_dist)
+            .attract_dist(SqDist::from_dist(self.attract_dist))
+            .connect_dist(SqDist::from_dist(self.connect_dist))
+            .jitter(self.jitter, seed)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Range {
+    pub fn new(min: f32, max: f32) -> Range {
+        assert!(min <= max);
+        Range { min, max }
+    }
+
+    /// A parameter which is not searched.
+    pub fn fixed(value: f32) -> Range {
+        Range::new(value, value)
+    }
+
+    /// ```t``` is clamped to [0, 1].
+    fn at(&self, t: f32) -> f32 {
+        self.min + (self.max - self.min) * t.clamp(0.0, 1.0)
+    }
+
+    /// ```steps``` evenly spaced values from ```min``` to ```max```.
+    fn grid(&self, steps: usize) -> Vec<f32> {
+        if steps <= 1 || self.min == self.max {
+            return vec![self.min];
+        }
+        (0..steps).map(|k| self.at(k as f32 / (steps - 1) as f32)).collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SearchSpace {
+    pub move_dist: Range,
+    pub attract_dist: Range,
+    pub connect_dist: Range,
+    pub jitter: Range,
+}
+
+impl SearchSpace {
+    /// The parameters at the normalized coordinates ```t```.
+    fn params_at(&self, t: &[f32]) -> Params {
+        Params {
+            move_dist: self.move_dist.at(t[0]),
+            attract_dist: self.attract_dist.at(t[1]),
+            connect_dist: self.connect_dist.at(t[2]),
+            jitter: self.jitter.at(t[3]),
+        }
+    }
+}
+
+/// How every candidate is evaluated.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    /// Each candidate is run once per seed.
+    pub seeds: Vec<u64>,
+    pub budget: Budget,
+
+    /// See ```batch::run_all```.
+    pub num_threads: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TuneResult {
+    pub best: Params,
+    pub best_loss: f32,
+
+    /// Every evaluated candidate with its mean loss, in evaluation order.
+    pub evaluations: Vec<(Params, f32)>,
+}
+
+impl TuneResult {
+    fn new() -> TuneResult {
+        TuneResult {
+            best: Params {
+                move_dist: 0.0,
+                attract_dist: 0.0,
+                connect_dist: 0.0,
+                jitter: 0.0,
+            },
+            best_loss: f32::INFINITY,
+            evaluations: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, params: Params, loss: f32) {
+        if self.evaluations.is_empty() || loss < self.best_loss {
+            self.best = params;
+            self.best_loss = loss;
+        }
+        self.evaluations.push((params, loss));
+    }
+}
+
+/// The mean loss of every candidate over all seeds. A run which panicked
+/// counts as an infinite loss.
+fn evaluate<S, L, P, F, I>(candidates: &[Params],
+                           evaluation: &Evaluation,
+                           setup: &S,
+                           loss: &L)
+                           -> Vec<f32>
+    where S: Fn(&Params, u64) -> SpaceColonization<P, F, I> + Sync,
+          L: Fn(&Params, SpaceColonization<P, F, I>) -> f32 + Sync,
+          P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    let num_seeds = evaluation.seeds.len();
+    assert!(num_seeds > 0);
+    let mut configs = Vec::with_capacity(candidates.len() * num_seeds);
+    let mut seeds = Vec::with_capacity(candidates.len() * num_seeds);
+    for params in candidates.iter() {
+        for &seed in evaluation.seeds.iter() {
+            configs.push(*params);
+            seeds.push(seed);
+        }
+    }
+
+    let outputs = batch::run_all(&configs,
+                                 &seeds,
+                                 evaluation.budget,
+                                 evaluation.num_threads,
+                                 |params, seed| setup(params, seed),
+                                 |params, sc| loss(params, sc));
+    let mut losses = vec![0.0; candidates.len()];
+    for (run, output) in outputs.into_iter().enumerate() {
+        let l = match output {
+            Ok(output) => output.result,
+            Err(_) => f32::INFINITY,
+        };
+        losses[run / num_seeds] += l / num_seeds as f32;
+    }
+    losses
+}
+
+/// Evaluates every combination of ```steps``` values per parameter (fixed
+/// ranges contribute a single value).
+pub fn grid_search<S, L, P, F, I>(space: &SearchSpace,
+                                  steps: usize,
+                                  evaluation: &Evaluation,
+                                  setup: S,
+                                  loss: L)
+                                  -> TuneResult
+    where S: Fn(&Params, u64) -> SpaceColonization<P, F, I> + Sync,
+          L: Fn(&Params, SpaceColonization<P, F, I>) -> f32 + Sync,
+          P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    let mut candidates = Vec::new();
+    for &move_dist in space.move_dist.grid(steps).iter() {
+        for &attract_dist in space.attract_dist.grid(steps).iter() {
+            for &connect_dist in space.connect_dist.grid(steps).iter() {
+                for &jitter in space.jitter.grid(steps).iter() {
+                    candidates.push(Params {
+                        move_dist,
+                        attract_dist,
+                        connect_dist,
+                        jitter,
+                    });
+                }
+            }
+        }
+    }
+
+    let losses = evaluate(&candidates, evaluation, &setup, &loss);
+    let mut result = TuneResult::new();
+    for (params, l) in candidates.into_iter().zip(losses) {
+        result.push(params, l);
+    }
+    result
+}
+
+#[cfg(feature = "cmaes")]
+#[derive(Debug, Copy, Clone)]
+pub struct CmaEs {
+    pub generations: usize,
+
+    /// Candidates per generation, at least 2.
+    pub population: usize,
+
+    /// The initial step size, relative to the ranges.
+    pub sigma: f32,
+
+    pub seed: u64,
+}
+
+#[cfg(feature = "cmaes")]
+impl Default for CmaEs {
+    fn default() -> CmaEs {
+        CmaEs {
+            generations: 20,
+            population: 8,
+            sigma: 0.3,
+            seed: 0,
+        }
+    }
+}
+
+/// Separable CMA-ES (diagonal covariance) in the ranges normalized to
+/// [0, 1], starting from their centers. Candidates outside are clamped.
+#[cfg(feature = "cmaes")]
+pub fn cmaes_search<S, L, P, F, I>(space: &SearchSpace,
+                                   options: CmaEs,
+                                   evaluation: &Evaluation,
+                                   setup: S,
+                                   loss: L)
+                                   -> TuneResult
+    where S: Fn(&Params, u64) -> SpaceColonization<P, F, I> + Sync,
+          L: Fn(&Params, SpaceColonization<P, F, I>) -> f32 + Sync,
+          P: Position<f32, F> + Debug,
+          F: Vector<f32> + Copy + Debug,
+          I: Copy + Default + Debug
+{
+    use rand::{Rng, SeedableRng};
+    use rand::distributions::normal::StandardNormal;
+    use rand::isaac::Isaac64Rng;
+
+    const N: usize = 4;
+    let n = N as f32;
+    let lambda = options.population.max(2);
+    let mu = lambda / 2;
+    let mut weights: Vec<f32> = (0..mu)
+                                    .map(|i| (mu as f32 + 0.5).ln() - (i as f32 + 1.0).ln())
+                                    .collect();
+    let sum: f32 = weights.iter().sum();
+    for w in weights.iter_mut() {
+        *w /= sum;
+    }
+    let mu_eff = 1.0 / weights.iter().map(|w| w * w).sum::<f32>();
+
+    let c_sigma = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+    let d_sigma = 1.0 + 2.0 * (((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + c_sigma;
+    let c_c = (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n);
+    // the learning rates of the separable variant are larger.
+    let c_1 = (2.0 / ((n + 1.3).powi(2) + mu_eff) * (n + 2.0) / 3.0).min(1.0);
+    let c_mu = (2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff) * (n + 2.0) /
+                3.0)
+                   .min(1.0 - c_1);
+    let chi_n = n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n));
+
+    let mut rng = Isaac64Rng::from_seed(&[options.seed]);
+    let mut mean = [0.5f32; N];
+    let mut sigma = options.sigma;
+    let mut c = [1.0f32; N];
+    let mut p_sigma = [0.0f32; N];
+    let mut p_c = [0.0f32; N];
+    let mut result = TuneResult::new();
+
+    for generation in 0..options.generations {
+        let mut xs = Vec::with_capacity(lambda);
+        let mut ys = Vec::with_capacity(lambda);
+        for _ in 0..lambda {
+            let mut x = [0.0; N];
+            let mut y = [0.0; N];
+            for d in 0..N {
+                let StandardNormal(z) = rng.gen::<StandardNormal>();
+                x[d] = (mean[d] + sigma * c[d].sqrt() * z as f32).clamp(0.0, 1.0);
+                y[d] = (x[d] - mean[d]) / sigma;
+            }
+            xs.push(x);
+            ys.push(y);
+        }
+
+        let candidates: Vec<Params> = xs.iter().map(|x| space.params_at(x)).collect();
+        let losses = evaluate(&candidates, evaluation, &setup, &loss);
+        for (params, &l) in candidates.iter().zip(losses.iter()) {
+            result.push(*params, l);
+        }
+
+        let mut order: Vec<usize> = (0..lambda).collect();
+        order.sort_by(|&a, &b| losses[a].partial_cmp(&losses[b]).unwrap_or(Ordering::Equal));
+
+        let mut y_w = [0.0; N];
+        for (k, &i) in order.iter().take(mu).enumerate() {
+            for d in 0..N {
+                y_w[d] += weights[k] * ys[i][d];
+            }
+        }
+
+        let mut p_sigma_norm = 0.0;
+        for d in 0..N {
+            mean[d] += sigma * y_w[d];
+            p_sigma[d] = (1.0 - c_sigma) * p_sigma[d] +
+                         (c_sigma * (2.0 - c_sigma) * mu_eff).sqrt() * y_w[d] / c[d].sqrt();
+            p_sigma_norm += p_sigma[d] * p_sigma[d];
+        }
+        let p_sigma_norm = p_sigma_norm.sqrt();
+        let decay = 1.0 - (1.0 - c_sigma).powi(2 * (generation as i32 + 1));
+        let h_sigma = if p_sigma_norm / decay.sqrt() < (1.4 + 2.0 / (n + 1.0)) * chi_n {
+            1.0
+        } else {
+            0.0
+        };
+
+        for d in 0..N {
+            p_c[d] = (1.0 - c_c) * p_c[d] + h_sigma * (c_c * (2.0 - c_c) * mu_eff).sqrt() * y_w[d];
+            let rank_mu: f32 = order.iter()
+                                    .take(mu)
+                                    .enumerate()
+                                    .map(|(k, &i)| weights[k] * ys[i][d] * ys[i][d])
+                                    .sum();
+            c[d] = (1.0 - c_1 - c_mu) * c[d] +
+                   c_1 * (p_c[d] * p_c[d] + (1.0 - h_sigma) * c_c * (2.0 - c_c) * c[d]) +
+                   c_mu * rank_mu;
+        }
+        sigma *= ((c_sigma / d_sigma) * (p_sigma_norm / chi_n - 1.0)).exp();
+    }
+    result
+}
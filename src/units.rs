@@ -0,0 +1,115 @@
+//! The physical size of a scene unit. Distances are given in the units of
+//! the positions, so parameters tuned for a scene of one size misbehave in
+//! a scene of another size. Recording how many meters a unit is lets a
+//! simulation be converted to other units as a whole, e.g. when a tree
+//! tuned in meters is placed into a scene modelled in centimeters.
+//!
+//! Closures and user types (fields, flow fields, attractor motion, metrics,
+//! manifolds, lattices, obstacles and the growth domain) are not rescaled.
+
+use position::{Position, Vector, Coords, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, SqDist, scalar};
+
+fn scale_sqdist<S: Scalar>(d: SqDist<S>, factor: S) -> SqDist<S> {
+    SqDist(d.0 * factor * factor)
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// Meters per unit, 1 unless changed by ```set_world_scale```.
+    pub fn world_scale(&self) -> f32 {
+        self.world_scale
+    }
+
+    /// Switches to units of ```meters_per_unit``` meters: all positions
+    /// (about the origin) and all distance-like parameters, including
+    /// those of existing attractors, the step size and the biomass
+    /// budgets, are rescaled so that the structure keeps its physical size.
+    pub fn set_world_scale(&mut self, meters_per_unit: f32) {
+        assert!(meters_per_unit > 0.0);
+        let factor = self.world_scale / meters_per_unit;
+        self.world_scale = meters_per_unit;
+        if factor == 1.0 {
+            return;
+        }
+
+        let origin: P = Coords::origin();
+        let s: S = scalar(factor);
+        let scale = |p: &P| origin + (*p - origin) * s;
+
+        for node in self.nodes.iter_mut() {
+            node.position = scale(&node.position);
+        }
+        for (p, node) in self.positions.iter_mut().zip(self.nodes.iter()) {
+            *p = node.position;
+        }
+        for (ap, meta) in self.attractors.iter_mut().zip(self.attractor_meta.iter_mut()) {
+            ap.position = scale(&ap.position);
+            meta.velocity = meta.velocity.map(|v| v * s);
+        }
+        for cluster in self.clusters.iter_mut() {
+            cluster.centroid = scale(&cluster.centroid);
+            for p in cluster.members.iter_mut() {
+                *p = scale(p);
+            }
+        }
+        if let Some(ref mut heatmap) = self.heatmap {
+            heatmap.grid.origin = scale(&heatmap.grid.origin);
+            heatmap.grid.spacing *= factor;
+        }
+        self.scale_distances(factor);
+        self.clear_history();
+    }
+
+    /// Scales the distance-like parameters, but not the positions (nor the
+    /// heatmap grid), by ```factor``` and rebuilds the spatial index.
+    pub(crate) fn scale_distances(&mut self, factor: f32) {
+        let s: S = scalar(factor);
+        for root in self.roots.iter_mut() {
+            root.biomass = root.biomass * s;
+        }
+        for ap in self.attractors.iter_mut() {
+            ap.attract_dist = scale_sqdist(ap.attract_dist, s);
+            ap.connect_dist = scale_sqdist(ap.connect_dist, s);
+        }
+        if let Some(ref mut options) = self.cluster_options {
+            options.cell_size *= factor;
+            options.uncluster_dist *= factor;
+        }
+
+        self.default_attract_dist = scale_sqdist(self.default_attract_dist, s);
+        self.default_connect_dist = scale_sqdist(self.default_connect_dist, s);
+        for keyframe in self.move_dist.keyframes.iter_mut() {
+            keyframe.1 = keyframe.1 * s;
+        }
+        if let Some(ref mut biomass) = self.biomass {
+            biomass.initial = biomass.initial * s;
+            biomass.per_connection = biomass.per_connection * s;
+        }
+        if let Some(ref mut targeting) = self.node_targeting {
+            targeting.attract_dist = scale_sqdist(targeting.attract_dist, s);
+            targeting.connect_dist = scale_sqdist(targeting.connect_dist, s);
+        }
+        if let Some(ref mut anastomosis) = self.anastomosis {
+            anastomosis.distance = anastomosis.distance * s;
+        }
+        if let Some(ref mut distance) = self.merge_distance {
+            *distance = *distance * s;
+        }
+        if let Some(ref mut oscillation) = self.oscillation {
+            oscillation.detection.radius = oscillation.detection.radius * s;
+        }
+        if let Some(ref mut injection) = self.frontier_injection {
+            injection.distance = injection.distance * s;
+            injection.attract_dist = scale_sqdist(injection.attract_dist, s);
+            injection.connect_dist = scale_sqdist(injection.connect_dist, s);
+        }
+        let cell_size = self.spatial_index_cell_size();
+        self.set_spatial_index(cell_size.map(|cell_size| cell_size * factor));
+    }
+}
@@ -0,0 +1,229 @@
+//! A read-only view of the grown structure, see
+//! ```SpaceColonization::view```.
+//!
+//! The simulation holds closures (fields, metrics, attractor motion) and a
+//! channel sender, so it can't be shared between threads. The view only
+//! borrows the nodes and attractors and is ```Send``` and ```Sync``` for
+//! ```Send + Sync``` points, vectors and information. Analysis and export
+//! can thus run on worker threads:
+//!
+//! ```ignore
+//! let view = sc.view();
+//! thread::scope(|s| {
+//!     s.spawn(move || view.write_obj(&mut obj_file));
+//!     s.spawn(move || view.compute_radii(0.01, 2.0));
+//! });
+//! ```
+//!
+//! The queries of ```SpaceColonization``` which only read the structure
+//! are implemented on the view, so both give the same results.
+
+use position::{Position, Vector, Scalar};
+use std::fmt::Debug;
+use super::{SpaceColonization, Node, NodeIdx, Attractor, AttractorMeta, IndexType};
+use roots::RootState;
+
+pub struct SpaceColonizationView<'a, P, F, I, S = f32>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Copy + Debug + 'a,
+          S: 'a
+{
+    pub(crate) nodes: &'a [Node<P, F, I>],
+    pub(crate) positions: &'a [P],
+    pub(crate) parents: &'a [IndexType],
+    pub(crate) children: &'a [Vec<IndexType>],
+    pub(crate) roots: &'a [RootState<F, I, S>],
+    pub(crate) attractors: &'a [Attractor<P, I, S>],
+    pub(crate) attractor_meta: &'a [AttractorMeta<F>],
+    pub(crate) bridges: &'a [(NodeIdx, NodeIdx)],
+    pub(crate) next_iteration: u32,
+}
+
+impl<'a, P, F, I, S> Clone for SpaceColonizationView<'a, P, F, I, S>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Copy + Debug + 'a,
+          S: 'a
+{
+    fn clone(&self) -> SpaceColonizationView<'a, P, F, I, S> {
+        *self
+    }
+}
+
+impl<'a, P, F, I, S> Copy for SpaceColonizationView<'a, P, F, I, S>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Copy + Debug + 'a,
+          S: 'a
+{
+}
+
+impl<'a, P, F, I, S> SpaceColonizationView<'a, P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// The positions of all nodes, indexed by ```NodeIdx```.
+    pub fn positions(&self) -> &'a [P] {
+        self.positions
+    }
+
+    /// See ```SpaceColonization::parents```.
+    pub fn parents(&self) -> &'a [IndexType] {
+        self.parents
+    }
+
+    /// The (tip, target) pairs of ```SpaceColonization::bridges```.
+    pub fn bridges(&self) -> &'a [(NodeIdx, NodeIdx)] {
+        self.bridges
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_attractors(&self) -> usize {
+        self.attractors.len()
+    }
+
+    /// The iteration the simulation will perform next.
+    pub fn next_iteration(&self) -> u32 {
+        self.next_iteration
+    }
+
+    pub(crate) fn get_node(&self, node_idx: NodeIdx) -> Option<&'a Node<P, F, I>> {
+        self.nodes.get(node_idx.0 as usize)
+    }
+
+    pub fn visit_attractor_points<V>(&self, visitor: &mut V)
+        where V: FnMut(&P)
+    {
+        for attractor in self.attractors.iter() {
+            visitor(&attractor.position)
+        }
+    }
+
+    pub fn visit_attractors<V>(&self, visitor: &mut V)
+        where V: FnMut(&Attractor<P, I, S>)
+    {
+        for attractor in self.attractors.iter() {
+            visitor(attractor)
+        }
+    }
+
+    /// See ```SpaceColonization::disabled_attractors```.
+    pub fn disabled_attractors(&self) -> impl Iterator<Item = (u64, u32)> + 'a {
+        let next_iteration = self.next_iteration;
+        self.attractors
+            .iter()
+            .zip(self.attractor_meta.iter())
+            .filter(move |&(ap, _)| {
+                let from = ap.active_from_iteration;
+                from > next_iteration && !ap.is_expired_in(from)
+            })
+            .map(|(ap, meta)| (meta.id, ap.active_from_iteration))
+    }
+
+    pub fn visit_node_segments<V>(&self, visitor: &mut V)
+        where V: FnMut(&P, &P)
+    {
+        for node in self.nodes.iter() {
+            if !node.is_root() {
+                visitor(&node.position,
+                        &self.get_node(node.parent).unwrap().position);
+            }
+        }
+    }
+
+    /// See ```SpaceColonization::visit_bridge_segments```.
+    pub fn visit_bridge_segments<V>(&self, visitor: &mut V)
+        where V: FnMut(&P, &P)
+    {
+        for &(tip, target) in self.bridges.iter() {
+            visitor(&self.nodes[tip.0 as usize].position,
+                    &self.nodes[target.0 as usize].position);
+        }
+    }
+
+    /// See ```SpaceColonization::visit_nodes_with_info_and_root```.
+    pub fn visit_nodes_with_info_and_root<V>(&self, visitor: &mut V)
+        where V: FnMut(&Node<P, F, I>, &Node<P, F, I>)
+    {
+        for node in self.nodes.iter() {
+            if node.assigned_information.is_some() && !node.is_root() {
+                visitor(node, self.get_node(node.root).unwrap());
+            }
+        }
+    }
+
+    pub fn visit_root_nodes<V>(&self, visitor: &mut V)
+        where V: FnMut(&Node<P, F, I>)
+    {
+        for node in self.nodes.iter() {
+            if node.is_root() {
+                visitor(node);
+            }
+        }
+    }
+
+    /// See ```SpaceColonization::nodes_with_info```.
+    pub fn nodes_with_info(&self) -> impl Iterator<Item = (NodeIdx, &'a Node<P, F, I>)> + 'a {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|&(_, node)| node.assigned_information.is_some())
+            .map(|(i, node)| (NodeIdx::of(i), node))
+    }
+
+    /// See ```SpaceColonization::nodes_where_info```.
+    pub fn nodes_where_info<Q>(&self,
+                               predicate: Q)
+                               -> impl Iterator<Item = (NodeIdx, &'a Node<P, F, I>)> + 'a
+        where Q: Fn(&I) -> bool + 'a
+    {
+        self.nodes_with_info().filter(move |&(_, node)| {
+            match node.assigned_information {
+                Some(ref info) => predicate(info),
+                None => false,
+            }
+        })
+    }
+}
+
+impl<P, F, I, S> SpaceColonization<P, F, I, S>
+    where P: Position<S, F> + Debug,
+          F: Vector<S> + Copy + Debug,
+          I: Copy + Default + Debug,
+          S: Scalar + Debug
+{
+    /// A read-only view of the current structure, which can be shared with
+    /// other threads while the simulation is borrowed.
+    pub fn view<'a>(&'a self) -> SpaceColonizationView<'a, P, F, I, S> {
+        SpaceColonizationView {
+            nodes: &self.nodes,
+            positions: &self.positions,
+            parents: &self.parents,
+            children: &self.children,
+            roots: &self.roots,
+            attractors: &self.attractors,
+            attractor_meta: &self.attractor_meta,
+            bridges: &self.bridges,
+            next_iteration: self.next_iteration,
+        }
+    }
+}
+
+/// Fails to compile if the view stops being ```Send``` and ```Sync``` for
+/// ```Send + Sync``` points, vectors and information.
+#[allow(dead_code)]
+fn assert_send_sync<'a, P, F, I, S>()
+    where P: Debug + Send + Sync + 'a,
+          F: Debug + Send + Sync + 'a,
+          I: Copy + Debug + Send + Sync + 'a,
+          S: Send + Sync + 'a
+{
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<SpaceColonizationView<'a, P, F, I, S>>();
+}
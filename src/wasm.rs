@@ -0,0 +1,120 @@
+//! A simplified, JavaScript friendly API for use via ```wasm-pack```.
+//!
+//! Positions are passed in and out as flat ```Float32Array```s of
+//! ```[x, y, z, x, y, z, ...]```. For 2D use ```z = 0```.
+
+use na::{Point3, Vector3};
+use wasm_bindgen::prelude::*;
+use super::{SpaceColonization, SqDist};
+
+/// Rejects arrays whose length is not a multiple of three rather than
+/// dropping the trailing values.
+fn points(coords: &[f32]) -> Result<Vec<Point3<f32>>, JsValue> {
+    if coords.len() % 3 != 0 {
+        let message = format!("expected three coordinates per point, got {} values",
+                              coords.len());
+        return Err(JsValue::from_str(&message));
+    }
+    Ok(coords.chunks(3).map(|c| Point3::new(c[0], c[1], c[2])).collect())
+}
+
+fn push_point(out: &mut Vec<f32>, p: &Point3<f32>) {
+    out.push(p.x);
+    out.push(p.y);
+    out.push(p.z);
+}
+
+#[wasm_bindgen]
+pub struct Colonization {
+    inner: SpaceColonization<Point3<f32>, Vector3<f32>, ()>,
+}
+
+#[wasm_bindgen]
+impl Colonization {
+    /// Distances are plain (not squared) distances.
+    #[wasm_bindgen(constructor)]
+    pub fn new(attract_dist: f32,
+               connect_dist: f32,
+               move_dist: f32,
+               max_length: u32,
+               max_branches: u32)
+               -> Colonization {
+        Colonization {
+            inner: SpaceColonization::new(SqDist::from_dist(attract_dist),
+                                          SqDist::from_dist(connect_dist),
+                                          max_length,
+                                          max_branches,
+                                          move_dist),
+        }
+    }
+
+    /// Throws if the length of ```coords``` is not a multiple of three or
+    /// the node limit is reached.
+    #[wasm_bindgen(js_name = addRoots)]
+    pub fn add_roots(&mut self, coords: &[f32]) -> Result<(), JsValue> {
+        for p in points(coords)? {
+            self.inner
+                .try_add_root_node(p, None)
+                .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Throws if the length of ```coords``` is not a multiple of three.
+    #[wasm_bindgen(js_name = addAttractors)]
+    pub fn add_attractors(&mut self, coords: &[f32]) -> Result<(), JsValue> {
+        self.inner.add_default_attractors_from(&points(coords)?);
+        Ok(())
+    }
+
+    /// Performs one iteration. Returns the number of new nodes.
+    pub fn step(&mut self) -> u32 {
+        self.inner.next().unwrap_or(0) as u32
+    }
+
+    #[wasm_bindgen(js_name = numNodes)]
+    pub fn num_nodes(&self) -> u32 {
+        self.inner.num_nodes() as u32
+    }
+
+    #[wasm_bindgen(js_name = numAttractors)]
+    pub fn num_attractors(&self) -> u32 {
+        self.inner.num_attractors() as u32
+    }
+
+    /// Six values per segment: the node followed by its parent.
+    #[wasm_bindgen(js_name = getSegments)]
+    pub fn get_segments(&self) -> Vec<f32> {
+        let mut out = Vec::new();
+        self.inner.visit_node_segments(&mut |a, b| {
+            push_point(&mut out, a);
+            push_point(&mut out, b);
+        });
+        out
+    }
+
+    /// Three values per node, indexed by node index.
+    #[wasm_bindgen(js_name = getPositions)]
+    pub fn get_positions(&self) -> Vec<f32> {
+        let mut out = Vec::with_capacity(3 * self.inner.num_nodes());
+        for p in self.inner.positions().iter() {
+            push_point(&mut out, p);
+        }
+        out
+    }
+
+    /// The parent index of every node. Roots are their own parent.
+    #[wasm_bindgen(js_name = getParents)]
+    // ```IndexType``` is u64 with the ```u64-index``` feature.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn get_parents(&self) -> Vec<u32> {
+        self.inner.parents().iter().map(|&p| p as u32).collect()
+    }
+
+    #[wasm_bindgen(js_name = getAttractors)]
+    pub fn get_attractors(&self) -> Vec<f32> {
+        let mut out = Vec::new();
+        self.inner.visit_attractor_points(&mut |p| push_point(&mut out, p));
+        out
+    }
+}